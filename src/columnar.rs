@@ -0,0 +1,298 @@
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BinaryBuilder, BooleanBuilder, Date32Builder, Decimal128Builder, Float64Builder, Int32Builder,
+    Int64Builder, StringBuilder, Time64MicrosecondBuilder, TimestampMicrosecondBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions};
+use arrow_schema::{DataType, Field as ArrowField, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Timelike};
+
+use crate::error::AppError;
+use crate::rows::Column;
+use crate::value::Value;
+
+// // ----- COLUMNAR BATCHES ----- // //
+// Turns generated rows into Arrow record batches for the Parquet and Arrow IPC exports.
+// Every column gets the Arrow type matching its postgres column type, so numbers, dates
+// and timestamps keep their logical type instead of becoming text. Rows are gathered a
+// batch at a time and handed to the writer, which never holds more than a row group.
+
+// Rows gathered before they are handed to the writer as one record batch
+pub const BATCH_ROWS: usize = 8192;
+
+// Arrow type storing a postgres column type, text for anything without a closer match
+pub fn arrow_type(sql_type: &str) -> DataType {
+    let sql_type = sql_type.trim().to_uppercase();
+    match sql_type.as_str() {
+        "INT" | "INTEGER" | "INT4" | "SERIAL" => DataType::Int32,
+        "BIGINT" | "INT8" | "BIGSERIAL" => DataType::Int64,
+        "FLOAT" | "FLOAT8" | "DOUBLE PRECISION" => DataType::Float64,
+        "BOOLEAN" | "BOOL" => DataType::Boolean,
+        "DATE" => DataType::Date32,
+        "TIME" => DataType::Time64(TimeUnit::Microsecond),
+        "TIMESTAMP" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        "BYTEA" => DataType::Binary,
+        _ => decimal_type(&sql_type).unwrap_or(DataType::Utf8),
+    }
+}
+
+// DECIMAL(precision,scale) or NUMERIC(precision,scale)
+fn decimal_type(sql_type: &str) -> Option<DataType> {
+    let args = sql_type
+        .strip_prefix("DECIMAL")
+        .or_else(|| sql_type.strip_prefix("NUMERIC"))?
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    let (precision, scale) = args.split_once(',')?;
+    let precision: u8 = precision.trim().parse().ok()?;
+    let scale: i8 = scale.trim().parse().ok()?;
+    (1..=38)
+        .contains(&precision)
+        .then_some(DataType::Decimal128(precision, scale))
+}
+
+// Arrow schema of the rows, every column is nullable like the generated values
+pub fn arrow_schema(columns: &[Column]) -> SchemaRef {
+    let fields: Vec<ArrowField> = columns
+        .iter()
+        .map(|c| ArrowField::new(c.name.clone(), arrow_type(&c.sql_type), true))
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+// Collects rows column by column until they are taken as a record batch
+pub struct BatchBuilder {
+    schema: SchemaRef,
+    columns: Vec<ColumnBuilder>,
+    rows: usize,
+}
+
+impl BatchBuilder {
+    pub fn new(schema: SchemaRef) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| ColumnBuilder::new(f.data_type()))
+            .collect();
+        BatchBuilder {
+            schema,
+            columns,
+            rows: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn push(&mut self, row: &[Value]) -> Result<(), AppError> {
+        for ((builder, value), field) in self.columns.iter_mut().zip(row).zip(self.schema.fields())
+        {
+            builder.append(value).map_err(|()| {
+                AppError::internal(format!(
+                    "Can't write {:?} to the {} column -> {} <-",
+                    value,
+                    field.data_type(),
+                    field.name()
+                ))
+            })?;
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    // The gathered rows as a record batch, the builder starts over empty
+    pub fn take(&mut self) -> Result<RecordBatch, AppError> {
+        let arrays: Vec<ArrayRef> = self.columns.iter_mut().map(ColumnBuilder::finish).collect();
+        // Tables without columns still have a row count
+        let options = RecordBatchOptions::new().with_row_count(Some(self.rows));
+        self.rows = 0;
+        RecordBatch::try_new_with_options(self.schema.clone(), arrays, &options)
+            .map_err(|err| AppError::internal(format!("Can't build a record batch: {}", err)))
+    }
+}
+
+enum ColumnBuilder {
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Boolean(BooleanBuilder),
+    Date32(Date32Builder),
+    Time64(Time64MicrosecondBuilder),
+    Timestamp(TimestampMicrosecondBuilder),
+    Decimal128(Decimal128Builder, u32),
+    Binary(BinaryBuilder),
+    Utf8(StringBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Int32 => ColumnBuilder::Int32(Int32Builder::new()),
+            DataType::Int64 => ColumnBuilder::Int64(Int64Builder::new()),
+            DataType::Float64 => ColumnBuilder::Float64(Float64Builder::new()),
+            DataType::Boolean => ColumnBuilder::Boolean(BooleanBuilder::new()),
+            DataType::Date32 => ColumnBuilder::Date32(Date32Builder::new()),
+            DataType::Time64(_) => ColumnBuilder::Time64(Time64MicrosecondBuilder::new()),
+            DataType::Timestamp(_, _) => {
+                ColumnBuilder::Timestamp(TimestampMicrosecondBuilder::new())
+            }
+            &DataType::Decimal128(precision, scale) => ColumnBuilder::Decimal128(
+                Decimal128Builder::new()
+                    .with_precision_and_scale(precision, scale)
+                    .expect("arrow_type only returns valid decimal types"),
+                scale.max(0) as u32,
+            ),
+            DataType::Binary => ColumnBuilder::Binary(BinaryBuilder::new()),
+            _ => ColumnBuilder::Utf8(StringBuilder::new()),
+        }
+    }
+
+    // Err when the value doesn't fit the column type
+    fn append(&mut self, value: &Value) -> Result<(), ()> {
+        match (self, value) {
+            (ColumnBuilder::Int32(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Int32(b), Value::Int(v)) => {
+                b.append_value(i32::try_from(*v).map_err(|_| ())?)
+            }
+            (ColumnBuilder::Int64(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Int64(b), Value::Int(v)) => b.append_value(*v),
+            (ColumnBuilder::Float64(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Float64(b), Value::Float(v)) => b.append_value(*v),
+            (ColumnBuilder::Float64(b), Value::Int(v)) => b.append_value(*v as f64),
+            (ColumnBuilder::Boolean(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Boolean(b), Value::Bool(v)) => b.append_value(*v),
+            (ColumnBuilder::Date32(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Date32(b), Value::Date(v)) => {
+                b.append_value((*v - DateTime::UNIX_EPOCH.date_naive()).num_days() as i32)
+            }
+            (ColumnBuilder::Time64(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Time64(b), Value::Time(v)) => b.append_value(
+                v.num_seconds_from_midnight() as i64 * 1_000_000 + v.nanosecond() as i64 / 1_000,
+            ),
+            (ColumnBuilder::Timestamp(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Timestamp(b), Value::Timestamp(v)) => {
+                b.append_value(v.and_utc().timestamp_micros())
+            }
+            (ColumnBuilder::Decimal128(b, _), Value::Null) => b.append_null(),
+            (ColumnBuilder::Decimal128(b, scale), Value::Decimal(v)) => {
+                let mut v = *v;
+                v.rescale(*scale);
+                b.append_value(v.mantissa())
+            }
+            (ColumnBuilder::Binary(b), Value::Null) => b.append_null(),
+            (ColumnBuilder::Binary(b), Value::Bytes(v)) => b.append_value(v),
+            // Text columns take any value in its postgres text form
+            (ColumnBuilder::Utf8(b), value) => b.append_option(value.to_sql_text()),
+            _ => return Err(()),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Int32(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Boolean(b) => Arc::new(b.finish()),
+            ColumnBuilder::Date32(b) => Arc::new(b.finish()),
+            ColumnBuilder::Time64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Timestamp(b) => Arc::new(b.finish()),
+            ColumnBuilder::Decimal128(b, _) => Arc::new(b.finish()),
+            ColumnBuilder::Binary(b) => Arc::new(b.finish()),
+            ColumnBuilder::Utf8(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Decimal128Type, Int32Type, TimestampMicrosecondType};
+    use arrow_array::Array;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    fn schema(sql_types: &[&str]) -> SchemaRef {
+        let fields: Vec<ArrowField> = sql_types
+            .iter()
+            .enumerate()
+            .map(|(i, sql_type)| ArrowField::new(format!("c{}", i), arrow_type(sql_type), true))
+            .collect();
+        Arc::new(Schema::new(fields))
+    }
+
+    #[test]
+    fn columns_keep_their_logical_type() {
+        assert_eq!(arrow_type("serial"), DataType::Int32);
+        assert_eq!(arrow_type(" BIGINT "), DataType::Int64);
+        assert_eq!(arrow_type("NUMERIC(10, 2)"), DataType::Decimal128(10, 2));
+        assert_eq!(arrow_type("DECIMAL(40,2)"), DataType::Utf8);
+        assert_eq!(arrow_type("NUMERIC"), DataType::Utf8);
+        assert_eq!(arrow_type("VARCHAR(20)"), DataType::Utf8);
+        assert_eq!(
+            arrow_type("TIMESTAMP"),
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        );
+    }
+
+    #[test]
+    fn batches_hold_the_pushed_rows() {
+        let mut builder =
+            BatchBuilder::new(schema(&["INTEGER", "NUMERIC(6,2)", "TIMESTAMP", "TEXT"]));
+        let at = NaiveDate::from_ymd_opt(1970, 1, 2)
+            .unwrap()
+            .and_hms_opt(0, 0, 1)
+            .unwrap();
+        builder
+            .push(&[
+                Value::Int(-7),
+                Value::Decimal(Decimal::new(123, 1)),
+                Value::Timestamp(at),
+                Value::Int(5),
+            ])
+            .unwrap();
+        builder
+            .push(&[Value::Null, Value::Null, Value::Null, Value::Null])
+            .unwrap();
+        assert_eq!(builder.len(), 2);
+
+        let batch = builder.take().unwrap();
+        assert!(builder.is_empty());
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(0).as_primitive::<Int32Type>().value(0), -7);
+        assert_eq!(
+            batch.column(1).as_primitive::<Decimal128Type>().value(0),
+            1230
+        );
+        assert_eq!(
+            batch
+                .column(2)
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(0),
+            86_401_000_000
+        );
+        assert_eq!(batch.column(3).as_string::<i32>().value(0), "5");
+        assert!((0..4).all(|i| batch.column(i).is_null(1)));
+    }
+
+    #[test]
+    fn values_must_fit_their_column() {
+        let mut builder = BatchBuilder::new(schema(&["INTEGER"]));
+        assert!(builder.push(&[Value::Int(1 << 40)]).is_err());
+        assert!(builder.push(&[Value::Text("1".to_string())]).is_err());
+
+        // Tables without columns still count their rows
+        let mut builder = BatchBuilder::new(schema(&[]));
+        builder.push(&[]).unwrap();
+        assert_eq!(builder.take().unwrap().num_rows(), 1);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use sqlx::mysql::{MySqlConnectOptions, MySqlSslMode};
+use sqlx::postgres::{PgConnectOptions, PgSslMode};
+use sqlx::sqlite::SqliteConnectOptions;
+
+use crate::copy::LoadMode;
+use crate::dialect::Dialect;
+
+// // ----- CONFIGURATION ----- // //
+// Settings are resolved in three layers, each one overriding the previous:
+//   1. the built in defaults (a local postgres + mongo, same as the old hardcoded values)
+//   2. an optional TOML config file (`--config` / DATASYNTH_CONFIG)
+//   3. command line flags, which can also come from the environment or a `.env` file
+//
+// Example config file:
+//
+//   bind = "0.0.0.0:3000"
+//   default_target = "dev"
+//   insert_batch_size = 5000
+//   load_mode = "copy_binary"
+//   defer_indexes = false
+//   max_unique_rows = 50000000
+//
+//   [mongo]
+//   uri = "mongodb://localhost:27017/"
+//
+//   [targets.dev]
+//   host = "localhost"
+//   password = "password"
+//
+//   [targets.staging]
+//   host = "staging-db.internal"
+//   port = 5433
+//   user = "seeder"
+//   password = "secret"
+//   ssl_mode = "require"
+//
+//   [targets.orders_mysql]
+//   dialect = "mysql"
+//   host = "orders-db.internal"
+//   user = "root"
+//   password = "secret"
+//
+//   [targets.fixtures]
+//   dialect = "sqlite"
+//   path = "fixtures"
+//
+//   [targets.events]
+//   dialect = "mongodb"
+//   uri = "mongodb://events-db.internal:27017/"
+
+#[derive(Debug, Parser)]
+#[command(name = "data_synth", about = "Synthetic data generator")]
+pub struct Cli {
+    /// Path to a TOML config file with named targets
+    #[arg(long, env = "DATASYNTH_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Address the http server listens on
+    #[arg(long, env = "DATASYNTH_BIND")]
+    pub bind: Option<String>,
+
+    /// Mongo connection string used to store schemas and relations
+    #[arg(long, env = "DATASYNTH_MONGO_URI")]
+    pub mongo_uri: Option<String>,
+
+    /// Target used when a request does not name one
+    #[arg(long, env = "DATASYNTH_DEFAULT_TARGET")]
+    pub default_target: Option<String>,
+
+    /// Postgres host of the default target
+    #[arg(long, env = "DATASYNTH_PG_HOST")]
+    pub pg_host: Option<String>,
+
+    /// Postgres port of the default target
+    #[arg(long, env = "DATASYNTH_PG_PORT")]
+    pub pg_port: Option<u16>,
+
+    /// Postgres user of the default target
+    #[arg(long, env = "DATASYNTH_PG_USER")]
+    pub pg_user: Option<String>,
+
+    /// Postgres password of the default target
+    #[arg(long, env = "DATASYNTH_PG_PASSWORD", hide_env_values = true)]
+    pub pg_password: Option<String>,
+
+    /// Postgres ssl mode of the default target
+    #[arg(long, env = "DATASYNTH_PG_SSLMODE", value_enum)]
+    pub pg_sslmode: Option<SslMode>,
+
+    /// Maintenance database of the default target
+    #[arg(long, env = "DATASYNTH_PG_DATABASE")]
+    pub pg_database: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl From<SslMode> for MySqlSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => MySqlSslMode::Disabled,
+            SslMode::Allow | SslMode::Prefer => MySqlSslMode::Preferred,
+            SslMode::Require => MySqlSslMode::Required,
+            SslMode::VerifyCa => MySqlSslMode::VerifyCa,
+            SslMode::VerifyFull => MySqlSslMode::VerifyIdentity,
+        }
+    }
+}
+
+impl From<SslMode> for PgSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Allow => PgSslMode::Allow,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub bind: String,
+    pub default_target: String,
+    // Rows sent per INSERT statement unless a table overrides it
+    pub insert_batch_size: usize,
+    // How tables are loaded unless they override it
+    pub load_mode: LoadMode,
+    // Whether secondary indexes are created after the rows are loaded, unless a table
+    // overrides it. Loading is faster without indexes to keep up to date.
+    pub defer_indexes: bool,
+    // Largest table with unique fields or keys. Each of them keeps a 64 bit fingerprint
+    // per row while the table is generated, about 16 bytes a row with the hash set.
+    pub max_unique_rows: usize,
+    pub mongo: MongoConfig,
+    pub targets: HashMap<String, TargetConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MongoConfig {
+    pub uri: String,
+    pub database: String,
+}
+
+// A named database server the generated data can be written to.
+// `database` is the maintenance database we connect to before the schema's
+// own database exists (to run CREATE DATABASE), mysql targets don't need one.
+// `port` defaults to the usual port of the dialect.
+// `path` is the directory of a sqlite target, every database is a `<database>.db` file
+// in it. Sqlite targets only use `path` and `max_connections`.
+// `uri` is the connection string of a mongodb target, the server's own `mongo.uri` when
+// missing. Mongodb targets only use `uri`.
+// `max_connections` caps each pool the server keeps open for this target.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TargetConfig {
+    pub dialect: Dialect,
+    pub host: String,
+    pub port: Option<u16>,
+    pub user: String,
+    pub password: String,
+    pub ssl_mode: SslMode,
+    pub database: String,
+    pub path: PathBuf,
+    pub uri: Option<String>,
+    pub max_connections: u32,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            bind: "127.0.0.1:3000".to_string(),
+            default_target: "default".to_string(),
+            insert_batch_size: 1000,
+            load_mode: LoadMode::Insert,
+            defer_indexes: true,
+            max_unique_rows: 10_000_000,
+            mongo: MongoConfig::default(),
+            targets: HashMap::new(),
+        }
+    }
+}
+
+impl Default for MongoConfig {
+    fn default() -> Self {
+        MongoConfig {
+            uri: "mongodb://localhost:27017/".to_string(),
+            database: "datasynth".to_string(),
+        }
+    }
+}
+
+impl Default for TargetConfig {
+    fn default() -> Self {
+        TargetConfig {
+            dialect: Dialect::Postgres,
+            host: "localhost".to_string(),
+            port: None,
+            user: "postgres".to_string(),
+            password: "password".to_string(),
+            ssl_mode: SslMode::Prefer,
+            database: "postgres".to_string(),
+            path: PathBuf::from("."),
+            uri: None,
+            max_connections: 10,
+        }
+    }
+}
+
+impl TargetConfig {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or_else(|| self.dialect.default_port())
+    }
+
+    // Connection options for `database` on a postgres target
+    pub fn pg_connect_options(&self, database: &str) -> PgConnectOptions {
+        PgConnectOptions::new()
+            .host(&self.host)
+            .port(self.port())
+            .username(&self.user)
+            .password(&self.password)
+            .ssl_mode(self.ssl_mode.into())
+            .database(database)
+    }
+
+    // Connection options for a mysql target, without a database for the maintenance pool
+    pub fn mysql_connect_options(&self, database: Option<&str>) -> MySqlConnectOptions {
+        let options = MySqlConnectOptions::new()
+            .host(&self.host)
+            .port(self.port())
+            .username(&self.user)
+            .password(&self.password)
+            .ssl_mode(self.ssl_mode.into());
+        match database {
+            Some(database) => options.database(database),
+            None => options,
+        }
+    }
+
+    // Connection options for the file of `database` on a sqlite target, created on first use
+    pub fn sqlite_connect_options(&self, database: &str) -> SqliteConnectOptions {
+        SqliteConnectOptions::new()
+            .filename(self.path.join(format!("{}.db", database)))
+            .create_if_missing(true)
+    }
+}
+
+impl AppConfig {
+    // Loads `.env`, parses the command line and merges everything on top of the config file
+    pub fn load() -> anyhow::Result<Self> {
+        dotenvy::dotenv().ok();
+        let cli = Cli::parse();
+
+        let mut config = match &cli.config {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read config file {}", path.display()))?;
+                toml::from_str(&contents)
+                    .with_context(|| format!("Failed to parse config file {}", path.display()))?
+            }
+            None => AppConfig::default(),
+        };
+
+        config.apply_cli(cli);
+        Ok(config)
+    }
+
+    fn apply_cli(&mut self, cli: Cli) {
+        if let Some(bind) = cli.bind {
+            self.bind = bind;
+        }
+        if let Some(uri) = cli.mongo_uri {
+            self.mongo.uri = uri;
+        }
+        if let Some(name) = cli.default_target {
+            self.default_target = name;
+        }
+
+        // The --pg-* flags patch the default target, creating it if the file didn't define it
+        let target = self.targets.entry(self.default_target.clone()).or_default();
+        if let Some(host) = cli.pg_host {
+            target.host = host;
+        }
+        if let Some(port) = cli.pg_port {
+            target.port = Some(port);
+        }
+        if let Some(user) = cli.pg_user {
+            target.user = user;
+        }
+        if let Some(password) = cli.pg_password {
+            target.password = password;
+        }
+        if let Some(ssl_mode) = cli.pg_sslmode {
+            target.ssl_mode = ssl_mode;
+        }
+        if let Some(database) = cli.pg_database {
+            target.database = database;
+        }
+    }
+
+    // Name of the target a request asked for, the default target when it didn't
+    pub fn target_name<'a>(&'a self, name: Option<&'a str>) -> &'a str {
+        name.unwrap_or(&self.default_target)
+    }
+
+    // Looks up a target by name, falling back to the default target
+    pub fn target(&self, name: Option<&str>) -> anyhow::Result<&TargetConfig> {
+        let name = self.target_name(name);
+        self.targets
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown target -> {} <-", name))
+    }
+}
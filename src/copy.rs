@@ -0,0 +1,424 @@
+use anyhow::{anyhow, bail, Context};
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use tokio::sync::mpsc;
+
+use crate::dialect::Dialect;
+use crate::rows::RowGenerator;
+use crate::value::Value;
+
+// // ----- COPY BULK LOAD ----- // //
+// Streams generated rows into postgres with `COPY ... FROM STDIN`.
+// Rows are generated and encoded on a blocking thread while the async side
+// forwards the encoded chunks to the server, so only a few chunks are ever in memory.
+
+// How rows are written into a postgres table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadMode {
+    // Batched INSERT statements with bound parameters
+    #[default]
+    Insert,
+    // COPY in the text format
+    Copy,
+    // COPY in the binary format
+    CopyBinary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    Text,
+    Binary,
+}
+
+// Size of the chunks handed to the connection
+const CHUNK_SIZE: usize = 1 << 20;
+// Chunks allowed to wait in the channel before generation blocks
+const CHANNEL_DEPTH: usize = 4;
+
+// Header of the binary COPY format: signature, flags and header extension length
+const BINARY_SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+// Wire representation of a column in the binary format
+#[derive(Debug, Clone, Copy)]
+enum BinaryKind {
+    Text,
+    Int4,
+    Int8,
+    Float8,
+    Bool,
+    Numeric,
+    Date,
+    Time,
+    Timestamp,
+    Uuid,
+    Json,
+    Jsonb,
+    Bytea,
+}
+
+impl BinaryKind {
+    fn from_sql_type(sql_type: &str) -> BinaryKind {
+        let sql_type = sql_type.to_uppercase();
+        match sql_type.split('(').next().unwrap_or_default().trim() {
+            "INT" | "INTEGER" | "SERIAL" => BinaryKind::Int4,
+            "BIGINT" | "BIGSERIAL" => BinaryKind::Int8,
+            "FLOAT" | "DOUBLE PRECISION" => BinaryKind::Float8,
+            "BOOLEAN" => BinaryKind::Bool,
+            "DECIMAL" | "NUMERIC" => BinaryKind::Numeric,
+            "DATE" => BinaryKind::Date,
+            "TIME" => BinaryKind::Time,
+            "TIMESTAMP" => BinaryKind::Timestamp,
+            "UUID" => BinaryKind::Uuid,
+            "JSON" => BinaryKind::Json,
+            "JSONB" => BinaryKind::Jsonb,
+            "BYTEA" => BinaryKind::Bytea,
+            _ => BinaryKind::Text,
+        }
+    }
+}
+
+// Generates `datasize` rows for `fields` and loads them into `tablename` with COPY.
+// Returns the number of rows postgres reports as copied.
+pub async fn copy_rows(
+    pool: &PgPool,
+    tablename: &str,
+    rows: RowGenerator,
+    datasize: usize,
+    format: CopyFormat,
+) -> anyhow::Result<u64> {
+    let column_names: Vec<&str> = rows.columns().iter().map(|c| c.name.as_str()).collect();
+    let tablename = Dialect::Postgres.quote(tablename);
+    let column_names = Dialect::Postgres.quote_all(&column_names);
+
+    let statement = match format {
+        CopyFormat::Text => format!("COPY {} ({}) FROM STDIN", tablename, column_names),
+        CopyFormat::Binary => format!(
+            "COPY {} ({}) FROM STDIN WITH (FORMAT binary)",
+            tablename, column_names
+        ),
+    };
+
+    let (sender, mut receiver) = mpsc::channel::<Vec<u8>>(CHANNEL_DEPTH);
+    let producer =
+        actix_web::rt::task::spawn_blocking(move || produce_rows(rows, datasize, format, sender));
+
+    let mut copy = pool.copy_in_raw(&statement).await?;
+    let mut send_error = None;
+    while let Some(chunk) = receiver.recv().await {
+        if let Err(err) = copy.send(chunk).await {
+            send_error = Some(err);
+            break;
+        }
+    }
+    // Stops the producer if we bailed out early
+    drop(receiver);
+
+    // The connection goes back to the pool, it must not be left in the middle of a COPY
+    let produced = match producer.await {
+        Ok(produced) => produced,
+        Err(err) => {
+            copy.abort("Row generation panicked").await.ok();
+            return Err(anyhow::Error::new(err).context("Row generation panicked"));
+        }
+    };
+    if let Some(err) = send_error {
+        copy.abort(err.to_string()).await.ok();
+        return Err(err.into());
+    }
+    if let Err(err) = produced {
+        copy.abort(err.to_string()).await.ok();
+        return Err(err);
+    }
+
+    Ok(copy.finish().await?)
+}
+
+// Runs on the blocking pool: generates and encodes every row, flushing full chunks to `sender`
+fn produce_rows(
+    mut rows: RowGenerator,
+    datasize: usize,
+    format: CopyFormat,
+    sender: mpsc::Sender<Vec<u8>>,
+) -> anyhow::Result<()> {
+    let kinds: Vec<BinaryKind> = rows
+        .columns()
+        .iter()
+        .map(|c| BinaryKind::from_sql_type(&c.sql_type))
+        .collect();
+
+    let mut buf = Vec::with_capacity(CHUNK_SIZE);
+    if format == CopyFormat::Binary {
+        buf.extend_from_slice(BINARY_SIGNATURE);
+        buf.extend_from_slice(&0i32.to_be_bytes());
+        buf.extend_from_slice(&0i32.to_be_bytes());
+    }
+
+    for _ in 0..datasize {
+        match format {
+            CopyFormat::Text => {
+                for (i, value) in rows.next_row()?.iter().enumerate() {
+                    if i > 0 {
+                        buf.push(b'\t');
+                    }
+                    write_text_value(&mut buf, value);
+                }
+                buf.push(b'\n');
+            }
+            CopyFormat::Binary => {
+                buf.extend_from_slice(&(kinds.len() as i16).to_be_bytes());
+                let row = rows.next_row()?;
+                for ((value, kind), column) in row.iter().zip(&kinds).zip(rows.columns()) {
+                    write_binary_value(&mut buf, *kind, value).with_context(|| {
+                        format!("Can't encode -> {:?} <- for {}", value, column.name)
+                    })?;
+                }
+            }
+        }
+
+        if buf.len() >= CHUNK_SIZE {
+            let chunk = std::mem::replace(&mut buf, Vec::with_capacity(CHUNK_SIZE));
+            sender
+                .blocking_send(chunk)
+                .map_err(|_| anyhow!("COPY stream closed"))?;
+        }
+    }
+
+    if format == CopyFormat::Binary {
+        buf.extend_from_slice(&(-1i16).to_be_bytes());
+    }
+    if !buf.is_empty() {
+        sender
+            .blocking_send(buf)
+            .map_err(|_| anyhow!("COPY stream closed"))?;
+    }
+    Ok(())
+}
+
+// Text format: backslash escapes for the characters COPY treats specially, \N for NULL
+fn write_text_value(buf: &mut Vec<u8>, value: &Value) {
+    let text = match value.to_sql_text() {
+        Some(text) => text,
+        None => {
+            buf.extend_from_slice(b"\\N");
+            return;
+        }
+    };
+    for byte in text.bytes() {
+        match byte {
+            b'\\' => buf.extend_from_slice(b"\\\\"),
+            b'\t' => buf.extend_from_slice(b"\\t"),
+            b'\n' => buf.extend_from_slice(b"\\n"),
+            b'\r' => buf.extend_from_slice(b"\\r"),
+            _ => buf.push(byte),
+        }
+    }
+}
+
+// Binary format: a 4 byte length followed by the type's send representation, -1 for NULL
+fn write_binary_value(buf: &mut Vec<u8>, kind: BinaryKind, value: &Value) -> anyhow::Result<()> {
+    let bytes: Vec<u8> = match (kind, value) {
+        (_, Value::Null) => {
+            buf.extend_from_slice(&(-1i32).to_be_bytes());
+            return Ok(());
+        }
+        (BinaryKind::Int4, Value::Int(v)) => i32::try_from(*v)?.to_be_bytes().to_vec(),
+        (BinaryKind::Int8, Value::Int(v)) => v.to_be_bytes().to_vec(),
+        (BinaryKind::Float8, Value::Float(v)) => v.to_be_bytes().to_vec(),
+        (BinaryKind::Float8, Value::Int(v)) => (*v as f64).to_be_bytes().to_vec(),
+        (BinaryKind::Bool, Value::Bool(v)) => vec![*v as u8],
+        (BinaryKind::Numeric, Value::Decimal(_) | Value::Int(_) | Value::Float(_)) => {
+            encode_numeric(&value.to_sql_text().unwrap_or_default())?
+        }
+        (BinaryKind::Date, Value::Date(v)) => {
+            let days = v.signed_duration_since(pg_epoch().date()).num_days();
+            i32::try_from(days)?.to_be_bytes().to_vec()
+        }
+        (BinaryKind::Time, Value::Time(v)) => {
+            let micros =
+                v.num_seconds_from_midnight() as i64 * 1_000_000 + (v.nanosecond() / 1_000) as i64;
+            micros.to_be_bytes().to_vec()
+        }
+        (BinaryKind::Timestamp, Value::Timestamp(v)) => {
+            let micros = v
+                .signed_duration_since(pg_epoch())
+                .num_microseconds()
+                .ok_or_else(|| anyhow!("timestamp out of range"))?;
+            micros.to_be_bytes().to_vec()
+        }
+        (BinaryKind::Uuid, Value::Uuid(v)) => v.as_bytes().to_vec(),
+        // jsonb is prefixed with its format version
+        (BinaryKind::Jsonb, Value::Json(v)) => {
+            let mut bytes = vec![1u8];
+            bytes.extend_from_slice(v.to_string().as_bytes());
+            bytes
+        }
+        (BinaryKind::Bytea, Value::Bytes(v)) => v.clone(),
+        // Text columns take the text form of any value
+        (BinaryKind::Text | BinaryKind::Json, _) => {
+            value.to_sql_text().unwrap_or_default().into_bytes()
+        }
+        _ => bail!("a {:?} column can't hold {:?}", kind, value),
+    };
+
+    buf.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+    buf.extend_from_slice(&bytes);
+    Ok(())
+}
+
+// Postgres counts dates and timestamps from 2000-01-01
+fn pg_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+// NUMERIC send format: base 10000 digits with a weight, sign and display scale
+fn encode_numeric(value: &str) -> anyhow::Result<Vec<u8>> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && frac_part.is_empty()
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        bail!("not a decimal number");
+    }
+
+    let int_part = int_part.trim_start_matches('0');
+    let dscale = frac_part.len() as u16;
+
+    // Left pad the integer part and right pad the fraction to whole groups of 4 digits
+    let int_pad = (4 - int_part.len() % 4) % 4;
+    let frac_pad = (4 - frac_part.len() % 4) % 4;
+    let padded = format!(
+        "{}{}{}{}",
+        "0".repeat(int_pad),
+        int_part,
+        frac_part,
+        "0".repeat(frac_pad)
+    );
+    let mut groups: Vec<i16> = padded
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap().parse().unwrap())
+        .collect();
+    let mut weight = ((int_part.len() + int_pad) / 4) as i16 - 1;
+
+    // Leading and trailing zero groups are implied by the weight and ndigits
+    while groups.first() == Some(&0) {
+        groups.remove(0);
+        weight -= 1;
+    }
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    if groups.is_empty() {
+        weight = 0;
+    }
+
+    let sign: u16 = if negative && !groups.is_empty() {
+        0x4000
+    } else {
+        0x0000
+    };
+
+    let mut bytes = Vec::with_capacity(8 + groups.len() * 2);
+    bytes.extend_from_slice(&(groups.len() as i16).to_be_bytes());
+    bytes.extend_from_slice(&weight.to_be_bytes());
+    bytes.extend_from_slice(&sign.to_be_bytes());
+    bytes.extend_from_slice(&dscale.to_be_bytes());
+    for group in groups {
+        bytes.extend_from_slice(&group.to_be_bytes());
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NUMERIC send format back to its text form
+    fn decode_numeric(bytes: &[u8]) -> String {
+        let word = |i: usize| i16::from_be_bytes([bytes[2 * i], bytes[2 * i + 1]]);
+        let (ndigits, weight, sign, dscale) = (word(0), word(1), word(2), word(3) as usize);
+        // Base 10000 digit of weight `position`, the ones left out are zeros
+        let group = |position: i16| match weight - position {
+            i if (0..ndigits).contains(&i) => word(4 + i as usize),
+            _ => 0,
+        };
+        let integer: String = (0..=weight.max(0))
+            .rev()
+            .map(|position| format!("{:04}", group(position)))
+            .collect();
+        let fraction: String = (1..=dscale.div_ceil(4) as i16)
+            .map(|position| format!("{:04}", group(-position)))
+            .collect();
+
+        let mut text = match integer.trim_start_matches('0') {
+            "" => "0".to_string(),
+            digits => digits.to_string(),
+        };
+        if dscale > 0 {
+            text = format!("{}.{}", text, &fraction[..dscale]);
+        }
+        match sign as u16 {
+            0x4000 => format!("-{}", text),
+            _ => text,
+        }
+    }
+
+    #[test]
+    fn numeric_round_trips() {
+        let cases = [
+            ("0", "0"),
+            ("-0", "0"),
+            ("0.00", "0.00"),
+            ("+5", "5"),
+            (".5", "0.5"),
+            ("007.10", "7.10"),
+            ("-0.001", "-0.001"),
+            ("10000", "10000"),
+            ("99999999.99999", "99999999.99999"),
+            ("-1234567.89", "-1234567.89"),
+            ("123456789.000123", "123456789.000123"),
+            ("0.0000000001", "0.0000000001"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(decode_numeric(&encode_numeric(input).unwrap()), expected);
+        }
+    }
+
+    #[test]
+    fn numeric_rejects_what_isnt_a_decimal() {
+        for input in ["", ".", "-", "1e5", "abc", "1.2.3", "--1", "1 "] {
+            assert!(encode_numeric(input).is_err(), "{:?} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn text_values_escape_copy_characters() {
+        let mut buf = Vec::new();
+        write_text_value(&mut buf, &Value::Text("a\\b\tc\nd\re".to_string()));
+        assert_eq!(buf, b"a\\\\b\\tc\\nd\\re");
+        buf.clear();
+        write_text_value(&mut buf, &Value::Null);
+        assert_eq!(buf, b"\\N");
+    }
+
+    #[test]
+    fn binary_values_fit_their_column() {
+        let mut buf = Vec::new();
+        write_binary_value(&mut buf, BinaryKind::Int4, &Value::Null).unwrap();
+        write_binary_value(&mut buf, BinaryKind::Int4, &Value::Int(-2)).unwrap();
+        assert_eq!(buf, [255, 255, 255, 255, 0, 0, 0, 4, 255, 255, 255, 254]);
+        assert!(write_binary_value(&mut buf, BinaryKind::Int4, &Value::Int(1 << 40)).is_err());
+        assert!(write_binary_value(&mut buf, BinaryKind::Uuid, &Value::Int(1)).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+// // ----- SQL DIALECTS ----- // //
+// The SQL flavours the generated data can be written in. Column types come from the
+// generators (`Generator::sql_type`), everything else that differs between servers
+// (identifier quoting, auto increment keys) is answered here, and the
+// statements are built from it in `main.rs`, `mysql.rs` and `sqlite.rs`.
+// MongoDB targets get no SQL, their "column types" are the BSON types of the
+// values, see `mongo.rs`.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dialect {
+    #[default]
+    Postgres,
+    // MySQL 8 and MariaDB 10.3 or later
+    Mysql,
+    // A database file per schema, see `sqlite.rs`
+    Sqlite,
+    // A collection per table and a document per row
+    Mongodb,
+}
+
+impl Dialect {
+    pub const ALL: [Dialect; 4] = [
+        Dialect::Postgres,
+        Dialect::Mysql,
+        Dialect::Sqlite,
+        Dialect::Mongodb,
+    ];
+
+    pub fn default_port(self) -> u16 {
+        match self {
+            Dialect::Postgres => 5432,
+            Dialect::Mysql => 3306,
+            // Files are opened directly, there's no server to reach
+            Dialect::Sqlite => 0,
+            Dialect::Mongodb => 27017,
+        }
+    }
+
+    // Quoted identifier, so names like `order` or `user` can be used.
+    // Postgres folds unquoted names to lower case, quoting the lower case name keeps
+    // the tables and columns it creates the same as when the names weren't quoted.
+    pub fn quote(self, name: &str) -> String {
+        match self {
+            Dialect::Postgres => format!("\"{}\"", name.to_lowercase().replace('"', "\"\"")),
+            Dialect::Mysql => format!("`{}`", name.replace('`', "``")),
+            // Names are matched case insensitively, they are kept as written
+            Dialect::Sqlite => format!("\"{}\"", name.replace('"', "\"\"")),
+            // Collection and field names are used as they are
+            Dialect::Mongodb => name.to_string(),
+        }
+    }
+
+    // Comma separated list of quoted identifiers
+    pub fn quote_all<S: AsRef<str>>(self, names: &[S]) -> String {
+        names
+            .iter()
+            .map(|name| self.quote(name.as_ref()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    // Column type of a column referencing a key of type `sql_type`,
+    // auto incremented keys are plain integers on the referencing side
+    pub fn key_type(self, sql_type: String) -> String {
+        match (self, sql_type.to_uppercase().as_str()) {
+            (Dialect::Postgres, "SERIAL") => "INTEGER".to_string(),
+            (Dialect::Postgres, "BIGSERIAL") => "BIGINT".to_string(),
+            (Dialect::Mysql, upper) => match upper.strip_suffix(" AUTO_INCREMENT") {
+                Some(integer) => integer.to_string(),
+                None => sql_type,
+            },
+            _ => sql_type,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting_escapes_the_quote_character() {
+        assert_eq!(Dialect::Postgres.quote("Order"), "\"order\"");
+        assert_eq!(Dialect::Postgres.quote("a\"b"), "\"a\"\"b\"");
+        assert_eq!(Dialect::Mysql.quote("Order"), "`Order`");
+        assert_eq!(Dialect::Mysql.quote("a`b"), "`a``b`");
+        assert_eq!(Dialect::Mysql.quote_all(&["a", "b"]), "`a`, `b`");
+    }
+
+    #[test]
+    fn references_to_auto_increment_keys_are_plain_integers() {
+        let key_type = |dialect: Dialect, sql_type: &str| dialect.key_type(sql_type.to_string());
+        assert_eq!(key_type(Dialect::Postgres, "serial"), "INTEGER");
+        assert_eq!(key_type(Dialect::Postgres, "BIGSERIAL"), "BIGINT");
+        assert_eq!(key_type(Dialect::Postgres, "TEXT"), "TEXT");
+        assert_eq!(key_type(Dialect::Mysql, "INT AUTO_INCREMENT"), "INT");
+        assert_eq!(key_type(Dialect::Mysql, "VARCHAR(255)"), "VARCHAR(255)");
+    }
+}
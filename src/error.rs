@@ -0,0 +1,173 @@
+use std::fmt;
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+use sqlx::postgres::PgDatabaseError;
+
+use crate::validation::SchemaError;
+
+// // ----- ERRORS ----- // //
+// Every handler returns `Result<_, AppError>`. Errors are answered with a JSON body like
+//   {"code": "database", "message": "...", "table": "orders", "field": "customer_id"}
+// where `table` / `field` are only present when the failure can be pinned on them.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // The body or a parameter couldn't be read
+    InvalidRequest,
+    // The schema was read but can't be generated, `errors` lists why
+    InvalidSchema,
+    UnknownTarget,
+    NotFound,
+    // The schema is valid but its rows couldn't be generated, e.g. unique values ran out
+    Generation,
+    // Postgres rejected a statement
+    Database,
+    // Postgres or mongo couldn't be reached
+    Unavailable,
+    Internal,
+}
+
+impl ErrorCode {
+    fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::UnknownTarget => StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidSchema | ErrorCode::Generation => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Database | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<SchemaError>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        AppError {
+            code,
+            message: message.into(),
+            table: None,
+            field: None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::InvalidRequest, message)
+    }
+
+    pub fn invalid_schema(errors: Vec<SchemaError>) -> Self {
+        AppError {
+            errors,
+            ..AppError::new(ErrorCode::InvalidSchema, "The schema is invalid")
+        }
+    }
+
+    pub fn unknown_target(err: anyhow::Error) -> Self {
+        AppError::new(ErrorCode::UnknownTarget, err.to_string())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::Internal, message)
+    }
+
+    // Names the table the error happened in, unless a more precise one is already known
+    pub fn in_table(mut self, table: &str) -> Self {
+        self.table.get_or_insert_with(|| table.to_string());
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)?;
+        if let Some(table) = &self.table {
+            write!(f, " (table {}", table)?;
+            if let Some(field) = &self.field {
+                write!(f, ", field {}", field)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.code.status().is_server_error() {
+            eprintln!("{}", self);
+        }
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        match &err {
+            sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut => {
+                AppError::new(ErrorCode::Unavailable, err.to_string())
+            }
+            sqlx::Error::Database(db_err) => {
+                // Postgres tells us which table and column a constraint failed on
+                let pg_err = db_err.try_downcast_ref::<PgDatabaseError>();
+                AppError {
+                    table: pg_err.and_then(|e| e.table()).map(str::to_string),
+                    field: pg_err.and_then(|e| e.column()).map(str::to_string),
+                    ..AppError::new(ErrorCode::Database, db_err.message())
+                }
+            }
+            _ => AppError::new(ErrorCode::Database, err.to_string()),
+        }
+    }
+}
+
+impl From<mongodb::error::Error> for AppError {
+    fn from(err: mongodb::error::Error) -> Self {
+        use mongodb::error::ErrorKind;
+
+        match *err.kind {
+            ErrorKind::ServerSelection { .. }
+            | ErrorKind::Io(_)
+            | ErrorKind::ConnectionPoolCleared { .. } => {
+                AppError::new(ErrorCode::Unavailable, err.to_string())
+            }
+            _ => AppError::internal(err.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        // Keep the structured error when one was wrapped on the way up
+        match err.downcast::<sqlx::Error>() {
+            Ok(err) => AppError::from(err),
+            Err(err) => match err.downcast::<AppError>() {
+                Ok(err) => err,
+                Err(err) => AppError::internal(format!("{:#}", err)),
+            },
+        }
+    }
+}
@@ -0,0 +1,686 @@
+use std::io::{self, Write};
+
+use actix_web::web::Bytes;
+use arrow_ipc::writer::FileWriter;
+use futures_util::Stream;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::columnar::{arrow_schema, BatchBuilder, BATCH_ROWS};
+use crate::dialect::Dialect;
+use crate::error::AppError;
+use crate::references::{
+    fill_order, find_table, is_deferred, reference_groups, referenced_keys, KeyStore,
+};
+use crate::rows::RowGenerator;
+use crate::seed::table_seed;
+use crate::value::Value;
+use crate::{
+    create_indexes_sql, create_table_sql, deferred_constraints_sql, type_definitions_sql, Table,
+};
+
+// // ----- FILE EXPORT ----- // //
+// Writes the generated data to files instead of a database. Files are produced on a
+// blocking thread and streamed to the client chunk by chunk, so the rows themselves only
+// take a few chunks of memory.
+//
+// Without a database, serial keys are counted from 1 and the keys of referenced tables
+// are kept while the table is written. Those keys stay in memory for the whole export,
+// see `KeyStore` for what that costs. References inside a cycle need keys of tables
+// that aren't written yet, those tables are generated once more from the same seed to
+// get them (see `Exporter::ensure_keys`).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    // One `<table>.csv` per table with a header row, in a zip archive. NULL is written
+    // as \N, see `csv_field`.
+    Csv,
+    // One `<table>.ndjson` per table with an object per line, in a zip archive
+    Ndjson,
+    // A single document `{"<table>": [{...}, ...], ...}`
+    Json,
+    // A postgres script with the CREATE TABLE and INSERT statements
+    Sql,
+    // One `<table>.parquet` per table with typed columns, in a zip archive
+    Parquet,
+    // One `<table>.arrow` per table in the Arrow IPC file format, in a zip archive
+    Arrow,
+}
+
+impl FileFormat {
+    pub fn file_name(self, database: &str) -> String {
+        let extension = match self {
+            FileFormat::Json => "json",
+            FileFormat::Sql => "sql",
+            _ => "zip",
+        };
+        format!("{}.{}", database, extension)
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FileFormat::Json => "application/json",
+            FileFormat::Sql => "application/sql",
+            _ => "application/zip",
+        }
+    }
+
+    // Extension of the files in the zip archive, None for the single file formats
+    fn table_extension(self) -> Option<&'static str> {
+        match self {
+            FileFormat::Csv => Some("csv"),
+            FileFormat::Ndjson => Some("ndjson"),
+            FileFormat::Parquet => Some("parquet"),
+            FileFormat::Arrow => Some("arrow"),
+            FileFormat::Json | FileFormat::Sql => None,
+        }
+    }
+
+    // Parquet pages are compressed already, deflating them again only costs time
+    fn zip_options(self) -> SimpleFileOptions {
+        match self {
+            FileFormat::Parquet => {
+                SimpleFileOptions::default().compression_method(CompressionMethod::Stored)
+            }
+            _ => SimpleFileOptions::default(),
+        }
+    }
+}
+
+// Size of the chunks handed to the response
+const CHUNK_SIZE: usize = 1 << 20;
+// Chunks allowed to wait in the channel before generation blocks
+const CHANNEL_DEPTH: usize = 4;
+// Rows per parquet row group, the writer keeps one row group in memory
+const ROW_GROUP_ROWS: usize = 128 * 1024;
+
+// Generates the export on the blocking pool and returns it as a response body stream.
+// `batch_size` is the number of rows per INSERT statement of the sql format.
+pub fn export_stream(
+    tables: Vec<Table>,
+    seed: u64,
+    format: FileFormat,
+    batch_size: usize,
+) -> impl Stream<Item = Result<Bytes, AppError>> {
+    let (sender, receiver) = mpsc::channel(CHANNEL_DEPTH);
+    actix_web::rt::task::spawn_blocking(move || {
+        let mut out = ChunkWriter {
+            buf: Vec::with_capacity(CHUNK_SIZE),
+            sender,
+        };
+        let mut exporter = Exporter {
+            tables: &tables,
+            seed,
+            keys: KeyStore::default(),
+        };
+        let result = exporter
+            .write(&mut out, format, batch_size)
+            .and_then(|()| out.flush().map_err(io_error));
+        // The client went away when the channel is closed, nobody is left to tell
+        if let Err(err) = result {
+            out.sender.blocking_send(Err(err)).ok();
+        }
+    });
+
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    })
+}
+
+// Buffers the output and hands it to the response in chunks
+struct ChunkWriter {
+    buf: Vec<u8>,
+    sender: mpsc::Sender<Result<Bytes, AppError>>,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+        self.sender
+            .blocking_send(Ok(Bytes::from(chunk)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the download was cancelled"))
+    }
+}
+
+fn io_error(err: io::Error) -> AppError {
+    AppError::internal(format!("Can't write the export: {}", err))
+}
+
+struct Exporter<'a> {
+    tables: &'a [Table],
+    seed: u64,
+    keys: KeyStore,
+}
+
+impl<'a> Exporter<'a> {
+    fn write(
+        &mut self,
+        out: &mut (dyn Write + Send),
+        format: FileFormat,
+        batch_size: usize,
+    ) -> Result<(), AppError> {
+        let order: Vec<&Table> = fill_order(self.tables)
+            .into_iter()
+            .map(|i| &self.tables[i])
+            .collect();
+
+        if let Some(extension) = format.table_extension() {
+            let mut zip = ZipWriter::new_stream(out);
+            for &table in &order {
+                zip.start_file(
+                    format!("{}.{}", table.tablename, extension),
+                    format.zip_options(),
+                )
+                .map_err(|err| AppError::internal(err.to_string()))?;
+                match format {
+                    FileFormat::Csv => self.write_csv(&mut zip, table),
+                    FileFormat::Ndjson => self.write_ndjson(&mut zip, table),
+                    FileFormat::Parquet => self.write_parquet(&mut zip, table),
+                    _ => self.write_arrow(&mut zip, table),
+                }
+                .map_err(|err| err.in_table(&table.tablename))?;
+            }
+            zip.finish()
+                .map_err(|err| AppError::internal(err.to_string()))?;
+            return Ok(());
+        }
+
+        match format {
+            FileFormat::Json => {
+                out.write_all(b"{").map_err(io_error)?;
+                for (i, &table) in order.iter().enumerate() {
+                    if i > 0 {
+                        out.write_all(b",").map_err(io_error)?;
+                    }
+                    self.write_json(out, table)
+                        .map_err(|err| err.in_table(&table.tablename))?;
+                }
+                out.write_all(b"}\n").map_err(io_error)?;
+            }
+            FileFormat::Sql => self.write_sql(out, &order, batch_size)?,
+            _ => unreachable!("zipped formats are written above"),
+        }
+        Ok(())
+    }
+
+    fn write_csv(&mut self, out: &mut dyn Write, table: &Table) -> Result<(), AppError> {
+        let mut rows = self.rows(table)?;
+        let header: Vec<String> = rows.columns().iter().map(|c| csv_text(&c.name)).collect();
+        writeln!(out, "{}", header.join(",")).map_err(io_error)?;
+        self.each_row(table, &mut rows, |row| {
+            let record: Vec<String> = row.iter().map(csv_field).collect();
+            writeln!(out, "{}", record.join(",")).map_err(io_error)
+        })
+    }
+
+    fn write_ndjson(&mut self, out: &mut dyn Write, table: &Table) -> Result<(), AppError> {
+        let mut rows = self.rows(table)?;
+        let names: Vec<String> = rows.columns().iter().map(|c| c.name.clone()).collect();
+        self.each_row(table, &mut rows, |row| {
+            serde_json::to_writer(&mut *out, &json_object(&names, row)).map_err(json_error)?;
+            out.write_all(b"\n").map_err(io_error)
+        })
+    }
+
+    fn write_json(&mut self, out: &mut dyn Write, table: &Table) -> Result<(), AppError> {
+        let mut rows = self.rows(table)?;
+        let names: Vec<String> = rows.columns().iter().map(|c| c.name.clone()).collect();
+        serde_json::to_writer(&mut *out, &table.tablename).map_err(json_error)?;
+        out.write_all(b":[").map_err(io_error)?;
+        let mut first = true;
+        self.each_row(table, &mut rows, |row| {
+            if !first {
+                out.write_all(b",").map_err(io_error)?;
+            }
+            first = false;
+            serde_json::to_writer(&mut *out, &json_object(&names, row)).map_err(json_error)
+        })?;
+        out.write_all(b"]").map_err(io_error)
+    }
+
+    // Row groups are written as they fill up, so tables of any size fit in memory
+    fn write_parquet(
+        &mut self,
+        out: &mut (dyn Write + Send),
+        table: &Table,
+    ) -> Result<(), AppError> {
+        let mut rows = self.rows(table)?;
+        let schema = arrow_schema(rows.columns());
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_size(ROW_GROUP_ROWS)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(out, schema.clone(), Some(properties)).map_err(parquet_error)?;
+        let mut batch = BatchBuilder::new(schema);
+        self.each_row(table, &mut rows, |row| {
+            batch.push(row)?;
+            if batch.len() == BATCH_ROWS {
+                writer.write(&batch.take()?).map_err(parquet_error)?;
+            }
+            Ok(())
+        })?;
+        if !batch.is_empty() {
+            writer.write(&batch.take()?).map_err(parquet_error)?;
+        }
+        writer.close().map_err(parquet_error)?;
+        Ok(())
+    }
+
+    fn write_arrow(&mut self, out: &mut dyn Write, table: &Table) -> Result<(), AppError> {
+        let mut rows = self.rows(table)?;
+        let schema = arrow_schema(rows.columns());
+        let mut writer = FileWriter::try_new(out, &schema).map_err(arrow_error)?;
+        let mut batch = BatchBuilder::new(schema);
+        self.each_row(table, &mut rows, |row| {
+            batch.push(row)?;
+            if batch.len() == BATCH_ROWS {
+                writer.write(&batch.take()?).map_err(arrow_error)?;
+            }
+            Ok(())
+        })?;
+        if !batch.is_empty() {
+            writer.write(&batch.take()?).map_err(arrow_error)?;
+        }
+        writer.finish().map_err(arrow_error)
+    }
+
+    fn write_sql(
+        &mut self,
+        out: &mut dyn Write,
+        order: &[&Table],
+        batch_size: usize,
+    ) -> Result<(), AppError> {
+        writeln!(out, "-- Generated by data_synth with seed {}", self.seed).map_err(io_error)?;
+        writeln!(out, "BEGIN;\n").map_err(io_error)?;
+        let mut definitions: Vec<String> = Vec::new();
+        for &table in order {
+            for definition in type_definitions_sql(table, Dialect::Postgres) {
+                if !definitions.contains(&definition) {
+                    writeln!(out, "{};", definition).map_err(io_error)?;
+                    definitions.push(definition);
+                }
+            }
+        }
+        for &table in order {
+            writeln!(
+                out,
+                "{};",
+                create_table_sql(table, self.tables, Dialect::Postgres)?
+            )
+            .map_err(io_error)?;
+        }
+        writeln!(out).map_err(io_error)?;
+
+        for &table in order {
+            let mut rows = self.rows(table)?;
+            let names: Vec<&str> = rows.columns().iter().map(|c| c.name.as_str()).collect();
+            let insert = format!(
+                "INSERT INTO {} ({}) VALUES",
+                Dialect::Postgres.quote(&table.tablename),
+                Dialect::Postgres.quote_all(&names)
+            );
+            let serials: Vec<String> = rows
+                .columns()
+                .iter()
+                .filter(|c| c.serial)
+                .map(|c| c.name.clone())
+                .collect();
+
+            let mut in_batch = 0;
+            self.each_row(table, &mut rows, |row| {
+                let values: Vec<String> = row.iter().map(Value::to_sql_literal).collect();
+                let separator = if in_batch == 0 { insert.as_str() } else { "," };
+                write!(out, "{}\n  ({})", separator, values.join(", ")).map_err(io_error)?;
+                in_batch += 1;
+                if in_batch == batch_size.max(1) {
+                    writeln!(out, ";").map_err(io_error)?;
+                    in_batch = 0;
+                }
+                Ok(())
+            })
+            .map_err(|err| err.in_table(&table.tablename))?;
+            if in_batch > 0 {
+                writeln!(out, ";").map_err(io_error)?;
+            }
+
+            // The sequences continue after the counted keys
+            if table.datasize > 0 {
+                for serial in serials {
+                    writeln!(
+                        out,
+                        "SELECT setval(pg_get_serial_sequence('{}', '{}'), {});",
+                        table.tablename, serial, table.datasize
+                    )
+                    .map_err(io_error)?;
+                }
+            }
+            writeln!(out).map_err(io_error)?;
+        }
+
+        for &table in order {
+            if let Some(alter) = deferred_constraints_sql(table, self.tables, Dialect::Postgres) {
+                writeln!(out, "{};", alter).map_err(io_error)?;
+            }
+        }
+        // Indexes come last, the script loads faster without them
+        for &table in order {
+            for index in create_indexes_sql(table, Dialect::Postgres) {
+                writeln!(out, "{};", index).map_err(io_error)?;
+            }
+        }
+        writeln!(out, "COMMIT;").map_err(io_error)
+    }
+
+    // Rows of a table, the keys of every table it references are generated first
+    fn rows(&mut self, table: &Table) -> Result<RowGenerator, AppError> {
+        for group in reference_groups(table) {
+            if let Some(parent) = find_table(self.tables, group.parent) {
+                self.ensure_keys(parent)?;
+            }
+        }
+        RowGenerator::complete(table, self.tables, &self.keys, table_seed(self.seed, table))
+            .map_err(|err| err.in_table(&table.tablename))
+    }
+
+    // Generates the rows of `table` once to keep its referenced keys, unless they are known.
+    // Deferred references are left out: they aren't part of any key and the keys of their
+    // cycle may be the ones being generated. The other columns come out the same since
+    // every column has its own stream.
+    fn ensure_keys(&mut self, table: &Table) -> Result<(), AppError> {
+        let wanted = referenced_keys(table, self.tables);
+        if wanted
+            .iter()
+            .all(|fields| self.keys.contains(&table.tablename, fields))
+        {
+            return Ok(());
+        }
+
+        for group in reference_groups(table) {
+            if is_deferred(self.tables, table, &group) {
+                continue;
+            }
+            if let Some(parent) = find_table(self.tables, group.parent) {
+                self.ensure_keys(parent)?;
+            }
+        }
+        let mut rows = RowGenerator::complete_without_deferred(
+            table,
+            self.tables,
+            &self.keys,
+            table_seed(self.seed, table),
+        )
+        .map_err(|err| err.in_table(&table.tablename))?;
+        self.each_row(table, &mut rows, |_| Ok(()))
+    }
+
+    // Hands every row of `table` to `write`, keeping the keys other tables reference
+    fn each_row(
+        &mut self,
+        table: &Table,
+        rows: &mut RowGenerator,
+        mut write: impl FnMut(&[Value]) -> Result<(), AppError>,
+    ) -> Result<(), AppError> {
+        let mut collected: Vec<CollectedKeys> = Vec::new();
+        for fields in referenced_keys(table, self.tables) {
+            if self.keys.contains(&table.tablename, &fields) {
+                continue;
+            }
+            let positions = fields
+                .iter()
+                .map(|field| {
+                    rows.columns()
+                        .iter()
+                        .position(|c| c.name.eq_ignore_ascii_case(field))
+                })
+                .collect::<Option<Vec<usize>>>();
+            // Keys built from deferred references can't be collected here
+            if let Some(positions) = positions {
+                collected.push(CollectedKeys {
+                    fields,
+                    positions,
+                    values: Vec::with_capacity(table.datasize),
+                });
+            }
+        }
+
+        for _ in 0..table.datasize {
+            let row = rows.next_row()?;
+            for keys in &mut collected {
+                let key = keys.positions.iter().map(|&i| row[i].clone()).collect();
+                keys.values.push(key);
+            }
+            write(&row)?;
+        }
+
+        for keys in collected {
+            self.keys
+                .insert(&table.tablename, &keys.fields, keys.values);
+        }
+        Ok(())
+    }
+}
+
+// Key columns of a table kept while it is written
+struct CollectedKeys<'a> {
+    fields: Vec<&'a str>,
+    // Index of each field in the rows
+    positions: Vec<usize>,
+    values: Vec<Vec<Value>>,
+}
+
+// NULL is an unquoted \N, which postgres (COPY ... CSV NULL '\N') and mysql (LOAD DATA)
+// read back as NULL. An empty string stays an empty field.
+fn csv_field(value: &Value) -> String {
+    match value.to_sql_text() {
+        Some(text) => csv_text(&text),
+        None => "\\N".to_string(),
+    }
+}
+
+// Quoted when it holds a delimiter, a quote or a line break, or reads as the NULL marker
+fn csv_text(text: &str) -> String {
+    if text == "\\N" || text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// Members in the order of the columns, serde_json's preserve_order feature keeps them in
+// insertion order rather than sorting them
+fn json_object(names: &[String], row: &[Value]) -> serde_json::Map<String, serde_json::Value> {
+    names
+        .iter()
+        .cloned()
+        .zip(row.iter().map(Value::to_json))
+        .collect()
+}
+
+fn json_error(err: serde_json::Error) -> AppError {
+    AppError::internal(format!("Can't write the export: {}", err))
+}
+
+fn parquet_error(err: parquet::errors::ParquetError) -> AppError {
+    AppError::internal(format!("Can't write the export: {}", err))
+}
+
+fn arrow_error(err: arrow_schema::ArrowError) -> AppError {
+    AppError::internal(format!("Can't write the export: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use serde_json::json;
+
+    use super::*;
+
+    // Customers with a serial key, orders pointing at them and a pair of tables
+    // referencing each other
+    fn tables() -> Vec<Table> {
+        let reference = |name: &str, table: &str| {
+            json!({
+                "fieldname": name,
+                "data_type": "REFERENCE",
+                "config": {"foreign_key": {"table": table, "field": "id"}}
+            })
+        };
+        let id =
+            json!({"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}});
+        serde_json::from_value(json!([
+            {"tablename": "orders", "datasize": 5, "fields": [
+                id, reference("customer", "customers"),
+                {"fieldname": "total", "data_type": "Int", "config": {"min_length": 1, "max_length": 9}}
+            ]},
+            {"tablename": "customers", "datasize": 3, "fields": [
+                id, {"fieldname": "name", "data_type": "FirstName"}
+            ]},
+            {"tablename": "employees", "datasize": 4, "fields": [
+                id, reference("department", "departments")
+            ]},
+            {"tablename": "departments", "datasize": 2, "fields": [
+                id, reference("manager", "employees")
+            ]}
+        ]))
+        .unwrap()
+    }
+
+    fn export(tables: &[Table], seed: u64, format: FileFormat, batch_size: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut exporter = Exporter {
+            tables,
+            seed,
+            keys: KeyStore::default(),
+        };
+        exporter.write(&mut out, format, batch_size).unwrap();
+        out
+    }
+
+    // Values of `field` in the rows of `table` in a json export
+    fn column<'a>(
+        document: &'a serde_json::Value,
+        table: &str,
+        field: &str,
+    ) -> Vec<&'a serde_json::Value> {
+        document[table]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| &row[field])
+            .collect()
+    }
+
+    #[test]
+    fn json_export_is_reproducible_and_keeps_references_valid() {
+        let tables = tables();
+        let out = export(&tables, 7, FileFormat::Json, 1);
+        assert_eq!(out, export(&tables, 7, FileFormat::Json, 1));
+        assert_ne!(out, export(&tables, 8, FileFormat::Json, 1));
+
+        let document: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let order: Vec<&String> = document["orders"][0].as_object().unwrap().keys().collect();
+        assert_eq!(order, ["id", "customer", "total"]);
+        for (table, parent, field) in [
+            ("orders", "customers", "customer"),
+            ("employees", "departments", "department"),
+            ("departments", "employees", "manager"),
+        ] {
+            let ids = column(&document, parent, "id");
+            assert_eq!(
+                ids.len(),
+                tables
+                    .iter()
+                    .find(|t| t.tablename == parent)
+                    .unwrap()
+                    .datasize
+            );
+            assert!(column(&document, table, field)
+                .iter()
+                .all(|value| ids.contains(value)));
+        }
+    }
+
+    #[test]
+    fn sql_export_batches_the_inserts() {
+        let out = String::from_utf8(export(&tables(), 7, FileFormat::Sql, 2)).unwrap();
+        assert!(out.starts_with("-- Generated by data_synth with seed 7\nBEGIN;"));
+        assert!(out.trim_end().ends_with("COMMIT;"));
+        // 5 orders in batches of 2, then 3 customers, 4 employees and 2 departments
+        assert_eq!(out.matches("INSERT INTO").count(), 3 + 2 + 2 + 1);
+        assert_eq!(out.matches("CREATE TABLE").count(), 4);
+        assert!(out.contains("SELECT setval(pg_get_serial_sequence('orders', 'id'), 5);"));
+        // Customers are created and filled before the orders pointing at them
+        assert!(
+            out.find("INSERT INTO \"customers\"").unwrap()
+                < out.find("INSERT INTO \"orders\"").unwrap()
+        );
+    }
+
+    #[test]
+    fn csv_export_holds_a_file_per_table() {
+        let out = export(&tables(), 7, FileFormat::Csv, 1);
+        let mut archive = zip::ZipArchive::new(Cursor::new(out)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 4);
+
+        let mut csv = String::new();
+        archive
+            .by_name("orders.csv")
+            .unwrap()
+            .read_to_string(&mut csv)
+            .unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "id,customer,total");
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].starts_with("1,"));
+    }
+
+    #[test]
+    fn csv_fields_tell_null_from_text() {
+        let text = |text: &str| csv_field(&Value::Text(text.to_string()));
+        assert_eq!(csv_field(&Value::Null), "\\N");
+        assert_eq!(text("\\N"), "\"\\N\"");
+        assert_eq!(text(""), "");
+        assert_eq!(text("plain"), "plain");
+        assert_eq!(text("a,\"b\"\nc"), "\"a,\"\"b\"\"\nc\"");
+        assert_eq!(csv_field(&Value::Int(-3)), "-3");
+    }
+
+    #[test]
+    fn arrow_export_keeps_the_column_types() {
+        let out = export(&tables(), 7, FileFormat::Arrow, 1);
+        let mut archive = zip::ZipArchive::new(Cursor::new(out)).unwrap();
+        let mut file = Vec::new();
+        archive
+            .by_name("orders.arrow")
+            .unwrap()
+            .read_to_end(&mut file)
+            .unwrap();
+        let reader = arrow_ipc::reader::FileReader::try_new(Cursor::new(file), None).unwrap();
+        let schema = reader.schema();
+        let types: Vec<&arrow_schema::DataType> =
+            schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(types, [&arrow_schema::DataType::Int32; 3]);
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 5);
+    }
+}
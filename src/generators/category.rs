@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, RngCore};
+
+use super::{Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::Field;
+
+// // ----- CATEGORICAL DATA TYPES ----- // //
+// Labels drawn from a fixed list. The database is told the list too, so the column
+// only ever holds one of them.
+
+pub fn register(registry: &mut Registry) {
+    registry.register(Enum);
+}
+
+// Longest enum label postgres accepts, in bytes
+const MAX_LABEL_LENGTH: usize = 63;
+
+// One of `values`, with the odds given by `weights`. Postgres columns get the native
+// `enum_type` when one is named, a CHECK constraint on the labels otherwise. Mysql has
+// its own ENUM columns and sqlite gets the CHECK.
+struct Enum;
+
+impl Enum {
+    fn values(field: &Field) -> &[String] {
+        field.config.values.as_deref().unwrap_or_default()
+    }
+
+    // Labels as a list of SQL literals, `'a', 'b'`
+    fn labels(field: &Field) -> String {
+        Enum::values(field)
+            .iter()
+            .map(|value| Value::Text(value.clone()).to_sql_literal())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+impl Generator for Enum {
+    fn name(&self) -> &'static str {
+        "Enum"
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        let values = Enum::values(field);
+        let longest = values.iter().map(|v| v.chars().count()).max();
+        match (dialect, &field.config.enum_type, longest) {
+            (Dialect::Postgres, Some(enum_type), _) => dialect.quote(enum_type),
+            (Dialect::Mysql, _, Some(_)) => format!("ENUM({})", Enum::labels(field)),
+            (Dialect::Postgres | Dialect::Mysql, _, _) => {
+                format!("VARCHAR({})", longest.unwrap_or(255).max(1))
+            }
+            (Dialect::Sqlite, _, _) => "TEXT".to_string(),
+            (Dialect::Mongodb, _, _) => "string".to_string(),
+        }
+    }
+
+    // A type that is already there is kept when it has the same labels, so tables can
+    // share one. Different labels are an error rather than failing inserts later on.
+    fn type_definition(&self, field: &Field, dialect: Dialect) -> Option<String> {
+        let enum_type = field.config.enum_type.as_ref()?;
+        if dialect != Dialect::Postgres {
+            return None;
+        }
+        let quoted = dialect.quote(enum_type);
+        let labels = Enum::labels(field);
+        Some(format!(
+            "DO $$ DECLARE labels text[]; BEGIN \
+             SELECT array_agg(enumlabel::text ORDER BY enumsortorder) INTO labels \
+             FROM pg_enum WHERE enumtypid = to_regtype({}); \
+             IF labels IS NULL THEN CREATE TYPE {} AS ENUM ({}); \
+             ELSIF labels <> ARRAY[{}]::text[] THEN \
+             RAISE EXCEPTION 'enum type {} already exists with the labels %, not %', \
+             labels, ARRAY[{}]::text[]; \
+             END IF; END $$",
+            Value::Text(quoted.clone()).to_sql_literal(),
+            quoted,
+            labels,
+            labels,
+            quoted,
+            labels
+        ))
+    }
+
+    fn column_constraint(&self, field: &Field, dialect: Dialect) -> Option<String> {
+        let check = match dialect {
+            Dialect::Postgres => field.config.enum_type.is_none(),
+            Dialect::Sqlite => true,
+            Dialect::Mysql | Dialect::Mongodb => false,
+        };
+        check.then(|| {
+            format!(
+                "CHECK ({} IN ({}))",
+                dialect.quote(&field.fieldname),
+                Enum::labels(field)
+            )
+        })
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        let values = Enum::values(field);
+        if values.is_empty() {
+            return Err("an Enum field needs the labels to draw from in values".to_string());
+        }
+        let mut seen = HashSet::new();
+        for value in values {
+            if !seen.insert(value) {
+                return Err(format!("value -> {} <- is listed more than once", value));
+            }
+            // `$` could close the dollar quotes of the type definition and mysql reads a
+            // `\` in its ENUM labels as an escape
+            if value.contains(['$', '\\']) {
+                return Err(format!("value -> {} <- can't contain $ or \\", value));
+            }
+            if field.config.enum_type.is_some()
+                && (value.is_empty() || value.len() > MAX_LABEL_LENGTH)
+            {
+                return Err(format!(
+                    "value -> {} <- must be 1 to {} bytes long to be an enum label",
+                    value, MAX_LABEL_LENGTH
+                ));
+            }
+        }
+
+        if let Some(weights) = &field.config.weights {
+            if weights.len() != values.len() {
+                return Err(format!(
+                    "weights has {} entries but values has {}",
+                    weights.len(),
+                    values.len()
+                ));
+            }
+            WeightedIndex::new(weights).map_err(|err| format!("invalid weights: {}", err))?;
+        }
+        Ok(())
+    }
+
+    // Labels with a weight of 0 are never drawn
+    fn value_space(&self, field: &Field) -> Option<u64> {
+        let drawn = match &field.config.weights {
+            Some(weights) => weights.iter().filter(|&&weight| weight > 0.0).count(),
+            None => Enum::values(field).len(),
+        };
+        Some(drawn as u64)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let values = Enum::values(field);
+        if values.is_empty() {
+            return Value::Null;
+        }
+        let index = match field.config.weights.as_ref().map(WeightedIndex::new) {
+            Some(Ok(weights)) => weights.sample(rng),
+            _ => rng.gen_range(0..values.len()),
+        };
+        Value::Text(values[index].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::generators::test_field;
+    use crate::seed::seeded_rng;
+
+    fn check(values: &[&str]) -> Result<(), String> {
+        Enum.validate(&test_field(
+            "Enum",
+            json!({"values": values, "enum_type": "mood"}),
+        ))
+    }
+
+    #[test]
+    fn rejects_labels_that_cant_be_told_apart() {
+        assert!(check(&["ok", "$$; DROP TABLE t; $$"]).is_err());
+        assert!(check(&["ok", "back\\slash"]).is_err());
+        assert!(check(&["ok", "ok"]).is_err());
+        assert!(check(&["ok", ""]).is_err());
+        assert!(check(&[]).is_err());
+        check(&["it's", "100%", "a \"b\""]).unwrap();
+
+        let field = test_field("Enum", json!({"values": ["a", "b"], "weights": [1.0]}));
+        assert!(Enum.validate(&field).unwrap_err().contains("weights"));
+    }
+
+    #[test]
+    fn weights_set_the_odds() {
+        let field = test_field(
+            "Enum",
+            json!({"values": ["never", "rare", "common"], "weights": [0.0, 1.0, 3.0]}),
+        );
+        let mut rng = seeded_rng(1);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            match Enum.generate(&field, &mut rng) {
+                Value::Text(label) if label == "never" => counts[0] += 1,
+                Value::Text(label) if label == "rare" => counts[1] += 1,
+                Value::Text(label) if label == "common" => counts[2] += 1,
+                value => panic!("unexpected {:?}", value),
+            }
+        }
+        assert_eq!(counts[0], 0);
+        assert!((2800..3200).contains(&counts[2]), "{:?}", counts);
+    }
+
+    #[test]
+    fn columns_only_hold_the_labels() {
+        let named = test_field(
+            "Enum",
+            json!({"values": ["a", "it's"], "enum_type": "Mood"}),
+        );
+        assert_eq!(Enum.sql_type(&named, Dialect::Postgres), "\"mood\"");
+        assert!(Enum.column_constraint(&named, Dialect::Postgres).is_none());
+        assert_eq!(Enum.sql_type(&named, Dialect::Mysql), "ENUM('a', 'it''s')");
+
+        let unnamed = test_field("Enum", json!({"values": ["a", "it's"]}));
+        assert_eq!(Enum.sql_type(&unnamed, Dialect::Postgres), "VARCHAR(4)");
+        assert_eq!(
+            Enum.column_constraint(&unnamed, Dialect::Sqlite).as_deref(),
+            Some("CHECK (\"f\" IN ('a', 'it''s'))")
+        );
+    }
+
+    #[test]
+    fn type_definition_checks_the_existing_labels() {
+        let field = test_field(
+            "Enum",
+            json!({"values": ["happy", "it's"], "enum_type": "mood"}),
+        );
+        let definition = Enum.type_definition(&field, Dialect::Postgres).unwrap();
+        assert!(definition.contains("ARRAY['happy', 'it''s']::text[]"));
+        assert!(definition.contains("RAISE EXCEPTION"));
+        assert!(Enum.type_definition(&field, Dialect::Mysql).is_none());
+    }
+}
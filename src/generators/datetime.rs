@@ -0,0 +1,156 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use fake::faker::chrono::en::{
+    Date, DateTime as AnyDateTime, DateTimeAfter, DateTimeBefore, DateTimeBetween, Time,
+};
+use fake::Fake;
+use rand::RngCore;
+
+use super::{Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::Field;
+
+// // ----- DATE AND TIME DATA TYPES ----- // //
+// `date` and `date_end` configs are written as "YYYY-MM-DD HH:MM:SS" in UTC
+
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+pub(super) const DEFAULT_START: &str = "2001-05-05 05:05:01";
+const DEFAULT_END: &str = "2023-04-14 05:05:01";
+
+pub fn register(registry: &mut Registry) {
+    registry.register(TimeOfDay);
+    registry.register(Day);
+    registry.register(Timestamp {
+        name: "DateTime",
+        check: |_| Ok(()),
+        fake: |_, rng| AnyDateTime().fake_with_rng(rng),
+    });
+    registry.register(Timestamp {
+        name: "DateTimeBefore",
+        check: |field| parse_config_date(&field.config.date, DEFAULT_END).map(|_| ()),
+        fake: |field, rng| {
+            let dt_before = parse_config_date(&field.config.date, DEFAULT_END)
+                .expect("date is checked before generation");
+            DateTimeBefore(dt_before).fake_with_rng(rng)
+        },
+    });
+    registry.register(Timestamp {
+        name: "DateTimeAfter",
+        check: |field| parse_config_date(&field.config.date, DEFAULT_END).map(|_| ()),
+        fake: |field, rng| {
+            let dt_after = parse_config_date(&field.config.date, DEFAULT_END)
+                .expect("date is checked before generation");
+            DateTimeAfter(dt_after).fake_with_rng(rng)
+        },
+    });
+    registry.register(Timestamp {
+        name: "DateTimeBetween",
+        check: |field| between_bounds(field).map(|_| ()),
+        fake: |field, rng| {
+            let (dt_start, dt_end) =
+                between_bounds(field).expect("dates are checked before generation");
+            DateTimeBetween(dt_start, dt_end).fake_with_rng(rng)
+        },
+    });
+}
+
+// Parses a `date` / `date_end` config, using `default` when it's missing
+pub(super) fn parse_config_date(
+    date: &Option<String>,
+    default: &str,
+) -> Result<DateTime<Utc>, String> {
+    let date = date.as_deref().unwrap_or(default);
+    NaiveDateTime::parse_from_str(date, DATETIME_FORMAT)
+        .map(|naive| naive.and_utc())
+        .map_err(|err| format!("Can't parse -> {} <- as {}: {}", date, DATETIME_FORMAT, err))
+}
+
+fn between_bounds(field: &Field) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let dt_start = parse_config_date(&field.config.date, DEFAULT_START)?;
+    let dt_end = parse_config_date(&field.config.date_end, DEFAULT_END)?;
+    if dt_start >= dt_end {
+        return Err(format!(
+            "date ({}) must be before date_end ({})",
+            dt_start, dt_end
+        ));
+    }
+    Ok((dt_start, dt_end))
+}
+
+struct TimeOfDay;
+
+impl Generator for TimeOfDay {
+    fn name(&self) -> &'static str {
+        "Time"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Postgres | Dialect::Mysql => "TIME".to_string(),
+            Dialect::Sqlite => "TEXT".to_string(),
+            // BSON has no time of day type
+            Dialect::Mongodb => "string".to_string(),
+        }
+    }
+
+    fn generate(&self, _field: &Field, rng: &mut dyn RngCore) -> Value {
+        let fake_time: NaiveTime = Time().fake_with_rng(rng);
+        Value::Time(fake_time)
+    }
+}
+
+struct Day;
+
+impl Generator for Day {
+    fn name(&self) -> &'static str {
+        "Date"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Postgres | Dialect::Mysql => "DATE".to_string(),
+            Dialect::Sqlite => "TEXT".to_string(),
+            Dialect::Mongodb => "date".to_string(),
+        }
+    }
+
+    fn generate(&self, _field: &Field, rng: &mut dyn RngCore) -> Value {
+        let fake_date: NaiveDate = Date().fake_with_rng(rng);
+        Value::Date(fake_date)
+    }
+}
+
+pub(super) fn timestamp_type(dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Postgres => "TIMESTAMP".to_string(),
+        // Microseconds like postgres, TIMESTAMP only covers 1970 to 2038 in mysql
+        Dialect::Mysql => "DATETIME(6)".to_string(),
+        // The ISO text the date and time functions of sqlite read
+        Dialect::Sqlite => "TEXT".to_string(),
+        Dialect::Mongodb => "date".to_string(),
+    }
+}
+
+// TIMESTAMP column, the value is stored in UTC
+struct Timestamp {
+    name: &'static str,
+    check: fn(&Field) -> Result<(), String>,
+    fake: fn(&Field, &mut dyn RngCore) -> DateTime<Utc>,
+}
+
+impl Generator for Timestamp {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        timestamp_type(dialect)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        (self.check)(field)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        Value::Timestamp((self.fake)(field, rng).naive_utc())
+    }
+}
@@ -0,0 +1,192 @@
+use std::f64::consts::PI;
+
+use rand::{Rng, RngCore};
+
+// // ----- DISTRIBUTIONS ----- // //
+// Samplers behind the `distribution` config of the numeric data types. Each one draws
+// from `rng` only, so a seeded run gives back the same numbers.
+
+// Below this mean poisson draws multiply uniforms, above it they use rejection
+const POISSON_SMALL_MEAN: f64 = 30.0;
+
+// Uniform in (0, 1], safe to take the log of
+fn open_unit(rng: &mut dyn RngCore) -> f64 {
+    1.0 - rng.gen::<f64>()
+}
+
+// Box-Muller transform
+pub fn normal(rng: &mut dyn RngCore, mean: f64, stddev: f64) -> f64 {
+    let radius = (-2.0 * open_unit(rng).ln()).sqrt();
+    let angle = 2.0 * PI * rng.gen::<f64>();
+    mean + stddev * radius * angle.cos()
+}
+
+// `mean` and `stddev` are those of the drawn values, not of their logarithm
+pub fn log_normal(rng: &mut dyn RngCore, mean: f64, stddev: f64) -> f64 {
+    let variance = (1.0 + (stddev / mean).powi(2)).ln();
+    normal(rng, mean.ln() - variance / 2.0, variance.sqrt()).exp()
+}
+
+pub fn exponential(rng: &mut dyn RngCore, mean: f64) -> f64 {
+    -mean * open_unit(rng).ln()
+}
+
+// Knuth's multiplication for small means, Hörmann's transformed rejection (PTRS) above
+pub fn poisson(rng: &mut dyn RngCore, mean: f64) -> f64 {
+    if mean < POISSON_SMALL_MEAN {
+        let limit = (-mean).exp();
+        let mut count = 0.0;
+        let mut product = rng.gen::<f64>();
+        while product > limit {
+            count += 1.0;
+            product *= rng.gen::<f64>();
+        }
+        return count;
+    }
+
+    let sqrt_mean = mean.sqrt();
+    let log_mean = mean.ln();
+    let b = 0.931 + 2.53 * sqrt_mean;
+    let a = -0.059 + 0.02483 * b;
+    let inv_alpha = 1.1239 + 1.1328 / (b - 3.4);
+    let v_r = 0.9277 - 3.6224 / (b - 2.0);
+    loop {
+        let u = rng.gen::<f64>() - 0.5;
+        let v = rng.gen::<f64>();
+        let us = 0.5 - u.abs();
+        let count = ((2.0 * a / us + b) * u + mean + 0.43).floor();
+        if us >= 0.07 && v <= v_r {
+            return count;
+        }
+        if count < 0.0 || (us < 0.013 && v > us) {
+            continue;
+        }
+        if v.ln() + inv_alpha.ln() - (a / (us * us) + b).ln()
+            <= -mean + count * log_mean - log_factorial(count)
+        {
+            return count;
+        }
+    }
+}
+
+// ln(n!), summed for small n and from Stirling's series above
+fn log_factorial(n: f64) -> f64 {
+    if n < 10.0 {
+        return (2..=n as u64).map(|k| (k as f64).ln()).sum();
+    }
+    n * n.ln() - n + 0.5 * (2.0 * PI * n).ln() + 1.0 / (12.0 * n) - 1.0 / (360.0 * n.powi(3))
+}
+
+// Rank in 1..=ranks, rank k drawn with odds 1 / k^exponent. Rejection-inversion from
+// Hörmann and Derflinger, so it runs in constant time whatever the number of ranks.
+pub fn zipf(rng: &mut dyn RngCore, ranks: f64, exponent: f64) -> f64 {
+    let q = if exponent != 1.0 {
+        1.0 / (1.0 - exponent)
+    } else {
+        1.0
+    };
+    let t = if exponent != 1.0 {
+        (ranks.powf(1.0 - exponent) - exponent) * q
+    } else {
+        1.0 + ranks.ln()
+    };
+    loop {
+        let pt = rng.gen::<f64>() * t;
+        let inv_b = if pt <= 1.0 {
+            pt
+        } else if exponent != 1.0 {
+            (pt * (1.0 - exponent) + exponent).powf(q)
+        } else {
+            (pt - 1.0).exp()
+        };
+        let rank = (inv_b + 1.0).floor();
+        let mut ratio = rank.powf(-exponent);
+        if rank > 1.0 {
+            ratio *= inv_b.powf(exponent);
+        }
+        if rng.gen::<f64>() < ratio {
+            return rank.min(ranks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::seeded_rng;
+
+    const DRAWS: usize = 20_000;
+
+    fn mean_and_variance(draws: &[f64]) -> (f64, f64) {
+        let mean = draws.iter().sum::<f64>() / draws.len() as f64;
+        let variance = draws.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / draws.len() as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn poisson_matches_its_mean_and_variance() {
+        let mut rng = seeded_rng(1);
+        // Both sides of the switch from multiplication to rejection
+        for expected in [0.5, 3.0, 29.0, 31.0, 5000.0] {
+            let draws: Vec<f64> = (0..DRAWS).map(|_| poisson(&mut rng, expected)).collect();
+            assert!(draws.iter().all(|x| *x >= 0.0 && x.fract() == 0.0));
+            let (mean, variance) = mean_and_variance(&draws);
+            assert!(
+                (mean - expected).abs() < 0.05 * expected.max(1.0),
+                "mean {} for {}",
+                mean,
+                expected
+            );
+            assert!(
+                (variance - expected).abs() < 0.1 * expected.max(1.0),
+                "variance {} for {}",
+                variance,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn zipf_ranks_follow_their_odds() {
+        let mut rng = seeded_rng(1);
+        for (ranks, exponent) in [(1000.0, 1.1), (10.0, 1.0), (5.0, 0.5)] {
+            let mut counts = vec![0usize; ranks as usize];
+            for _ in 0..DRAWS {
+                let rank = zipf(&mut rng, ranks, exponent);
+                assert!(rank >= 1.0 && rank <= ranks && rank.fract() == 0.0);
+                counts[rank as usize - 1] += 1;
+            }
+            let total: f64 = (1..=ranks as usize)
+                .map(|k| (k as f64).powf(-exponent))
+                .sum();
+            for k in [1, 2, ranks as usize] {
+                let expected = (k as f64).powf(-exponent) / total;
+                let observed = counts[k - 1] as f64 / DRAWS as f64;
+                assert!(
+                    (observed - expected).abs() < 0.01,
+                    "rank {} of {} drawn {} of the time, not {}",
+                    k,
+                    ranks,
+                    observed,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn log_normal_matches_the_mean_of_its_values() {
+        let mut rng = seeded_rng(1);
+        let draws: Vec<f64> = (0..DRAWS)
+            .map(|_| log_normal(&mut rng, 80.0, 40.0))
+            .collect();
+        assert!(draws.iter().all(|x| *x > 0.0));
+        let (mean, variance) = mean_and_variance(&draws);
+        assert!((mean - 80.0).abs() < 2.0, "mean {}", mean);
+        assert!(
+            (variance.sqrt() - 40.0).abs() < 3.0,
+            "stddev {}",
+            variance.sqrt()
+        );
+    }
+}
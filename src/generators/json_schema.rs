@@ -0,0 +1,703 @@
+use chrono::{Duration, NaiveDate};
+use fake::faker::internet::en::{DomainSuffix, FreeEmailProvider, IPv4, IPv6, Username};
+use fake::faker::lorem::en::Word;
+use fake::Fake;
+use rand::distributions::Distribution;
+use rand::{Rng, RngCore};
+use serde_json::{Map, Value as JsonValue};
+
+use super::{compile_pattern, json_type, Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::Field;
+
+// // ----- JSON SCHEMA DATA TYPE ----- // //
+// `Json` fields hold documents conforming to the JSON Schema in their `schema` config.
+// Supported keywords:
+//   type (one or a list), enum, const, anyOf / oneOf (one branch is picked), allOf
+//   (merged), $ref to `#/definitions/...` or `#/$defs/...`,
+//   object: properties, required (optional properties are present half of the time)
+//   array: items, prefixItems, minItems, maxItems, uniqueItems
+//   string: minLength, maxLength, pattern, format (date-time, date, time, email, uuid,
+//   uri, hostname, ipv4, ipv6)
+//   integer / number: minimum, maximum, exclusiveMinimum, exclusiveMaximum, multipleOf
+// Other keywords are ignored, nothing outside `properties` is ever added to an object.
+
+pub fn register(registry: &mut Registry) {
+    registry.register(Json);
+}
+
+const TYPES: [&str; 7] = [
+    "object", "array", "string", "integer", "number", "boolean", "null",
+];
+
+// Past this nesting (recursive $refs) optional properties and items are left out
+const MAX_DEPTH: usize = 12;
+
+struct Json;
+
+impl Generator for Json {
+    fn name(&self) -> &'static str {
+        "Json"
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        let schema = field.config.schema.as_ref();
+        let bson_type = match schema.and_then(|s| s.get("type")) {
+            Some(JsonValue::String(t)) if t == "array" => "array",
+            _ => "object",
+        };
+        json_type(dialect, bson_type)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        let Some(schema) = &field.config.schema else {
+            return Err(
+                "a Json field needs the JSON Schema of its documents in schema".to_string(),
+            );
+        };
+        check_schema(schema, schema, "#")
+            .map_err(|(pointer, message)| format!("schema at {}: {}", pointer, message))
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        match &field.config.schema {
+            Some(schema) => Value::Json(SchemaGenerator { root: schema }.generate(schema, 0, rng)),
+            None => Value::Null,
+        }
+    }
+}
+
+// Resolves a local `$ref`, None when it points outside the document or at nothing
+fn resolve_ref<'a>(root: &'a JsonValue, reference: &str) -> Option<&'a JsonValue> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn number(schema: &JsonValue, keyword: &str) -> Option<f64> {
+    schema.get(keyword).and_then(JsonValue::as_f64)
+}
+
+fn count(schema: &JsonValue, keyword: &str) -> Option<usize> {
+    schema
+        .get(keyword)
+        .and_then(JsonValue::as_u64)
+        .map(|n| n as usize)
+}
+
+// Lower and upper bound of a number schema, exclusive bounds as in draft 6 and later
+fn number_bounds(schema: &JsonValue) -> (f64, bool, f64, bool) {
+    let (minimum, min_exclusive) = match number(schema, "exclusiveMinimum") {
+        Some(n) => (Some(n), true),
+        None => (number(schema, "minimum"), false),
+    };
+    let (maximum, max_exclusive) = match number(schema, "exclusiveMaximum") {
+        Some(n) => (Some(n), true),
+        None => (number(schema, "maximum"), false),
+    };
+    let (minimum, maximum) = match (minimum, maximum) {
+        (Some(min), Some(max)) => (min, max),
+        (Some(min), None) => (min, min + 1000.0),
+        (None, Some(max)) => (max - 1000.0, max),
+        (None, None) => (0.0, 1000.0),
+    };
+    (minimum, min_exclusive, maximum, max_exclusive)
+}
+
+// Integer bounds, inclusive
+fn integer_bounds(schema: &JsonValue) -> (i64, i64) {
+    let (minimum, min_exclusive, maximum, max_exclusive) = number_bounds(schema);
+    let minimum = if min_exclusive {
+        minimum.floor() as i64 + 1
+    } else {
+        minimum.ceil() as i64
+    };
+    let maximum = if max_exclusive {
+        maximum.ceil() as i64 - 1
+    } else {
+        maximum.floor() as i64
+    };
+    (minimum, maximum)
+}
+
+// Bounds of `n` such that `n * multiple_of` is inside `minimum..=maximum`
+fn multiples(minimum: f64, maximum: f64, multiple_of: f64) -> (i64, i64) {
+    (
+        (minimum / multiple_of).ceil() as i64,
+        (maximum / multiple_of).floor() as i64,
+    )
+}
+
+fn length_bounds(schema: &JsonValue) -> (usize, usize) {
+    let min_length = count(schema, "minLength").unwrap_or(0);
+    let max_length = count(schema, "maxLength").unwrap_or(min_length.max(3) + 17);
+    (min_length, max_length)
+}
+
+fn item_bounds(schema: &JsonValue) -> (usize, usize) {
+    let min_items = count(schema, "minItems").unwrap_or(0);
+    let max_items = count(schema, "maxItems").unwrap_or(min_items + 5);
+    (min_items, max_items)
+}
+
+// The types a schema allows, guessed from its keywords when `type` is missing
+fn schema_types(schema: &JsonValue) -> Vec<&str> {
+    match schema.get("type") {
+        Some(JsonValue::String(t)) => vec![t.as_str()],
+        Some(JsonValue::Array(types)) => types.iter().filter_map(JsonValue::as_str).collect(),
+        _ if schema.get("properties").is_some() || schema.get("required").is_some() => {
+            vec!["object"]
+        }
+        _ if schema.get("items").is_some() || schema.get("prefixItems").is_some() => {
+            vec!["array"]
+        }
+        _ if [
+            "minimum",
+            "maximum",
+            "exclusiveMinimum",
+            "exclusiveMaximum",
+            "multipleOf",
+        ]
+        .iter()
+        .any(|keyword| schema.get(keyword).is_some()) =>
+        {
+            vec!["number"]
+        }
+        _ => vec!["string"],
+    }
+}
+
+// `allOf` branches merged into their parent: properties and required are combined,
+// the other keywords of later branches win. Branches nested past MAX_DEPTH are left as
+// they are, `check_schema` rejects the $ref cycles that would get there.
+fn merge_all_of(schema: &JsonValue, root: &JsonValue, depth: usize) -> JsonValue {
+    let Some(JsonValue::Array(branches)) = schema.get("allOf") else {
+        return schema.clone();
+    };
+    if depth > MAX_DEPTH {
+        return schema.clone();
+    }
+    let mut merged = schema.as_object().cloned().unwrap_or_default();
+    merged.remove("allOf");
+    for branch in branches {
+        let branch = match branch.get("$ref").and_then(JsonValue::as_str) {
+            Some(reference) => resolve_ref(root, reference).unwrap_or(branch),
+            None => branch,
+        };
+        let branch = merge_all_of(branch, root, depth + 1);
+        let Some(branch) = branch.as_object() else {
+            continue;
+        };
+        for (keyword, value) in branch {
+            match (keyword.as_str(), merged.get_mut(keyword), value) {
+                ("properties", Some(JsonValue::Object(properties)), JsonValue::Object(more)) => {
+                    properties.extend(more.clone());
+                }
+                ("required", Some(JsonValue::Array(required)), JsonValue::Array(more)) => {
+                    required.extend(
+                        more.iter()
+                            .filter(|name| !required.contains(name))
+                            .cloned()
+                            .collect::<Vec<_>>(),
+                    );
+                }
+                _ => {
+                    merged.insert(keyword.clone(), value.clone());
+                }
+            }
+        }
+    }
+    JsonValue::Object(merged)
+}
+
+struct SchemaGenerator<'a> {
+    root: &'a JsonValue,
+}
+
+impl SchemaGenerator<'_> {
+    fn generate(&self, schema: &JsonValue, depth: usize, rng: &mut dyn RngCore) -> JsonValue {
+        // Only reachable through a $ref cycle that always recurses
+        if depth > 2 * MAX_DEPTH {
+            return JsonValue::Null;
+        }
+        let schema = match schema {
+            JsonValue::Object(_) => schema,
+            // `true` accepts anything, `false` nothing
+            JsonValue::Bool(true) => return JsonValue::String(Word().fake_with_rng(rng)),
+            _ => return JsonValue::Null,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(JsonValue::as_str) {
+            return match resolve_ref(self.root, reference) {
+                Some(target) => self.generate(target, depth + 1, rng),
+                None => JsonValue::Null,
+            };
+        }
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        if let Some(JsonValue::Array(values)) = schema.get("enum") {
+            return values[rng.gen_range(0..values.len())].clone();
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(JsonValue::Array(branches)) = schema.get(keyword) {
+                let branch = &branches[rng.gen_range(0..branches.len())];
+                return self.generate(branch, depth + 1, rng);
+            }
+        }
+        if schema.get("allOf").is_some() {
+            let merged = merge_all_of(schema, self.root, depth);
+            return self.generate(&merged, depth + 1, rng);
+        }
+
+        let types = schema_types(schema);
+        match types[rng.gen_range(0..types.len())] {
+            "object" => self.generate_object(schema, depth, rng),
+            "array" => self.generate_array(schema, depth, rng),
+            "integer" => generate_integer(schema, rng),
+            "number" => generate_number(schema, rng),
+            "boolean" => JsonValue::Bool(rng.gen()),
+            "null" => JsonValue::Null,
+            _ => JsonValue::String(generate_string(schema, rng)),
+        }
+    }
+
+    fn generate_object(
+        &self,
+        schema: &JsonValue,
+        depth: usize,
+        rng: &mut dyn RngCore,
+    ) -> JsonValue {
+        let required: Vec<&str> = match schema.get("required") {
+            Some(JsonValue::Array(names)) => names.iter().filter_map(JsonValue::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let mut object = Map::new();
+        if let Some(JsonValue::Object(properties)) = schema.get("properties") {
+            for (name, property) in properties {
+                let present =
+                    required.contains(&name.as_str()) || (depth < MAX_DEPTH && rng.gen_bool(0.5));
+                if present {
+                    object.insert(name.clone(), self.generate(property, depth + 1, rng));
+                }
+            }
+        }
+        // Required without a schema, any value will do
+        for name in required {
+            if !object.contains_key(name) {
+                object.insert(
+                    name.to_string(),
+                    JsonValue::String(Word().fake_with_rng(rng)),
+                );
+            }
+        }
+        JsonValue::Object(object)
+    }
+
+    fn generate_array(&self, schema: &JsonValue, depth: usize, rng: &mut dyn RngCore) -> JsonValue {
+        let (min_items, max_items) = item_bounds(schema);
+        let count = if depth < MAX_DEPTH {
+            rng.gen_range(min_items..=max_items)
+        } else {
+            min_items
+        };
+        let prefix_items = match schema.get("prefixItems") {
+            Some(JsonValue::Array(prefix)) => prefix.as_slice(),
+            _ => &[],
+        };
+        let items = schema.get("items").unwrap_or(&JsonValue::Bool(true));
+        let unique = schema.get("uniqueItems") == Some(&JsonValue::Bool(true));
+
+        let mut array: Vec<JsonValue> = Vec::with_capacity(count);
+        for i in 0..count {
+            let item_schema = prefix_items.get(i).unwrap_or(items);
+            // A few draws to find an item not in the array yet, small enums run out
+            let item = (0..10)
+                .map(|_| self.generate(item_schema, depth + 1, rng))
+                .find(|item| !unique || !array.contains(item));
+            match item {
+                Some(item) => array.push(item),
+                None => break,
+            }
+        }
+        JsonValue::Array(array)
+    }
+}
+
+fn generate_integer(schema: &JsonValue, rng: &mut dyn RngCore) -> JsonValue {
+    let (minimum, maximum) = integer_bounds(schema);
+    let value = match number(schema, "multipleOf") {
+        Some(multiple_of) => {
+            let (low, high) = multiples(minimum as f64, maximum as f64, multiple_of);
+            (rng.gen_range(low..=high) as f64 * multiple_of) as i64
+        }
+        None => rng.gen_range(minimum..=maximum),
+    };
+    value.into()
+}
+
+fn generate_number(schema: &JsonValue, rng: &mut dyn RngCore) -> JsonValue {
+    let (minimum, min_exclusive, maximum, max_exclusive) = number_bounds(schema);
+    let value = match number(schema, "multipleOf") {
+        Some(multiple_of) => {
+            let (low, high) = multiples(minimum, maximum, multiple_of);
+            let n = rng.gen_range(low..=high);
+            let n = if min_exclusive && n as f64 * multiple_of <= minimum {
+                n + 1
+            } else {
+                n
+            };
+            let n = if max_exclusive && n as f64 * multiple_of >= maximum {
+                n - 1
+            } else {
+                n
+            };
+            n as f64 * multiple_of
+        }
+        None => {
+            let value = rng.gen_range(minimum..=maximum);
+            // Two decimals read better, unless rounding leaves the bounds
+            let rounded = (value * 100.0).round() / 100.0;
+            let inside = (rounded > minimum || (!min_exclusive && rounded == minimum))
+                && (rounded < maximum || (!max_exclusive && rounded == maximum));
+            if inside {
+                rounded
+            } else {
+                value
+            }
+        }
+    };
+    serde_json::Number::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+}
+
+fn generate_string(schema: &JsonValue, rng: &mut dyn RngCore) -> String {
+    if let Some(pattern) = schema.get("pattern").and_then(JsonValue::as_str) {
+        if let Ok(regex) = compile_pattern(pattern) {
+            return regex.sample(rng);
+        }
+    }
+    if let Some(text) = schema
+        .get("format")
+        .and_then(JsonValue::as_str)
+        .and_then(|format| generate_format(format, rng))
+    {
+        return text;
+    }
+
+    // Words up to a length inside the bounds
+    let (min_length, max_length) = length_bounds(schema);
+    let length = rng.gen_range(min_length..=max_length);
+    let mut text = String::new();
+    while text.chars().count() < length {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(Word().fake_with_rng(rng));
+    }
+    // A cut right after a word leaves a trailing space, it becomes a letter
+    let mut text: String = text.chars().take(length).collect();
+    if text.ends_with(' ') {
+        text.pop();
+        text.push(rng.gen_range('a'..='z'));
+    }
+    text
+}
+
+fn generate_format(format: &str, rng: &mut dyn RngCore) -> Option<String> {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?.and_hms_opt(0, 0, 0)?;
+    let timestamp = epoch + Duration::seconds(rng.gen_range(0..30 * 365 * 24 * 3600));
+    let text = match format {
+        "date-time" => timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        "date" => timestamp.format("%Y-%m-%d").to_string(),
+        "time" => timestamp.format("%H:%M:%S").to_string(),
+        "email" => format!(
+            "{}@{}",
+            Username().fake_with_rng::<String, _>(rng),
+            FreeEmailProvider().fake_with_rng::<String, _>(rng)
+        ),
+        "uuid" => uuid::Builder::from_random_bytes(rng.gen())
+            .into_uuid()
+            .to_string(),
+        "hostname" => format!(
+            "{}.{}",
+            Word().fake_with_rng::<String, _>(rng),
+            DomainSuffix().fake_with_rng::<String, _>(rng)
+        ),
+        "uri" => format!(
+            "https://{}.{}/{}",
+            Word().fake_with_rng::<String, _>(rng),
+            DomainSuffix().fake_with_rng::<String, _>(rng),
+            Word().fake_with_rng::<String, _>(rng)
+        ),
+        "ipv4" => IPv4().fake_with_rng(rng),
+        "ipv6" => IPv6().fake_with_rng(rng),
+        _ => return None,
+    };
+    Some(text)
+}
+
+// Checks the keywords `generate` relies on, errors carry the JSON pointer of the subschema
+fn check_schema(
+    root: &JsonValue,
+    schema: &JsonValue,
+    pointer: &str,
+) -> Result<(), (String, String)> {
+    let error = |message: String| Err((pointer.to_string(), message));
+    check_ref_cycle(root, schema, &mut Vec::new())
+        .map_err(|message| (pointer.to_string(), message))?;
+    let schema = match schema {
+        JsonValue::Object(schema) => schema,
+        JsonValue::Bool(_) => return Ok(()),
+        _ => return error("a schema must be an object or a boolean".to_string()),
+    };
+
+    if let Some(reference) = schema.get("$ref") {
+        let resolved = reference.as_str().and_then(|r| resolve_ref(root, r));
+        if resolved.is_none() {
+            return error(format!(
+                "$ref {} doesn't point inside the schema",
+                reference
+            ));
+        }
+    }
+
+    match schema.get("type") {
+        None => {}
+        Some(JsonValue::String(t)) if TYPES.contains(&t.as_str()) => {}
+        Some(JsonValue::Array(types))
+            if !types.is_empty()
+                && types
+                    .iter()
+                    .all(|t| t.as_str().is_some_and(|t| TYPES.contains(&t))) => {}
+        Some(t) => {
+            return error(format!(
+                "unknown type {}, expected one of {}",
+                t,
+                TYPES.join(", ")
+            ))
+        }
+    }
+
+    for keyword in ["enum", "anyOf", "oneOf", "allOf"] {
+        match schema.get(keyword) {
+            None => {}
+            Some(JsonValue::Array(values)) if !values.is_empty() => {}
+            Some(_) => return error(format!("{} must be a non empty array", keyword)),
+        }
+    }
+
+    let schema_value = JsonValue::Object(schema.clone());
+    if let Some(pattern) = schema.get("pattern") {
+        let Some(pattern) = pattern.as_str() else {
+            return error("pattern must be a string".to_string());
+        };
+        compile_pattern(pattern).map_err(|message| (pointer.to_string(), message))?;
+    }
+    let (min_length, max_length) = length_bounds(&schema_value);
+    if min_length > max_length {
+        return error(format!(
+            "minLength ({}) can't be above maxLength ({})",
+            min_length, max_length
+        ));
+    }
+    let (min_items, max_items) = item_bounds(&schema_value);
+    if min_items > max_items {
+        return error(format!(
+            "minItems ({}) can't be above maxItems ({})",
+            min_items, max_items
+        ));
+    }
+    let types = schema_types(&schema_value);
+    let (minimum, min_exclusive, maximum, max_exclusive) = number_bounds(&schema_value);
+    let multiple_of = number(&schema_value, "multipleOf");
+    if multiple_of.is_some_and(|m| m <= 0.0) {
+        return error("multipleOf must be above 0".to_string());
+    }
+    if types.contains(&"integer") {
+        let (minimum, maximum) = integer_bounds(&schema_value);
+        let empty = match multiple_of {
+            Some(m) => {
+                let (low, high) = multiples(minimum as f64, maximum as f64, m);
+                low > high
+            }
+            None => minimum > maximum,
+        };
+        if empty {
+            return error("no integer is inside minimum and maximum".to_string());
+        }
+    }
+    let numeric = types.contains(&"number") || types.contains(&"integer");
+    if numeric && !(maximum - minimum).is_finite() {
+        return error(format!(
+            "minimum ({}) and maximum ({}) are too far apart",
+            minimum, maximum
+        ));
+    }
+    if numeric && multiple_of.is_some_and(|m| !((maximum - minimum) / m).is_finite()) {
+        return error("multipleOf is too small for minimum and maximum".to_string());
+    }
+    if types.contains(&"number")
+        && (minimum > maximum || (minimum == maximum && (min_exclusive || max_exclusive)))
+    {
+        return error(format!(
+            "no number is inside minimum ({}) and maximum ({})",
+            minimum, maximum
+        ));
+    }
+
+    // Subschemas
+    for keyword in ["properties", "definitions", "$defs"] {
+        if let Some(JsonValue::Object(subschemas)) = schema.get(keyword) {
+            for (name, subschema) in subschemas {
+                check_schema(
+                    root,
+                    subschema,
+                    &format!("{}/{}/{}", pointer, keyword, name),
+                )?;
+            }
+        }
+    }
+    for keyword in ["anyOf", "oneOf", "allOf", "prefixItems"] {
+        if let Some(JsonValue::Array(subschemas)) = schema.get(keyword) {
+            for (i, subschema) in subschemas.iter().enumerate() {
+                check_schema(root, subschema, &format!("{}/{}/{}", pointer, keyword, i))?;
+            }
+        }
+    }
+    if let Some(items) = schema.get("items") {
+        check_schema(root, items, &format!("{}/items", pointer))?;
+    }
+    Ok(())
+}
+
+// Follows the `$ref`s a schema resolves to without nesting into a property or an item.
+// A $ref met twice on the way is a cycle generation would never get out of.
+fn check_ref_cycle<'a>(
+    root: &'a JsonValue,
+    schema: &'a JsonValue,
+    chain: &mut Vec<&'a str>,
+) -> Result<(), String> {
+    if let Some(reference) = schema.get("$ref").and_then(JsonValue::as_str) {
+        if chain.contains(&reference) {
+            return Err(format!(
+                "$ref {} leads back to itself through $ref and allOf",
+                reference
+            ));
+        }
+        if let Some(target) = resolve_ref(root, reference) {
+            chain.push(reference);
+            check_ref_cycle(root, target, chain)?;
+            chain.pop();
+        }
+    }
+    if let Some(JsonValue::Array(branches)) = schema.get("allOf") {
+        for branch in branches {
+            check_ref_cycle(root, branch, chain)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::generators::test_field;
+    use crate::seed::seeded_rng;
+
+    fn check(schema: JsonValue) -> Result<(), String> {
+        Json.validate(&test_field("Json", json!({ "schema": schema })))
+    }
+
+    fn generate(schema: JsonValue, count: usize) -> Vec<JsonValue> {
+        let field = test_field("Json", json!({ "schema": schema }));
+        let mut rng = seeded_rng(1);
+        (0..count)
+            .map(|_| match Json.generate(&field, &mut rng) {
+                Value::Json(document) => document,
+                value => panic!("expected a document, got {:?}", value),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rejects_ref_cycles() {
+        assert!(check(json!({"$ref": "#"}))
+            .unwrap_err()
+            .contains("leads back"));
+        let through_all_of = json!({
+            "$defs": {
+                "a": {"allOf": [{"$ref": "#/$defs/b"}]},
+                "b": {"allOf": [{"type": "object"}, {"$ref": "#/$defs/a"}]}
+            },
+            "$ref": "#/$defs/a"
+        });
+        assert!(check(through_all_of).unwrap_err().contains("leads back"));
+    }
+
+    #[test]
+    fn accepts_recursion_through_properties() {
+        let tree = json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+                    }
+                }
+            },
+            "$ref": "#/$defs/node"
+        });
+        check(tree.clone()).unwrap();
+        assert!(generate(tree, 20).iter().all(JsonValue::is_object));
+    }
+
+    #[test]
+    fn rejects_spans_without_values() {
+        let rejected = [
+            json!({"type": "number", "minimum": -1e308, "maximum": 1e308}),
+            json!({"type": "integer", "minimum": 5, "maximum": 1}),
+            json!({"type": "integer", "minimum": 5, "maximum": 7, "multipleOf": 4}),
+            json!({"type": "number", "minimum": 2, "maximum": 1}),
+            json!({"type": "number", "exclusiveMinimum": 1, "exclusiveMaximum": 1}),
+            json!({"type": "number", "minimum": 0, "maximum": 1e300, "multipleOf": 1e-300}),
+            json!({"type": "number", "multipleOf": 0}),
+        ];
+        for schema in rejected {
+            assert!(check(schema.clone()).is_err(), "{} was accepted", schema);
+        }
+    }
+
+    #[test]
+    fn objects_hold_their_required_properties() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"name": {"type": "string", "minLength": 2, "maxLength": 4}},
+            "required": ["name"]
+        });
+        check(schema.clone()).unwrap();
+        for document in generate(schema, 50) {
+            let name = document["name"].as_str().unwrap();
+            assert!((2..=4).contains(&name.chars().count()));
+        }
+    }
+
+    #[test]
+    fn numbers_stay_within_bounds() {
+        let integers = generate(
+            json!({"type": "integer", "minimum": 3, "maximum": 9, "multipleOf": 2}),
+            200,
+        );
+        assert!(integers
+            .iter()
+            .all(|v| [4, 6, 8].contains(&v.as_i64().unwrap())));
+
+        let numbers = generate(
+            json!({"type": "number", "exclusiveMinimum": 0, "maximum": 1}),
+            200,
+        );
+        assert!(numbers.iter().all(|v| {
+            let v = v.as_f64().unwrap();
+            v > 0.0 && v <= 1.0
+        }));
+    }
+}
@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+use fake::faker::lorem::en::{Paragraph, Sentence, Word};
+use fake::Fake;
+use rand::RngCore;
+
+use super::{check_exclusive_range, max_length, truncate, Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::Field;
+
+// // ----- LOREM TEXT DATA TYPES ----- // //
+// Filler text stored in TEXT columns
+
+pub fn register(registry: &mut Registry) {
+    registry.register(Lorem {
+        name: "Sentence",
+        min_length: 0,
+        max_length: 255,
+        chars_per_length: 1,
+        fake: |words, rng| Sentence(words).fake_with_rng(rng),
+    });
+    registry.register(Lorem {
+        name: "Sentences",
+        min_length: 5,
+        max_length: 25,
+        chars_per_length: 10,
+        fake: |words, rng| Sentence(words).fake_with_rng(rng),
+    });
+    registry.register(Lorem {
+        name: "Paragraph",
+        min_length: 5,
+        max_length: 25,
+        chars_per_length: 1,
+        fake: |sentences, rng| Paragraph(sentences).fake_with_rng(rng),
+    });
+    registry.register(Lorem {
+        name: "Paragraphs",
+        min_length: 5,
+        max_length: 55,
+        chars_per_length: 10,
+        fake: |sentences, rng| Paragraph(sentences).fake_with_rng(rng),
+    });
+    registry.register(Words);
+}
+
+fn text(dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Postgres | Dialect::Sqlite => "TEXT".to_string(),
+        Dialect::Mongodb => "string".to_string(),
+        // TEXT holds 64KB in mysql
+        Dialect::Mysql => "LONGTEXT".to_string(),
+    }
+}
+
+// Sentences or paragraphs with a length drawn from min_length..max_length,
+// cut to `max_length * chars_per_length` characters
+struct Lorem {
+    name: &'static str,
+    min_length: i32,
+    max_length: i32,
+    chars_per_length: i32,
+    fake: fn(Range<usize>, &mut dyn RngCore) -> String,
+}
+
+impl Lorem {
+    fn lengths(&self, field: &Field) -> (i32, i32) {
+        (
+            field.config.min_length.unwrap_or(self.min_length),
+            max_length(field, self.max_length),
+        )
+    }
+}
+
+impl Generator for Lorem {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        text(dialect)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        let (min_length, max_length) = self.lengths(field);
+        check_exclusive_range(min_length, max_length)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let (min_length, max_length) = self.lengths(field);
+        let fake_string = (self.fake)(min_length as usize..max_length as usize, rng);
+        truncate(fake_string, max_length * self.chars_per_length)
+    }
+}
+
+// max_length words (25 by default) separated by commas
+struct Words;
+
+impl Generator for Words {
+    fn name(&self) -> &'static str {
+        "Words"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        text(dialect)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let max_length = max_length(field, 25);
+        let words: Vec<String> = (0..max_length).map(|_| Word().fake_with_rng(rng)).collect();
+        truncate(words.join(", "), max_length * 10)
+    }
+}
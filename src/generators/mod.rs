@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use rand::{Rng, RngCore};
+
+use crate::dialect::Dialect;
+use crate::value::Value;
+use crate::Field;
+
+mod category;
+mod datetime;
+mod distribution;
+mod json_schema;
+mod lorem;
+mod nested;
+mod number;
+mod pattern;
+mod reference;
+mod sequence;
+mod text;
+
+// // ----- DATA TYPE GENERATORS ----- // //
+// Every `data_type` a schema can use is a `Generator` registered in the registry below.
+// A generator knows the column type it needs, which configs it accepts and how to
+// produce values, so adding a data type means writing one generator and registering it.
+
+pub trait Generator: Send + Sync {
+    // Name used as `data_type` in schemas
+    fn name(&self) -> &'static str;
+
+    // Column type backing the field in the given dialect
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String;
+
+    // Statement creating the type `sql_type` names, run before the table is created
+    fn type_definition(&self, _field: &Field, _dialect: Dialect) -> Option<String> {
+        None
+    }
+
+    // Constraint following the column type in the CREATE TABLE, e.g. a CHECK
+    fn column_constraint(&self, _field: &Field, _dialect: Dialect) -> Option<String> {
+        None
+    }
+
+    // Checks the field's config, runs before any table is created
+    fn validate(&self, _field: &Field) -> Result<(), String> {
+        Ok(())
+    }
+
+    // How many distinct values the field's config can produce, None when there are too
+    // many to run out of or they can't be counted. Unique fields need one per row.
+    fn value_space(&self, _field: &Field) -> Option<u64> {
+        None
+    }
+
+    // Columns the database fills on its own (serial keys) are left out of inserts
+    fn filled_by_database(&self) -> bool {
+        false
+    }
+
+    // Generators whose values follow each other, like sequences, have the rows of a table
+    // call `generate_next` in place of `generate`
+    fn sequential(&self) -> bool {
+        false
+    }
+
+    // Produces the value following `previous`, the last value of the column (None on the
+    // first row). An error ends the generation, e.g. a sequence running past its end.
+    fn generate_next(
+        &self,
+        field: &Field,
+        _previous: Option<&Value>,
+        rng: &mut dyn RngCore,
+    ) -> Result<Value, String> {
+        Ok(self.generate(field, rng))
+    }
+
+    // Produces one value for the field
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value;
+}
+
+pub struct Registry {
+    generators: BTreeMap<&'static str, Box<dyn Generator>>,
+}
+
+impl Registry {
+    fn with_builtin() -> Self {
+        let mut registry = Registry {
+            generators: BTreeMap::new(),
+        };
+        text::register(&mut registry);
+        category::register(&mut registry);
+        lorem::register(&mut registry);
+        nested::register(&mut registry);
+        number::register(&mut registry);
+        pattern::register(&mut registry);
+        datetime::register(&mut registry);
+        json_schema::register(&mut registry);
+        sequence::register(&mut registry);
+        reference::register(&mut registry);
+        registry
+    }
+
+    pub fn register(&mut self, generator: impl Generator + 'static) {
+        let name = generator.name();
+        if self.generators.insert(name, Box::new(generator)).is_some() {
+            panic!("Data type -> {} <- is registered twice", name);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Generator> {
+        self.generators.get(name).map(|g| g.as_ref())
+    }
+
+    // Generators sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Generator> {
+        self.generators.values().map(|g| g.as_ref())
+    }
+}
+
+// The registry holding every built in data type, created on first use
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::with_builtin)
+}
+
+// Whether the next value of the field is NULL, drawn from `rng` only when the field
+// has a `null_ratio` so the values of the other fields don't change
+pub fn draw_null(field: &Field, rng: &mut dyn RngCore) -> bool {
+    field
+        .config
+        .null_ratio
+        .is_some_and(|null_ratio| rng.gen_range(0..100) < null_ratio)
+}
+
+// Column type of a JSON document, `bson_type` on mongodb targets
+fn json_type(dialect: Dialect, bson_type: &str) -> String {
+    match dialect {
+        Dialect::Postgres => "JSONB".to_string(),
+        Dialect::Mysql => "JSON".to_string(),
+        Dialect::Sqlite => "TEXT".to_string(),
+        Dialect::Mongodb => bson_type.to_string(),
+    }
+}
+
+// Upper bound on the repetitions of `*`, `+` and `{n,}` in a pattern
+const MAX_REPEAT: u32 = 8;
+
+// A sampler of strings matching `pattern`, compiled once per pattern since generators
+// run once per value. A leading `^` or `\A` and a trailing `$` or `\z` are dropped,
+// every generated string is a whole match. Patterns are read as ASCII when they can be,
+// so `\d` or `\w` don't draw from every script.
+fn compile_pattern(pattern: &str) -> Result<Arc<rand_regex::Regex>, String> {
+    static PATTERNS: OnceLock<Mutex<HashMap<String, Arc<rand_regex::Regex>>>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(Default::default);
+    if let Some(regex) = patterns.lock().unwrap().get(pattern) {
+        return Ok(regex.clone());
+    }
+
+    let unanchored = strip_anchors(pattern);
+    let ascii = regex_syntax::ParserBuilder::new()
+        .unicode(false)
+        .build()
+        .parse(unanchored);
+    let hir = match ascii {
+        Ok(hir) => Ok(hir),
+        Err(_) => regex_syntax::Parser::new().parse(unanchored),
+    }
+    .map_err(|err| match err {
+        regex_syntax::Error::Parse(err) => {
+            format!("invalid pattern -> {} <-: {}", pattern, err.kind())
+        }
+        regex_syntax::Error::Translate(err) => {
+            format!("invalid pattern -> {} <-: {}", pattern, err.kind())
+        }
+        err => format!("invalid pattern -> {} <-: {}", pattern, err),
+    })?;
+    let regex = rand_regex::Regex::with_hir(hir, MAX_REPEAT)
+        .map_err(|err| format!("unsupported pattern -> {} <-: {}", pattern, err))?;
+
+    let regex = Arc::new(regex);
+    patterns
+        .lock()
+        .unwrap()
+        .insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+fn strip_anchors(pattern: &str) -> &str {
+    let pattern = pattern
+        .strip_prefix('^')
+        .or_else(|| pattern.strip_prefix("\\A"))
+        .unwrap_or(pattern);
+    let unanchored = pattern
+        .strip_suffix('$')
+        .or_else(|| pattern.strip_suffix("\\z"));
+    match unanchored {
+        // An escaped `\$` or `\z` is a literal, not an anchor
+        Some(rest) if (rest.len() - rest.trim_end_matches('\\').len()) % 2 == 0 => rest,
+        _ => pattern,
+    }
+}
+
+// Length limit shared by the string generators
+fn max_length(field: &Field, default: i32) -> i32 {
+    field.config.max_length.unwrap_or(default)
+}
+
+fn varchar(field: &Field, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Postgres | Dialect::Mysql => format!("VARCHAR({})", max_length(field, 255)),
+        // The length isn't enforced by sqlite
+        Dialect::Sqlite => "TEXT".to_string(),
+        Dialect::Mongodb => "string".to_string(),
+    }
+}
+
+fn truncate(text: String, max_length: i32) -> Value {
+    Value::Text(text.chars().take(max_length.max(0) as usize).collect())
+}
+
+// `min_length..max_length` must not be empty for generators drawing from an exclusive range
+fn check_exclusive_range(min_length: i32, max_length: i32) -> Result<(), String> {
+    if min_length < 0 || min_length >= max_length {
+        return Err(format!(
+            "min_length ({}) must be positive and below max_length ({})",
+            min_length, max_length
+        ));
+    }
+    Ok(())
+}
+
+// `min_length..=max_length` must not be empty for generators drawing from an inclusive range
+fn check_inclusive_range(min_length: i32, max_length: i32) -> Result<(), String> {
+    if min_length > max_length {
+        return Err(format!(
+            "min_length ({}) can't be above max_length ({})",
+            min_length, max_length
+        ));
+    }
+    Ok(())
+}
+
+// Field of `data_type` with the config given as JSON, for the tests of the generators
+#[cfg(test)]
+fn test_field(data_type: &str, config: serde_json::Value) -> Field {
+    serde_json::from_value(serde_json::json!({
+        "fieldname": "f",
+        "data_type": data_type,
+        "config": config,
+    }))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::seed::seeded_rng;
+
+    #[test]
+    fn null_ratio_is_the_share_of_nulls() {
+        let field = test_field("Int", json!({"null_ratio": 25}));
+        let mut rng = seeded_rng(1);
+        let nulls = (0..4000).filter(|_| draw_null(&field, &mut rng)).count();
+        assert!((900..1100).contains(&nulls), "{} nulls", nulls);
+
+        // Without a null_ratio the rng is left alone
+        let field = test_field("Int", json!({}));
+        let mut rng = seeded_rng(1);
+        assert!(!draw_null(&field, &mut rng));
+        assert_eq!(rng.next_u64(), seeded_rng(1).next_u64());
+    }
+}
@@ -0,0 +1,166 @@
+use rand::{Rng, RngCore};
+
+use super::{draw_null, json_type, registry, Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::Field;
+
+// // ----- NESTED DATA TYPES ----- // //
+// Object and Array fields describe JSON shaped values. SQL targets keep them in a JSON
+// column, mongodb targets as embedded documents and arrays. Their members are generated
+// by the generators of their own data types, `validation.rs` checks them like columns.
+
+pub fn register(registry: &mut Registry) {
+    registry.register(Object);
+    registry.register(Array);
+}
+
+// JSON form of a member, its data type is known once the schema is validated
+fn generate_member(field: &Field, rng: &mut dyn RngCore) -> serde_json::Value {
+    if draw_null(field, rng) {
+        return serde_json::Value::Null;
+    }
+    registry()
+        .get(&field.data_type)
+        .map_or(serde_json::Value::Null, |generator| {
+            generator.generate(field, rng).to_json()
+        })
+}
+
+// A document with one member per child field, in the order of `fields`
+struct Object;
+
+impl Generator for Object {
+    fn name(&self) -> &'static str {
+        "Object"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        json_type(dialect, "object")
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        if field.fields.is_empty() {
+            return Err("an Object field needs at least one member in fields".to_string());
+        }
+        Ok(())
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let members = field
+            .fields
+            .iter()
+            .map(|member| (member.fieldname.clone(), generate_member(member, rng)))
+            .collect();
+        Value::Json(serde_json::Value::Object(members))
+    }
+}
+
+// `min_items..=max_items` elements generated from `items` (0 to 5 by default)
+struct Array;
+
+impl Generator for Array {
+    fn name(&self) -> &'static str {
+        "Array"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        json_type(dialect, "array")
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        if field.items.is_none() {
+            return Err("an Array field needs the field describing its items".to_string());
+        }
+        let (min_items, max_items) = item_range(field);
+        if min_items > max_items {
+            return Err(format!(
+                "min_items ({}) can't be above max_items ({})",
+                min_items, max_items
+            ));
+        }
+        Ok(())
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let Some(items) = &field.items else {
+            return Value::Json(serde_json::Value::Array(Vec::new()));
+        };
+        let (min_items, max_items) = item_range(field);
+        let count = rng.gen_range(min_items..=max_items);
+        Value::Json(serde_json::Value::Array(
+            (0..count).map(|_| generate_member(items, rng)).collect(),
+        ))
+    }
+}
+
+fn item_range(field: &Field) -> (usize, usize) {
+    (
+        field.config.min_items.unwrap_or(0),
+        field.config.max_items.unwrap_or(5),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::seed::seeded_rng;
+
+    fn field(config: serde_json::Value) -> Field {
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn objects_hold_a_member_per_field() {
+        let object = field(json!({
+            "fieldname": "address",
+            "data_type": "Object",
+            "fields": [
+                {"fieldname": "number", "data_type": "Int"},
+                {"fieldname": "tags", "data_type": "Array", "items": {"fieldname": "tag", "data_type": "Int"}}
+            ]
+        }));
+        let Value::Json(serde_json::Value::Object(members)) =
+            Object.generate(&object, &mut seeded_rng(1))
+        else {
+            panic!("an Object generates a JSON object");
+        };
+        assert!(members["number"].is_i64());
+        assert!(members["tags"].is_array());
+    }
+
+    #[test]
+    fn arrays_hold_min_to_max_items() {
+        let array = field(json!({
+            "fieldname": "scores",
+            "data_type": "Array",
+            "config": {"min_items": 2, "max_items": 3},
+            "items": {"fieldname": "score", "data_type": "Int"}
+        }));
+        let mut rng = seeded_rng(1);
+        for _ in 0..50 {
+            let Value::Json(serde_json::Value::Array(items)) = Array.generate(&array, &mut rng)
+            else {
+                panic!("an Array generates a JSON array");
+            };
+            assert!((2..=3).contains(&items.len()));
+            assert!(items.iter().all(|item| item.is_i64()));
+        }
+    }
+
+    #[test]
+    fn validate_needs_the_members() {
+        let object = field(json!({"fieldname": "o", "data_type": "Object"}));
+        assert!(Object.validate(&object).is_err());
+        let array = field(json!({"fieldname": "a", "data_type": "Array"}));
+        assert!(Array.validate(&array).is_err());
+        let array = field(json!({
+            "fieldname": "a",
+            "data_type": "Array",
+            "config": {"min_items": 4, "max_items": 1},
+            "items": {"fieldname": "i", "data_type": "Int"}
+        }));
+        assert!(Array.validate(&array).unwrap_err().contains("min_items"));
+    }
+}
@@ -0,0 +1,574 @@
+use fake::faker::address::en::{Latitude, Longitude};
+use fake::faker::boolean::en::Boolean;
+use fake::faker::number::en::Digit;
+use fake::Fake;
+use rand::{Rng, RngCore};
+use rust_decimal::prelude::{Decimal, FromPrimitive};
+
+use super::{check_inclusive_range, distribution, max_length, Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::{Field, NumberDistribution};
+
+// // ----- NUMERIC DATA TYPES ----- // //
+
+pub fn register(registry: &mut Registry) {
+    registry.register(Number {
+        name: "Int",
+        integer: true,
+        limits: (i32::MIN as f64, i32::MAX as f64),
+        min_length: 5,
+        max_length: 25,
+        sql_type: |_, dialect| match dialect {
+            Dialect::Postgres | Dialect::Mysql => "INT".to_string(),
+            Dialect::Sqlite => "INTEGER".to_string(),
+            Dialect::Mongodb => "int".to_string(),
+        },
+    });
+    registry.register(Number {
+        name: "Bigint",
+        integer: true,
+        limits: (i64::MIN as f64, i64::MAX as f64),
+        min_length: 5,
+        max_length: 250000,
+        sql_type: |_, dialect| match dialect {
+            Dialect::Postgres | Dialect::Mysql => "BIGINT".to_string(),
+            Dialect::Sqlite => "INTEGER".to_string(),
+            Dialect::Mongodb => "long".to_string(),
+        },
+    });
+    registry.register(Number {
+        name: "Float",
+        integer: false,
+        limits: (f64::MIN, f64::MAX),
+        min_length: 5,
+        max_length: 250,
+        sql_type: |field, dialect| match (dialect, field.config.precision) {
+            (Dialect::Postgres | Dialect::Mysql, Some(precision)) => {
+                format!("DECIMAL({},{})", precision, field.config.scale.unwrap_or(0))
+            }
+            (Dialect::Postgres, None) => "FLOAT".to_string(),
+            // FLOAT is single precision in mysql
+            (Dialect::Mysql, None) => "DOUBLE".to_string(),
+            (Dialect::Sqlite, Some(_)) => "NUMERIC".to_string(),
+            (Dialect::Sqlite, None) => "REAL".to_string(),
+            (Dialect::Mongodb, Some(_)) => "decimal".to_string(),
+            (Dialect::Mongodb, None) => "double".to_string(),
+        },
+    });
+    // A number kept as text, e.g. for codes that must stay strings
+    registry.register(RangeNumber {
+        name: "StringInt",
+        min_length: 255,
+        max_length: 255,
+        sql_type: |field, dialect| match dialect {
+            Dialect::Postgres | Dialect::Mysql => {
+                format!("VARCHAR({})", max_length(field, 255))
+            }
+            Dialect::Sqlite => "TEXT".to_string(),
+            Dialect::Mongodb => "string".to_string(),
+        },
+        value: |num| Value::Text(num.to_string()),
+    });
+    registry.register(Coordinate {
+        name: "Latitude",
+        precision: 8,
+        fake: |rng| Latitude().fake_with_rng(rng),
+    });
+    registry.register(Coordinate {
+        name: "Longitude",
+        precision: 9,
+        fake: |rng| Longitude().fake_with_rng(rng),
+    });
+    registry.register(DigitNumber);
+    registry.register(Bool);
+    registry.register(Serial);
+}
+
+// Whole number drawn from min_length..=max_length
+struct RangeNumber {
+    name: &'static str,
+    min_length: i32,
+    max_length: i32,
+    sql_type: fn(&Field, Dialect) -> String,
+    value: fn(i32) -> Value,
+}
+
+impl RangeNumber {
+    fn bounds(&self, field: &Field) -> (i32, i32) {
+        (
+            field.config.min_length.unwrap_or(self.min_length),
+            max_length(field, self.max_length),
+        )
+    }
+}
+
+impl Generator for RangeNumber {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        (self.sql_type)(field, dialect)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        let (min_length, max_length) = self.bounds(field);
+        check_inclusive_range(min_length, max_length)
+    }
+
+    fn value_space(&self, field: &Field) -> Option<u64> {
+        let (min_length, max_length) = self.bounds(field);
+        Some((max_length as i64 - min_length as i64 + 1).max(0) as u64)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let (min_length, max_length) = self.bounds(field);
+        (self.value)(rng.gen_range(min_length..=max_length))
+    }
+}
+
+// Most digits a DECIMAL value can hold
+const MAX_PRECISION: u32 = 28;
+
+// Draws of a `clamp: false` field redone before falling back to the bounds
+const MAX_REDRAWS: usize = 100;
+
+// Number between `min` and `max` (min_length and max_length work too), spread by the
+// `distribution` config. Uniform and zipf draws default to the generator's bounds, the
+// unbounded distributions to whatever the column holds.
+struct Number {
+    name: &'static str,
+    // Whole numbers only
+    integer: bool,
+    // Range of the column type
+    limits: (f64, f64),
+    min_length: i32,
+    max_length: i32,
+    sql_type: fn(&Field, Dialect) -> String,
+}
+
+impl Number {
+    // Range of the column, narrowed by the precision of a DECIMAL
+    fn limits(&self, field: &Field) -> (f64, f64) {
+        match field.config.precision {
+            Some(precision) => {
+                let scale = field.config.scale.unwrap_or(0);
+                let largest =
+                    10f64.powi(precision as i32 - scale as i32) - 10f64.powi(-(scale as i32));
+                (-largest, largest)
+            }
+            None => self.limits,
+        }
+    }
+
+    fn bounds(&self, field: &Field) -> (f64, f64) {
+        let config = &field.config;
+        let (min, max) = match config.distribution.unwrap_or_default() {
+            NumberDistribution::Uniform | NumberDistribution::Zipf => {
+                (self.min_length as f64, self.max_length as f64)
+            }
+            _ => self.limits(field),
+        };
+        (
+            config
+                .min
+                .or(config.min_length.map(f64::from))
+                .unwrap_or(min),
+            config
+                .max
+                .or(config.max_length.map(f64::from))
+                .unwrap_or(max),
+        )
+    }
+
+    // Digits kept after the point, all of them when None
+    fn scale(&self, field: &Field) -> Option<u32> {
+        match (self.integer, field.config.precision, field.config.scale) {
+            (true, _, _) => Some(0),
+            (false, Some(_), scale) => Some(scale.unwrap_or(0)),
+            (false, None, scale) => scale,
+        }
+    }
+
+    // A draw of the distribution, before it is moved into the bounds
+    fn sample(field: &Field, min: f64, max: f64, rng: &mut dyn RngCore) -> f64 {
+        let config = &field.config;
+        let mean = config.mean.unwrap_or_default();
+        let stddev = config.stddev.unwrap_or_default();
+        match config.distribution.unwrap_or_default() {
+            NumberDistribution::Uniform => rng.gen_range(min..=max),
+            NumberDistribution::Normal => distribution::normal(rng, mean, stddev),
+            NumberDistribution::LogNormal => distribution::log_normal(rng, mean, stddev),
+            NumberDistribution::Exponential => distribution::exponential(rng, mean),
+            NumberDistribution::Poisson => distribution::poisson(rng, mean),
+            NumberDistribution::Zipf => {
+                let ranks = (max - min).floor() + 1.0;
+                min + distribution::zipf(rng, ranks, config.exponent.unwrap_or_default()) - 1.0
+            }
+        }
+    }
+
+    fn draw(&self, field: &Field, rng: &mut dyn RngCore) -> f64 {
+        let (min, max) = self.bounds(field);
+        // Whole numbers are drawn as such, rounding a real draw would halve the odds of the bounds
+        if self.integer
+            && field.config.distribution.unwrap_or_default() == NumberDistribution::Uniform
+        {
+            return rng.gen_range(min as i64..=max as i64) as f64;
+        }
+        let mut number = Number::sample(field, min, max, rng);
+        if !field.config.clamp.unwrap_or(true) {
+            for _ in 0..MAX_REDRAWS {
+                if (min..=max).contains(&number) {
+                    break;
+                }
+                number = Number::sample(field, min, max, rng);
+            }
+        }
+        number.clamp(min, max)
+    }
+
+    fn check_distribution(field: &Field) -> Result<(), String> {
+        let config = &field.config;
+        let distribution = config.distribution.unwrap_or_default();
+        let (name, parameters): (&str, &[&str]) = match distribution {
+            NumberDistribution::Uniform => ("uniform", &[]),
+            NumberDistribution::Normal => ("normal", &["mean", "stddev"]),
+            NumberDistribution::LogNormal => ("log_normal", &["mean", "stddev"]),
+            NumberDistribution::Exponential => ("exponential", &["mean"]),
+            NumberDistribution::Poisson => ("poisson", &["mean"]),
+            NumberDistribution::Zipf => ("zipf", &["exponent"]),
+        };
+        for (parameter, value) in [
+            ("mean", config.mean),
+            ("stddev", config.stddev),
+            ("exponent", config.exponent),
+        ] {
+            match (value, parameters.contains(&parameter)) {
+                (Some(_), false) => {
+                    return Err(format!(
+                        "{} isn't a parameter of the {} distribution",
+                        parameter, name
+                    ))
+                }
+                (None, true) => {
+                    return Err(format!("the {} distribution needs a {}", name, parameter))
+                }
+                _ => {}
+            }
+        }
+
+        let mean = config.mean.unwrap_or_default();
+        let positive_mean = matches!(
+            distribution,
+            NumberDistribution::LogNormal
+                | NumberDistribution::Exponential
+                | NumberDistribution::Poisson
+        );
+        if positive_mean && mean <= 0.0 {
+            return Err(format!(
+                "mean ({}) must be above 0 for the {} distribution",
+                mean, name
+            ));
+        }
+        match config.stddev {
+            Some(stddev) if stddev <= 0.0 => {
+                return Err(format!("stddev ({}) must be above 0", stddev))
+            }
+            _ => {}
+        }
+        match config.exponent {
+            Some(exponent) if exponent < 0.0 => {
+                return Err(format!("exponent ({}) can't be below 0", exponent))
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Generator for Number {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        (self.sql_type)(field, dialect)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        let config = &field.config;
+        if config.min.is_some() && config.min_length.is_some() {
+            return Err("min and min_length both bound the values, set one of them".to_string());
+        }
+        if config.max.is_some() && config.max_length.is_some() {
+            return Err("max and max_length both bound the values, set one of them".to_string());
+        }
+        if self.integer && (config.precision.is_some() || config.scale.is_some()) {
+            return Err("precision and scale only apply to Float fields".to_string());
+        }
+        if let Some(precision) = config.precision {
+            if precision == 0 || precision > MAX_PRECISION {
+                return Err(format!(
+                    "precision ({}) must be between 1 and {}",
+                    precision, MAX_PRECISION
+                ));
+            }
+        }
+        if let Some(scale) = config.scale {
+            let most = config.precision.unwrap_or(MAX_PRECISION);
+            if scale > most {
+                return Err(format!("scale ({}) can't be above {}", scale, most));
+            }
+        }
+
+        let (min, max) = self.bounds(field);
+        if min > max {
+            return Err(format!("min ({}) can't be above max ({})", min, max));
+        }
+        // Uniform and zipf draws span the whole range
+        let spanned = matches!(
+            config.distribution.unwrap_or_default(),
+            NumberDistribution::Uniform | NumberDistribution::Zipf
+        );
+        if spanned && (max - min).is_infinite() {
+            return Err(format!("min ({}) and max ({}) are too far apart", min, max));
+        }
+        let (lowest, highest) = self.limits(field);
+        if min < lowest || max > highest {
+            return Err(format!(
+                "min ({}) and max ({}) must be within {} and {}",
+                min, max, lowest, highest
+            ));
+        }
+        if self.integer && (min.fract() != 0.0 || max.fract() != 0.0) {
+            return Err(format!(
+                "min ({}) and max ({}) must be whole numbers",
+                min, max
+            ));
+        }
+        Number::check_distribution(field)
+    }
+
+    // Every whole number, or every number with `scale` decimals, between the bounds
+    fn value_space(&self, field: &Field) -> Option<u64> {
+        let scale = self.scale(field)?;
+        let (min, max) = self.bounds(field);
+        let steps = ((max - min) * 10f64.powi(scale as i32)).floor() + 1.0;
+        Some(steps.max(0.0) as u64)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let number = self.draw(field, rng);
+        match (self.integer, field.config.precision, self.scale(field)) {
+            (true, _, _) => Value::Int(number.round() as i64),
+            (false, Some(_), scale) => Value::Decimal(
+                Decimal::from_f64(number)
+                    .unwrap_or_default()
+                    .round_dp(scale.unwrap_or(0)),
+            ),
+            (false, None, Some(scale)) => {
+                let factor = 10f64.powi(scale as i32);
+                Value::Float((number * factor).round() / factor)
+            }
+            (false, None, None) => Value::Float(number),
+        }
+    }
+}
+
+// Degrees with 6 decimals, DECIMAL(precision, 6)
+struct Coordinate {
+    name: &'static str,
+    precision: u32,
+    fake: fn(&mut dyn RngCore) -> f64,
+}
+
+impl Generator for Coordinate {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Postgres | Dialect::Mysql => format!("DECIMAL({},6)", self.precision),
+            Dialect::Sqlite => "NUMERIC".to_string(),
+            Dialect::Mongodb => "decimal".to_string(),
+        }
+    }
+
+    fn generate(&self, _field: &Field, rng: &mut dyn RngCore) -> Value {
+        let fake_degrees = (self.fake)(rng);
+        Value::Decimal(
+            Decimal::from_f64(fake_degrees)
+                .unwrap_or_default()
+                .round_dp(6),
+        )
+    }
+}
+
+// Single digit
+struct DigitNumber;
+
+impl Generator for DigitNumber {
+    fn name(&self) -> &'static str {
+        "Digit"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Postgres | Dialect::Mysql => "INT".to_string(),
+            Dialect::Sqlite => "INTEGER".to_string(),
+            Dialect::Mongodb => "int".to_string(),
+        }
+    }
+
+    fn value_space(&self, _field: &Field) -> Option<u64> {
+        Some(10)
+    }
+
+    fn generate(&self, _field: &Field, rng: &mut dyn RngCore) -> Value {
+        let fake_string: String = Digit().fake_with_rng(rng);
+        Value::Int(fake_string.parse().unwrap_or_default())
+    }
+}
+
+// `ratio` is the percentage of true values (50 by default)
+struct Bool;
+
+impl Generator for Bool {
+    fn name(&self) -> &'static str {
+        "Bool"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Postgres | Dialect::Mysql => "BOOLEAN".to_string(),
+            // Stored as 0 and 1
+            Dialect::Sqlite => "INTEGER".to_string(),
+            Dialect::Mongodb => "bool".to_string(),
+        }
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        match field.config.ratio {
+            Some(ratio) if ratio > 100 => Err(format!("ratio ({}) can't be above 100", ratio)),
+            _ => Ok(()),
+        }
+    }
+
+    fn value_space(&self, field: &Field) -> Option<u64> {
+        match field.config.ratio {
+            Some(0 | 100) => Some(1),
+            _ => Some(2),
+        }
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let ratio: u8 = field.config.ratio.unwrap_or(50);
+        let fake_bool: bool = Boolean(ratio).fake_with_rng(rng);
+        Value::Bool(fake_bool)
+    }
+}
+
+// Auto incrementing key, the database numbers the rows itself
+struct Serial;
+
+impl Generator for Serial {
+    fn name(&self) -> &'static str {
+        "Serial"
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Postgres => "SERIAL".to_string(),
+            Dialect::Mysql => "INT AUTO_INCREMENT".to_string(),
+            // Numbered by sqlite when it is the table's only primary key column
+            Dialect::Sqlite => "INTEGER".to_string(),
+            // A fresh ObjectId per document, name the field `_id` to make it the document id
+            Dialect::Mongodb => "objectId".to_string(),
+        }
+    }
+
+    fn filled_by_database(&self) -> bool {
+        true
+    }
+
+    fn generate(&self, _field: &Field, _rng: &mut dyn RngCore) -> Value {
+        Value::Null
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::generators::{registry, test_field};
+    use crate::seed::seeded_rng;
+
+    fn check(data_type: &str, config: serde_json::Value) -> Result<(), String> {
+        let generator = registry().get(data_type).unwrap();
+        generator.validate(&test_field(data_type, config))
+    }
+
+    #[test]
+    fn validate_rejects_bounds_without_values() {
+        let rejected = [
+            ("Int", json!({"min": 5, "max": 1})),
+            ("Int", json!({"min": 0.5, "max": 1})),
+            ("Int", json!({"min": -3e9, "max": 0})),
+            ("Int", json!({"min": 1, "min_length": 1})),
+            ("Int", json!({"precision": 5})),
+            ("Float", json!({"min": -1.7e308, "max": 1.7e308})),
+            ("Float", json!({"precision": 29})),
+            ("Float", json!({"precision": 4, "scale": 5})),
+            ("Float", json!({"precision": 4, "scale": 2, "max": 1000})),
+        ];
+        for (data_type, config) in rejected {
+            assert!(
+                check(data_type, config.clone()).is_err(),
+                "{} {} was accepted",
+                data_type,
+                config
+            );
+        }
+        check("Bigint", json!({"min": -9e18, "max": 9e18})).unwrap();
+        check("Float", json!({"precision": 4, "scale": 2, "max": 99.99})).unwrap();
+    }
+
+    #[test]
+    fn validate_asks_for_the_parameters_of_the_distribution() {
+        assert!(check("Int", json!({"distribution": "normal", "mean": 5})).is_err());
+        assert!(check("Int", json!({"distribution": "zipf"})).is_err());
+        check(
+            "Float",
+            json!({"distribution": "normal", "mean": 0, "stddev": 1}),
+        )
+        .unwrap();
+        check("Int", json!({"distribution": "poisson", "mean": 3})).unwrap();
+    }
+
+    #[test]
+    fn draws_stay_within_bounds() {
+        let configs = [
+            json!({"min": 1, "max": 3}),
+            json!({"distribution": "normal", "mean": 2, "stddev": 10, "min": 1, "max": 3}),
+            json!({"distribution": "normal", "mean": 2, "stddev": 10, "min": 1, "max": 3, "clamp": false}),
+            json!({"distribution": "zipf", "exponent": 1.5, "min": 1, "max": 3}),
+        ];
+        let int = registry().get("Int").unwrap();
+        let mut rng = seeded_rng(1);
+        for config in configs {
+            let field = test_field("Int", config);
+            int.validate(&field).unwrap();
+            let mut seen = [false; 3];
+            for _ in 0..1000 {
+                match int.generate(&field, &mut rng) {
+                    Value::Int(v @ 1..=3) => seen[v as usize - 1] = true,
+                    value => panic!("{:?} is out of bounds", value),
+                }
+            }
+            assert_eq!(seen, [true; 3]);
+        }
+    }
+}
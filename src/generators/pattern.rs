@@ -0,0 +1,177 @@
+use rand::distributions::Distribution;
+use rand::RngCore;
+
+use super::{compile_pattern, max_length, truncate, varchar, Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::Field;
+
+// // ----- PATTERN DATA TYPES ----- // //
+// Strings drawn from a regex, see `compile_pattern` for what a pattern may contain.
+
+pub fn register(registry: &mut Registry) {
+    registry.register(Pattern);
+    registry.register(LicencePlate);
+}
+
+// A string matching the regex in `pattern`. The column is sized to the longest string
+// the pattern can produce, so `max_length` isn't used.
+struct Pattern;
+
+impl Generator for Pattern {
+    fn name(&self) -> &'static str {
+        "Pattern"
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        let longest = field
+            .config
+            .pattern
+            .as_deref()
+            .and_then(|pattern| compile_pattern(pattern).ok())
+            .map_or(255, |regex| regex.capacity().max(1));
+        match dialect {
+            Dialect::Postgres | Dialect::Mysql if longest <= 255 => format!("VARCHAR({})", longest),
+            Dialect::Postgres | Dialect::Mysql => "TEXT".to_string(),
+            Dialect::Sqlite => "TEXT".to_string(),
+            Dialect::Mongodb => "string".to_string(),
+        }
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        match &field.config.pattern {
+            Some(pattern) => compile_pattern(pattern).map(|_| ()),
+            None => Err("a Pattern field needs the regex to match in pattern".to_string()),
+        }
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        match field.config.pattern.as_deref().map(compile_pattern) {
+            Some(Ok(regex)) => Value::Text(regex.sample(rng)),
+            _ => Value::Null,
+        }
+    }
+}
+
+// Registration plate formats by ISO 3166 country code
+const PLATE_FORMATS: [(&str, &str); 10] = [
+    // State code, district, series and number, e.g. MH26RB5501
+    (
+        "IN",
+        "(MH|DL|KA|TN|GJ|UP|RJ|WB|KL|AP)[0-9]{2}[A-Z]{2}[0-9]{4}",
+    ),
+    // Californian style, e.g. 7ABC123
+    ("US", "[1-9][A-Z]{3}[0-9]{3}"),
+    // Area code, age identifier and random letters, e.g. AB51 ABC
+    (
+        "GB",
+        "[A-HJ-PR-Y][A-HJ-PR-Y](0[2-9]|[1-7][0-9]) [A-HJ-PR-Z]{3}",
+    ),
+    ("DE", "(B|M|HH|K|F|S|D|HB)-[A-Z]{1,2} [1-9][0-9]{0,3}"),
+    ("FR", "[A-HJ-NP-TV-Z]{2}-[0-9]{3}-[A-HJ-NP-TV-Z]{2}"),
+    ("IT", "[A-HJ-NPR-TV-Z]{2}[0-9]{3}[A-HJ-NPR-TV-Z]{2}"),
+    // Consonants only, e.g. 1234 BCD
+    ("ES", "[0-9]{4} [BCDFGHJKLMNPRSTVWXYZ]{3}"),
+    ("NL", "[A-Z]{2}-[0-9]{3}-[A-Z]"),
+    // Mercosur format, e.g. ABC1D23
+    ("BR", "[A-Z]{3}[0-9][A-Z][0-9]{2}"),
+    ("JP", "[0-9]{3} [0-9]{2}-[0-9]{2}"),
+];
+
+// Plate in the format of `country` (IN by default)
+struct LicencePlate;
+
+impl LicencePlate {
+    fn format(field: &Field) -> Option<&'static str> {
+        let country = field.config.country.as_deref().unwrap_or("IN");
+        PLATE_FORMATS
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(country))
+            .map(|(_, format)| *format)
+    }
+}
+
+impl Generator for LicencePlate {
+    fn name(&self) -> &'static str {
+        "LicencePlate"
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        varchar(field, dialect)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        if LicencePlate::format(field).is_none() {
+            let countries: Vec<&str> = PLATE_FORMATS.iter().map(|(code, _)| *code).collect();
+            return Err(format!(
+                "no plate format for country -> {} <-, expected one of {}",
+                field.config.country.as_deref().unwrap_or_default(),
+                countries.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        match LicencePlate::format(field).map(compile_pattern) {
+            Some(Ok(regex)) => truncate(regex.sample(rng), max_length(field, 255)),
+            _ => Value::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::generators::test_field;
+    use crate::seed::seeded_rng;
+
+    // Generates `count` values of the field, each has to match `expected`
+    fn assert_matches(generator: &dyn Generator, field: &Field, expected: &str, count: usize) {
+        let expected = regex::Regex::new(&format!("^(?:{})$", expected)).unwrap();
+        let mut rng = seeded_rng(1);
+        for _ in 0..count {
+            match generator.generate(field, &mut rng) {
+                Value::Text(text) => assert!(expected.is_match(&text), "{} doesn't match", text),
+                value => panic!("expected text, got {:?}", value),
+            }
+        }
+    }
+
+    #[test]
+    fn patterns_generate_matching_strings() {
+        let field = test_field("Pattern", json!({"pattern": "^[A-Z]{3}-[0-9]{2,4}$"}));
+        Pattern.validate(&field).unwrap();
+        assert_matches(&Pattern, &field, "[A-Z]{3}-[0-9]{2,4}", 200);
+        // Sized to the longest match
+        assert_eq!(Pattern.sql_type(&field, Dialect::Postgres), "VARCHAR(8)");
+        assert_eq!(Pattern.sql_type(&field, Dialect::Sqlite), "TEXT");
+    }
+
+    #[test]
+    fn patterns_must_compile() {
+        assert!(Pattern.validate(&test_field("Pattern", json!({}))).is_err());
+        let err = Pattern
+            .validate(&test_field("Pattern", json!({"pattern": "[A-Z"})))
+            .unwrap_err();
+        assert!(err.contains("invalid pattern"));
+    }
+
+    #[test]
+    fn plates_follow_the_format_of_their_country() {
+        for (country, format) in PLATE_FORMATS {
+            let field = test_field("LicencePlate", json!({"country": country.to_lowercase()}));
+            LicencePlate.validate(&field).unwrap();
+            assert_matches(&LicencePlate, &field, format, 50);
+        }
+        // India without a country
+        let field = test_field("LicencePlate", json!({}));
+        assert_matches(&LicencePlate, &field, PLATE_FORMATS[0].1, 50);
+
+        let err = LicencePlate
+            .validate(&test_field("LicencePlate", json!({"country": "XX"})))
+            .unwrap_err();
+        assert!(err.contains("-> XX <-"));
+    }
+}
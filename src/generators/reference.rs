@@ -0,0 +1,50 @@
+use rand::RngCore;
+
+use super::{registry, Dialect, Generator, Registry};
+use crate::references::REFERENCE;
+use crate::value::Value;
+use crate::Field;
+
+// // ----- REFERENCE DATA TYPE ----- // //
+// Foreign key to another table of the schema. The column type and values come from
+// the referenced column (see `references.rs`), this only describes the type on its own.
+
+pub fn register(registry: &mut Registry) {
+    registry.register(Reference);
+}
+
+struct Reference;
+
+impl Generator for Reference {
+    fn name(&self) -> &'static str {
+        REFERENCE
+    }
+
+    // The type given as `reference_type`, INTEGER like a serial key otherwise
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        let reference_type = field
+            .config
+            .foreign_key
+            .as_ref()
+            .and_then(|fk| fk.reference_type.as_deref());
+        match reference_type.and_then(|name| registry().get(name)) {
+            Some(generator) => generator.sql_type(field, dialect),
+            None => match dialect {
+                Dialect::Postgres | Dialect::Mysql | Dialect::Sqlite => "INTEGER".to_string(),
+                Dialect::Mongodb => "objectId".to_string(),
+            },
+        }
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        match &field.config.foreign_key {
+            Some(_) => Ok(()),
+            None => Err("a REFERENCE field needs a foreign_key config".to_string()),
+        }
+    }
+
+    // Values are sampled from the parent table, never generated
+    fn generate(&self, _field: &Field, _rng: &mut dyn RngCore) -> Value {
+        Value::Null
+    }
+}
@@ -0,0 +1,286 @@
+use chrono::DateTime;
+use rand::{Rng, RngCore};
+
+use super::datetime::{parse_config_date, timestamp_type, DEFAULT_START};
+use super::{Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::{Field, SequenceWrap};
+
+// // ----- SEQUENCE DATA TYPES ----- // //
+// Ordered values counted by the generator itself, unlike a Serial the database numbers.
+// Each row moves the previous value by `step`, give or take `jitter`, until `wrap` decides
+// what happens at the end. Timestamps are counted in seconds.
+
+pub fn register(registry: &mut Registry) {
+    // Invoice numbers, version counters
+    registry.register(Sequence {
+        name: "Sequence",
+        default_step: 1,
+        sql_type: |dialect| match dialect {
+            Dialect::Postgres | Dialect::Mysql => "BIGINT".to_string(),
+            Dialect::Sqlite => "INTEGER".to_string(),
+            Dialect::Mongodb => "long".to_string(),
+        },
+        bounds: |field| {
+            let config = &field.config;
+            if config.date.is_some() || config.date_end.is_some() {
+                return Err(
+                    "a Sequence runs from start to end, date and date_end are for a TimestampSequence"
+                        .to_string(),
+                );
+            }
+            Ok((config.start.unwrap_or(1), config.end))
+        },
+        to_value: |position| Some(Value::Int(position)),
+        position: |value| match value {
+            Value::Int(position) => Some(*position),
+            _ => None,
+        },
+    });
+    // Event logs and time series, one row a minute by default
+    registry.register(Sequence {
+        name: "TimestampSequence",
+        default_step: 60,
+        sql_type: timestamp_type,
+        bounds: |field| {
+            let config = &field.config;
+            if config.start.is_some() || config.end.is_some() {
+                return Err(
+                    "a TimestampSequence runs from date to date_end, start and end are for a Sequence"
+                        .to_string(),
+                );
+            }
+            let start = parse_config_date(&config.date, DEFAULT_START)?.timestamp();
+            let end = match config.date_end {
+                Some(_) => Some(parse_config_date(&config.date_end, DEFAULT_START)?.timestamp()),
+                None => None,
+            };
+            Ok((start, end))
+        },
+        to_value: |seconds| {
+            DateTime::from_timestamp(seconds, 0).map(|at| Value::Timestamp(at.naive_utc()))
+        },
+        position: |value| match value {
+            Value::Timestamp(at) => Some(at.and_utc().timestamp()),
+            _ => None,
+        },
+    });
+}
+
+// First and last position, a sequence without an end keeps going
+type Bounds = (i64, Option<i64>);
+
+// Values counted as whole numbers, `to_value` and `position` convert between the two
+struct Sequence {
+    name: &'static str,
+    default_step: i64,
+    sql_type: fn(Dialect) -> String,
+    bounds: fn(&Field) -> Result<Bounds, String>,
+    // None when the position is out of the range of the type
+    to_value: fn(i64) -> Option<Value>,
+    position: fn(&Value) -> Option<i64>,
+}
+
+impl Sequence {
+    fn step(&self, field: &Field) -> i64 {
+        field.config.step.unwrap_or(self.default_step)
+    }
+
+    // A position as it shows in the column, for error messages
+    fn show(&self, position: i64) -> String {
+        (self.to_value)(position)
+            .and_then(|value| value.to_sql_text())
+            .unwrap_or_else(|| position.to_string())
+    }
+}
+
+impl Generator for Sequence {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, _field: &Field, dialect: Dialect) -> String {
+        (self.sql_type)(dialect)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        let (start, end) = (self.bounds)(field)?;
+        let step = self.step(field);
+        if step == 0 {
+            return Err("step can't be 0".to_string());
+        }
+        let jitter = field.config.jitter.unwrap_or(0);
+        if jitter >= step.unsigned_abs() {
+            return Err(format!(
+                "jitter ({}) must be below the size of step ({}) for the values to keep going one way",
+                jitter, step
+            ));
+        }
+        match end {
+            Some(end) if (step > 0 && end < start) || (step < 0 && end > start) => Err(format!(
+                "a step of {} never takes the sequence from {} to {}",
+                step,
+                self.show(start),
+                self.show(end)
+            )),
+            None if field.config.wrap.is_some() => {
+                Err("wrap needs the end of the sequence to wrap at".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Every position up to the end when the steps vary, every step of the way otherwise
+    fn value_space(&self, field: &Field) -> Option<u64> {
+        let (start, end) = (self.bounds)(field).ok()?;
+        let span = end?.abs_diff(start);
+        match field.config.jitter.unwrap_or(0) {
+            0 => Some(span / self.step(field).unsigned_abs() + 1),
+            _ => Some(span.saturating_add(1)),
+        }
+    }
+
+    fn sequential(&self) -> bool {
+        true
+    }
+
+    fn generate_next(
+        &self,
+        field: &Field,
+        previous: Option<&Value>,
+        rng: &mut dyn RngCore,
+    ) -> Result<Value, String> {
+        let (start, end) = (self.bounds)(field)?;
+        let Some(previous) = previous.and_then(self.position) else {
+            return (self.to_value)(start)
+                .ok_or_else(|| "the sequence can't start there".to_string());
+        };
+
+        let step = self.step(field);
+        let jitter = field.config.jitter.unwrap_or(0) as i64;
+        let moved = match jitter {
+            0 => Some(step),
+            _ => step.checked_add(rng.gen_range(-jitter..=jitter)),
+        };
+        // Leaving the range of i64 counts as running past the end
+        let next = moved.and_then(|moved| previous.checked_add(moved));
+        let past_end = match (next, end) {
+            (None, _) => true,
+            (Some(next), Some(end)) => (step > 0 && next > end) || (step < 0 && next < end),
+            (Some(_), None) => false,
+        };
+        let next = match (past_end, field.config.wrap.unwrap_or_default()) {
+            (false, _) => next.unwrap_or(previous),
+            (true, SequenceWrap::Cycle) => start,
+            (true, SequenceWrap::Hold) => end.unwrap_or(previous),
+            (true, SequenceWrap::Error) => {
+                return Err(format!(
+                "the sequence ran past its end after {}, a wrap of cycle or hold keeps it going",
+                self.show(previous)
+            ))
+            }
+        };
+        (self.to_value)(next).ok_or_else(|| {
+            format!(
+                "the sequence ran out of values after {}",
+                self.show(previous)
+            )
+        })
+    }
+
+    // The first value, later ones come from `generate_next`
+    fn generate(&self, field: &Field, _rng: &mut dyn RngCore) -> Value {
+        (self.bounds)(field)
+            .ok()
+            .and_then(|(start, _)| (self.to_value)(start))
+            .unwrap_or(Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::generators::{registry, test_field};
+    use crate::seed::seeded_rng;
+
+    // The first `rows` values of the field, each following the one before
+    fn run(field: &Field, rows: usize) -> Result<Vec<Value>, String> {
+        let generator = registry().get(&field.data_type).unwrap();
+        let mut rng = seeded_rng(1);
+        let mut values: Vec<Value> = Vec::new();
+        for _ in 0..rows {
+            let next = generator.generate_next(field, values.last(), &mut rng)?;
+            values.push(next);
+        }
+        Ok(values)
+    }
+
+    fn ints(values: &[i64]) -> Vec<Value> {
+        values.iter().map(|v| Value::Int(*v)).collect()
+    }
+
+    #[test]
+    fn cycle_starts_over_past_the_end() {
+        let field = test_field("Sequence", json!({"start": 1, "end": 3, "wrap": "cycle"}));
+        assert_eq!(run(&field, 5).unwrap(), ints(&[1, 2, 3, 1, 2]));
+    }
+
+    #[test]
+    fn hold_stays_on_the_end() {
+        let field = test_field(
+            "Sequence",
+            json!({"start": 10, "end": 4, "step": -3, "wrap": "hold"}),
+        );
+        assert_eq!(run(&field, 5).unwrap(), ints(&[10, 7, 4, 4, 4]));
+    }
+
+    #[test]
+    fn error_past_the_end() {
+        let field = test_field("Sequence", json!({"start": 1, "end": 3}));
+        assert_eq!(run(&field, 3).unwrap(), ints(&[1, 2, 3]));
+        assert!(run(&field, 4).unwrap_err().contains("ran past its end"));
+    }
+
+    #[test]
+    fn overflow_runs_past_the_end() {
+        let field = test_field(
+            "Sequence",
+            json!({"start": i64::MAX - 10, "step": 5, "jitter": 4}),
+        );
+        assert!(run(&field, 20).unwrap_err().contains("ran past its end"));
+
+        let field = test_field(
+            "Sequence",
+            json!({"start": i64::MAX - 10, "end": i64::MAX, "step": 5, "jitter": 4, "wrap": "cycle"}),
+        );
+        let values = run(&field, 100).unwrap();
+        assert!(values
+            .iter()
+            .all(|v| matches!(v, Value::Int(v) if *v >= i64::MAX - 10)));
+        assert!(values[1..].contains(&Value::Int(i64::MAX - 10)));
+    }
+
+    #[test]
+    fn jitter_keeps_the_values_going_one_way() {
+        let field = test_field("Sequence", json!({"start": 0, "step": 10, "jitter": 9}));
+        let values = run(&field, 1000).unwrap();
+        assert!(values.windows(2).all(|pair| match pair {
+            [Value::Int(a), Value::Int(b)] => (1..=19).contains(&(b - a)),
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn validate_rejects_steps_that_never_reach_the_end() {
+        let sequence = registry().get("Sequence").unwrap();
+        let check = |config| sequence.validate(&test_field("Sequence", config));
+        assert!(check(json!({"step": 0})).is_err());
+        assert!(check(json!({"start": 5, "end": 1})).is_err());
+        assert!(check(json!({"start": 1, "end": 5, "step": -1})).is_err());
+        assert!(check(json!({"step": 3, "jitter": 3})).is_err());
+        assert!(check(json!({"wrap": "cycle"})).is_err());
+        assert!(check(json!({"start": i64::MIN, "end": i64::MAX, "step": 1})).is_ok());
+    }
+}
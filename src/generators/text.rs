@@ -0,0 +1,298 @@
+use fake::faker::{
+    address::en::{
+        BuildingNumber, CityName, CityPrefix, CitySuffix, CountryCode, CountryName, Geohash,
+        PostCode, SecondaryAddress, SecondaryAddressType, StateAbbr, StateName, StreetName,
+        StreetSuffix, ZipCode,
+    },
+    barcode::en::{Isbn, Isbn10, Isbn13},
+    color::en::{Color, HexColor, HslColor, RgbColor, RgbaColor},
+    company::en::{
+        Bs, BsAdj, BsNoun, BsVerb, Buzzword, BuzzwordMiddle, BuzzwordTail, CatchPhase, CompanyName,
+        CompanySuffix, Industry, Profession,
+    },
+    filesystem::en::{DirPath, FileExtension, FileName, FilePath},
+    finance::raw::Bic,
+    http::en::{RfcStatusCode, ValidStatusCode},
+    internet::en::{
+        DomainSuffix, FreeEmail, FreeEmailProvider, IPv4, IPv6, MACAddress, Password, SafeEmail,
+        UserAgent, Username, IP,
+    },
+    lorem::en::Word,
+    name::raw::{FirstName, LastName, Name, NameWithTitle, Suffix, Title},
+    phone_number::en::{CellNumber, PhoneNumber},
+};
+use fake::locales::EN;
+use fake::uuid::{UUIDv1, UUIDv3, UUIDv4, UUIDv5};
+use fake::{Fake, Faker};
+use rand::RngCore;
+use uuid::Uuid;
+
+use super::{check_exclusive_range, max_length, truncate, varchar, Dialect, Generator, Registry};
+use crate::value::Value;
+use crate::Field;
+
+// // ----- STRING DATA TYPES ----- // //
+// VARCHAR(max_length) columns, values are cut to `max_length` characters (255 by default)
+
+pub fn register(registry: &mut Registry) {
+    registry.register(FakerText::new("Name", |rng| Name(EN).fake_with_rng(rng)));
+    registry.register(FakerText::new("City", |rng| CityName().fake_with_rng(rng)));
+    registry.register(FakerText::new("Email", |rng| {
+        FreeEmail().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Word", |rng| Word().fake_with_rng(rng)));
+    registry.register(FakerText::new("FirstName", |rng| {
+        FirstName(EN).fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("LastName", |rng| {
+        LastName(EN).fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Title", |rng| Title(EN).fake_with_rng(rng)));
+    registry.register(FakerText::new("Suffix", |rng| {
+        Suffix(EN).fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("NameWithTitle", |rng| {
+        NameWithTitle(EN).fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("FreeEmailProvider", |rng| {
+        FreeEmailProvider().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("DomainSuffix", |rng| {
+        DomainSuffix().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("FreeEmail", |rng| {
+        FreeEmail().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("SafeEmail", |rng| {
+        SafeEmail().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Username", |rng| {
+        Username().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("IPv4", |rng| IPv4().fake_with_rng(rng)));
+    registry.register(FakerText::new("IPv6", |rng| IPv6().fake_with_rng(rng)));
+    registry.register(FakerText::new("IP", |rng| IP().fake_with_rng(rng)));
+    registry.register(FakerText::new("MACAddress", |rng| {
+        MACAddress().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("UserAgent", |rng| {
+        UserAgent().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("RfcStatusCode", |rng| {
+        RfcStatusCode().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("ValidStatusCode", |rng| {
+        ValidStatusCode().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("HexColor", |rng| {
+        HexColor().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("RgbColor", |rng| {
+        RgbColor().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("RgbaColor", |rng| {
+        RgbaColor().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("HslColor", |rng| {
+        HslColor().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Color", |rng| Color().fake_with_rng(rng)));
+    registry.register(FakerText::new("CompanySuffix", |rng| {
+        CompanySuffix().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CompanyName", |rng| {
+        CompanyName().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Buzzword", |rng| {
+        Buzzword().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("BuzzwordMiddle", |rng| {
+        BuzzwordMiddle().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("BuzzwordTail", |rng| {
+        BuzzwordTail().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CatchPhase", |rng| {
+        CatchPhase().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Verb", |rng| BsVerb().fake_with_rng(rng)));
+    registry.register(FakerText::new("Adj", |rng| BsAdj().fake_with_rng(rng)));
+    registry.register(FakerText::new("Noun", |rng| BsNoun().fake_with_rng(rng)));
+    registry.register(FakerText::new("Bs", |rng| Bs().fake_with_rng(rng)));
+    registry.register(FakerText::new("Profession", |rng| {
+        Profession().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Industry", |rng| {
+        Industry().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Geohash", |rng| {
+        Geohash(8).fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CityPrefix", |rng| {
+        CityPrefix().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CitySuffix", |rng| {
+        CitySuffix().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CityName", |rng| {
+        CityName().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CountryName", |rng| {
+        CountryName().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CountryCode", |rng| {
+        CountryCode().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("StreetSuffix", |rng| {
+        StreetSuffix().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("StreetName", |rng| {
+        StreetName().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("FilePath", |rng| {
+        FilePath().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("FileName", |rng| {
+        FileName().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("FileExtension", |rng| {
+        FileExtension().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("DirPath", |rng| {
+        DirPath().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("StateName", |rng| {
+        StateName().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("StateAbbr", |rng| {
+        StateAbbr().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("SecondaryAddressType", |rng| {
+        SecondaryAddressType().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("SecondaryAddress", |rng| {
+        SecondaryAddress().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("PostCode", |rng| {
+        PostCode().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("BuildingNumber", |rng| {
+        BuildingNumber().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("ZipCode", |rng| {
+        ZipCode().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Isbn", |rng| Isbn().fake_with_rng(rng)));
+    registry.register(FakerText::new("Isbn13", |rng| Isbn13().fake_with_rng(rng)));
+    registry.register(FakerText::new("Isbn10", |rng| Isbn10().fake_with_rng(rng)));
+    registry.register(FakerText::new("PhoneNumber", |rng| {
+        PhoneNumber().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("CellNumber", |rng| {
+        CellNumber().fake_with_rng(rng)
+    }));
+    registry.register(FakerText::new("Bic", |rng| Bic(EN).fake_with_rng(rng)));
+    registry.register(FakerText::new("String", |rng| Faker.fake_with_rng(rng)));
+    registry.register(FakerText::new("Product", |rng| {
+        let adj: String = BsAdj().fake_with_rng(rng);
+        let noun: String = BsNoun().fake_with_rng(rng);
+        format!("{} {}", adj, noun)
+    }));
+    registry.register(PasswordText);
+
+    registry.register(FakerUuid::new("UUIDv1", |rng| UUIDv1.fake_with_rng(rng)));
+    registry.register(FakerUuid::new("UUIDv3", |rng| UUIDv3.fake_with_rng(rng)));
+    registry.register(FakerUuid::new("UUIDv4", |rng| UUIDv4.fake_with_rng(rng)));
+    registry.register(FakerUuid::new("UUIDv5", |rng| UUIDv5.fake_with_rng(rng)));
+}
+
+// A string from one of the `fake` fakers
+struct FakerText {
+    name: &'static str,
+    fake: fn(&mut dyn RngCore) -> String,
+}
+
+impl FakerText {
+    fn new(name: &'static str, fake: fn(&mut dyn RngCore) -> String) -> Self {
+        FakerText { name, fake }
+    }
+}
+
+impl Generator for FakerText {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        varchar(field, dialect)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        truncate((self.fake)(rng), max_length(field, 255))
+    }
+}
+
+// Password between min_length and max_length characters (5 to 25 by default)
+struct PasswordText;
+
+impl PasswordText {
+    fn lengths(field: &Field) -> (i32, i32) {
+        let mut max_length = max_length(field, 25);
+        let mut min_length = field.config.min_length.unwrap_or(5);
+        if min_length >= max_length {
+            std::mem::swap(&mut min_length, &mut max_length);
+        }
+        (min_length, max_length)
+    }
+}
+
+impl Generator for PasswordText {
+    fn name(&self) -> &'static str {
+        "Password"
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        varchar(field, dialect)
+    }
+
+    fn validate(&self, field: &Field) -> Result<(), String> {
+        let (min_length, max_length) = PasswordText::lengths(field);
+        check_exclusive_range(min_length, max_length)
+    }
+
+    fn generate(&self, field: &Field, rng: &mut dyn RngCore) -> Value {
+        let (min_length, max_length) = PasswordText::lengths(field);
+        let fake_string: String =
+            Password(min_length as usize..max_length as usize).fake_with_rng(rng);
+        truncate(fake_string, max_length)
+    }
+}
+
+// UUID of the given version, stored in a VARCHAR column or as a BSON UUID
+struct FakerUuid {
+    name: &'static str,
+    fake: fn(&mut dyn RngCore) -> Uuid,
+}
+
+impl FakerUuid {
+    fn new(name: &'static str, fake: fn(&mut dyn RngCore) -> Uuid) -> Self {
+        FakerUuid { name, fake }
+    }
+}
+
+impl Generator for FakerUuid {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sql_type(&self, field: &Field, dialect: Dialect) -> String {
+        match dialect {
+            Dialect::Mongodb => "binData".to_string(),
+            _ => varchar(field, dialect),
+        }
+    }
+
+    fn generate(&self, _field: &Field, rng: &mut dyn RngCore) -> Value {
+        Value::Uuid((self.fake)(rng))
+    }
+}
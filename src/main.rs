@@ -3,62 +3,52 @@ use actix_web::{
     App, HttpResponse, HttpServer, Responder,
 };
 use bson::oid::ObjectId;
-use chrono::{TimeZone, Utc};
-use fake::faker::{
-    address::en::{
-        CountryCode, CountryName, Geohash, SecondaryAddress, SecondaryAddressType, StateAbbr,
-        StateName, StreetName, StreetSuffix,
-    },
-    chrono::en::{DateTimeAfter, DateTimeBetween},
-    color::en::{Color, HslColor, RgbColor, RgbaColor},
-    company::en::{
-        Bs, BsAdj, BsNoun, BsVerb, Buzzword, BuzzwordMiddle, BuzzwordTail, CatchPhase, CompanyName,
-        CompanySuffix, Profession,
-    },
-    filesystem::en::{DirPath, FileExtension, FileName, FilePath},
-    finance::raw::Bic,
-    http::en::ValidStatusCode,
-};
-use fake::faker::{
-    color::en::HexColor,
-    http::en::RfcStatusCode,
-    internet::en::{DomainSuffix, IPv4, IPv6, MACAddress, SafeEmail, UserAgent, Username, IP},
-    lorem::en::*,
-};
-use fake::locales::EN;
-use fake::{
-    faker::{
-        address::en::{BuildingNumber, Latitude, Longitude, PostCode, ZipCode},
-        barcode::en::{Isbn, Isbn10, Isbn13},
-        chrono::en::{Date, DateTime, DateTimeBefore, Time},
-        name::raw::*,
-        number::en::Digit,
-        phone_number::en::{CellNumber, PhoneNumber},
-    },
-    uuid::{UUIDv1, UUIDv3, UUIDv4, UUIDv5},
-};
-use fake::{
-    faker::{
-        address::en::{CityName, CityPrefix, CitySuffix},
-        boolean::en::Boolean,
-        internet::en::{FreeEmail, FreeEmailProvider, Password},
-    },
-    Fake, Faker,
-};
 use mongodb::options::FindOneOptions;
 use mongodb::{
     bson::{self, doc},
-    Client, Collection,
+    Collection,
 };
 // use mongodb::{error::Error};
-use rand::Rng;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::Value as JsonValue;
 // use sqlx::postgres::PgRow;
 use core::result::Result::Ok;
-use postgres::types::ToSql;
-use sqlx::postgres::{PgConnectOptions, PgPool};
-use sqlx::Row;
+use sqlx::postgres::PgPool;
+use sqlx::query_builder::Separated;
+use sqlx::{Postgres, QueryBuilder, Row};
+use std::collections::BTreeMap;
+
+mod columnar;
+mod config;
+mod copy;
+mod dialect;
+mod error;
+mod export;
+mod generators;
+mod mongo;
+mod mysql;
+mod references;
+mod rows;
+mod seed;
+mod sqlite;
+mod state;
+mod validation;
+mod value;
+
+use config::AppConfig;
+use copy::{CopyFormat, LoadMode};
+use dialect::Dialect;
+use error::AppError;
+use export::FileFormat;
+use generators::{registry, Generator};
+use references::{
+    column_sql_type, fill_order, is_deferred, reference_groups, KeyStore, ReferenceGroup,
+};
+use rows::{unique_keys, RowGenerator};
+use state::{AppState, DbPool};
+use validation::SchemaError;
+use value::Value;
 
 // // ----- IMPORTS END ----- // //
 
@@ -67,21 +57,43 @@ use sqlx::Row;
 struct CreateDataResponse {
     response: String,
     id: String,
+    // Seed the data was generated with, sending it back reproduces the same rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
 struct NewCreateDataResponse {
     response: String,
+    seed: u64,
+}
+
+#[derive(Deserialize)]
+struct CreateDataRequest {
+    database: String,
+    target: Option<String>,
+    seed: Option<u64>,
+    tables: Vec<Table>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct CreateDataUsingSchemaIdRequest {
     schema_id: String,
+    // Overrides the target stored with the schema
+    target: Option<String>,
+    // Overrides the seed stored with the schema
+    seed: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct AddSchemaRequest {
     database: String,
+    // Name of the configured target to write into, the default target when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    // Seed of every run of the schema, a random one when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
     tables: Vec<Table>,
 }
 
@@ -89,33 +101,220 @@ struct AddSchemaRequest {
 struct Table {
     tablename: String,
     datasize: usize,
+    // Rows per INSERT statement, the server's `insert_batch_size` when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    batch_size: Option<usize>,
+    // INSERT or COPY, the server's `load_mode` when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    load_mode: Option<LoadMode>,
+    // Derived from the schema seed when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
     fields: Vec<Field>,
+    // Secondary indexes, created once the rows are loaded when `defer_indexes` is set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    indexes: Vec<Index>,
+    // The server's `defer_indexes` when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    defer_indexes: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Fields sharing the same `unique_key` form one UNIQUE constraint, composite when there
+// are several. `check` and `default` are trusted raw SQL: they are written as they are in
+// the CREATE TABLE, so only schemas from trusted callers should carry them. Each must be a
+// single expression with balanced parentheses and quotes, and no `;`, `--`, `/*` or `\`.
+// The config of the field must generate values passing the check.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Attribute {
     is_primary: Option<bool>,
     is_not_null: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unique_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    check: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// An index on `fields`, named `<table>_<fields>_idx` unless `name` is given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Index {
+    fields: Vec<String>,
+    #[serde(default)]
+    method: IndexMethod,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+// Hash indexes cover one field, gin indexes JSON fields on postgres
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum IndexMethod {
+    #[default]
+    Btree,
+    Hash,
+    Gin,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Field {
+    // Left out for the `items` of an Array field
+    #[serde(default)]
     fieldname: String,
     data_type: String,
+    // Derived from the table seed when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(default)]
     config: Config,
+    #[serde(default)]
     attributes: Attribute,
+    // Members of an Object field
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<Field>,
+    // Element of an Array field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    items: Option<Box<Field>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Config {
     min_length: Option<i32>,
     max_length: Option<i32>,
     ratio: Option<u8>,
+    // No two rows get the same value, NULLs aside. Implied for a primary key of one field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unique: Option<bool>,
+    // Percent of the values left NULL, none when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    null_ratio: Option<u8>,
     date: Option<String>,
     date_end: Option<String>,
+    // Parent column of a REFERENCE field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    foreign_key: Option<ForeignKey>,
+    // Regex the values of a Pattern field match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    // ISO 3166 country code, e.g. the plate format of a LicencePlate field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    country: Option<String>,
+    // JSON Schema of the documents of a Json field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema: Option<JsonValue>,
+    // Element count of an Array field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_items: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_items: Option<usize>,
+    // Labels of an Enum field, drawn with the matching `weights` (uniformly without)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weights: Option<Vec<f64>>,
+    // Name of the postgres enum type backing an Enum field, a CHECK constraint without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enum_type: Option<String>,
+    // Bounds of an Int, Bigint or Float field, in place of min_length / max_length
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max: Option<f64>,
+    // How the values of a numeric field spread between its bounds, uniformly when missing.
+    // `mean`, `stddev` and `exponent` are the parameters of the distribution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    distribution: Option<NumberDistribution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mean: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stddev: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exponent: Option<f64>,
+    // Draws falling outside the bounds are moved onto them, or drawn again when false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    clamp: Option<bool>,
+    // Digits of a Float field, in all and after the point. A precision makes it DECIMAL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    precision: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scale: Option<u32>,
+    // First value of a Sequence, the first timestamp of a TimestampSequence is `date`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start: Option<i64>,
+    // Distance between two values of a sequence, in seconds for a TimestampSequence.
+    // Each step is moved by up to `jitter` either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    step: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jitter: Option<u64>,
+    // Last value of a Sequence (`date_end` for a TimestampSequence) and what comes after it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wrap: Option<SequenceWrap>,
     // Add Everything else that config may accept
 }
 
+// normal and log_normal take a `mean` and `stddev`, exponential and poisson a `mean`,
+// zipf an `exponent` and ranks the values from min upwards
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NumberDistribution {
+    #[default]
+    Uniform,
+    Normal,
+    LogNormal,
+    Exponential,
+    Poisson,
+    Zipf,
+}
+
+// What a sequence does once its next value would pass its end: fail the generation, start
+// over from the start, or hold the end
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SequenceWrap {
+    #[default]
+    Error,
+    Cycle,
+    Hold,
+}
+
+// REFERENCE fields sharing the same `key` and parent table form one composite foreign key,
+// without a `key` every REFERENCE field is a foreign key of its own.
+// `min_per_parent` / `max_per_parent` bound how many rows point at the same parent row,
+// `max_per_parent: 1` makes the relation one to one.
+// On mongodb targets `embed` stores the parent document itself in place of its key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ForeignKey {
+    table: String,
+    field: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    // Data type of the column, taken from the referenced field when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reference_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_per_parent: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_per_parent: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embed: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct ValidateSchemaRes {
+    valid: bool,
+    errors: Vec<SchemaError>,
+}
+
+#[derive(Debug, Serialize)]
+struct DataTypeRes {
+    name: &'static str,
+    // Column type used with the default config, per dialect
+    sql_types: BTreeMap<Dialect, String>,
+}
+
 #[derive(Debug, Serialize)]
 struct CreateRelationRes {
     relation_id: String,
@@ -128,23 +327,37 @@ struct DeleteRelationRes {
     response: String,
 }
 
-#[derive(Serialize, Deserialize)]
-struct StoredRelation {
+#[derive(Debug, Serialize, Deserialize)]
+struct CreateRelation {
     database: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
     primary_table: String,
     secondary_table: String,
+    // Mongodb targets only: embed the primary document instead of referencing its _id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embed: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct CreateRelation {
+#[derive(Deserialize)]
+struct ExportDataRequest {
     database: String,
-    primary_table: String,
-    secondary_table: String,
+    format: FileFormat,
+    seed: Option<u64>,
+    tables: Vec<Table>,
+}
+
+#[derive(Deserialize)]
+struct ExportSchemaQuery {
+    format: FileFormat,
+    // Overrides the seed stored with the schema
+    seed: Option<u64>,
 }
 
 #[derive(Deserialize)]
 struct DeleteDataRequest {
     database: String,
+    target: Option<String>,
     request_id: String,
 }
 
@@ -152,888 +365,607 @@ struct DeleteDataRequest {
 
 // // ----- HELPER FUNCTIONS START ----- // //
 
-//Creating Table
-async fn create_table(tablename: &String, fields: &Vec<Field>, database: &String) {
-    // Connecting to the Database
-    let connect_options = PgConnectOptions::new()
-        .username("postgres")
-        .password("password")
-        .host("localhost")
-        .database("postgres"); // connect to the default postgres database
-
-    let pool = PgPool::connect_with(connect_options)
-        .await
-        .expect("Failed to create database pool");
+// Upper bound on bind parameters in a single postgres statement
+const MAX_BIND_PARAMS: usize = 65535;
+
+//Creating the database if it doesn't exist yet
+// `admin_pool` must point at the maintenance database of the target
+async fn create_database(admin_pool: &DbPool, database: &str) -> Result<(), AppError> {
+    let admin_pool = match admin_pool {
+        DbPool::Postgres(pool) => pool,
+        DbPool::Mysql(pool) => return mysql::create_database(pool, database).await,
+        // Not a maintenance pool, see `AppState::maintenance_pool`
+        DbPool::Sqlite(_) | DbPool::Mongo(_) => return Ok(()),
+    };
 
     // Check if database exists
     let database_exists: bool = sqlx::query_scalar(
         "SELECT EXISTS(SELECT datname FROM pg_catalog.pg_database WHERE datname = $1)",
     )
     .bind(database)
-    .fetch_one(&pool)
-    .await
-    .expect("Failed to check if database exists");
+    .fetch_one(admin_pool)
+    .await?;
 
     if !database_exists {
         // Create database
-        sqlx::query(&format!("CREATE DATABASE {}", database))
-            .execute(&pool)
-            .await
-            .expect("Failed to create database");
+        sqlx::query(&format!(
+            "CREATE DATABASE {}",
+            Dialect::Postgres.quote(database)
+        ))
+        .execute(admin_pool)
+        .await?;
     }
+    Ok(())
+}
 
-    // Connect to the newly created or existing database
-    let connect_options = PgConnectOptions::new()
-        .username("postgres")
-        .password("password")
-        .host("localhost")
-        .database(&database);
+// Every problem in a schema, including a target this server doesn't know about
+fn schema_errors(
+    state: &AppState,
+    database: &str,
+    target: Option<&str>,
+    tables: &[Table],
+) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    let dialect = match state.config.target(target) {
+        Ok(target) => Some(target.dialect),
+        Err(err) => {
+            errors.push(SchemaError::new("target", err.to_string()));
+            None
+        }
+    };
+    errors.extend(validation::validate_schema(database, tables));
+    errors.extend(validation::validate_limits(
+        tables,
+        state.config.max_unique_rows,
+    ));
+    if let Some(dialect) = dialect {
+        errors.extend(validation::validate_dialect(tables, dialect));
+    }
+    errors
+}
 
-    let pool = PgPool::connect_with(connect_options)
-        .await
-        .expect("Failed to create database pool");
+// Names a request puts in SQL or in the path of a sqlite file, held to the same rules as
+// the names of a schema
+fn check_names(names: &[(&str, &str)]) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+    for (path, name) in names {
+        validation::check_identifier(&mut errors, path, name);
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(AppError {
+        errors,
+        ..AppError::invalid_request("The request holds names that can't be used")
+    })
+}
 
-    let table_exists: bool = sqlx::query_scalar(
-        "SELECT EXISTS (
-                SELECT *
-                FROM information_schema.tables 
-                WHERE table_schema = 'public' 
-                AND table_name = $1
-            )",
-    )
-    .bind(tablename)
-    .fetch_one(&pool)
-    .await
-    .expect("Failed to check if table exists");
+// Reads a request body, reporting where the JSON doesn't have the expected shape
+fn parse_request<T: DeserializeOwned>(json: JsonValue) -> Result<T, AppError> {
+    validation::parse(json).map_err(|errors| AppError {
+        errors,
+        ..AppError::invalid_request("The request body doesn't match the expected format")
+    })
+}
 
-    if !table_exists {
-        let mut create_query = format!("CREATE TABLE {} (", tablename);
-        let mut column_definitions = vec![];
+// Constraints a group of REFERENCE fields puts on its table
+fn reference_constraints(group: &ReferenceGroup, dialect: Dialect) -> Vec<String> {
+    let columns: Vec<&str> = group
+        .fields
+        .iter()
+        .map(|(_, f)| f.fieldname.as_str())
+        .collect();
+    let mut constraints = vec![format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({})",
+        dialect.quote_all(&columns),
+        dialect.quote(group.parent),
+        dialect.quote_all(&group.parent_fields)
+    )];
+    // A one to one relation can't point at the same parent row twice
+    if group.max_per_parent == Some(1) {
+        constraints.push(format!("UNIQUE ({})", dialect.quote_all(&columns)));
+    }
+    constraints
+}
 
-        for field in fields {
-            let mut column_definition = format!("");
-            match field.data_type.as_ref() {
-                "String"
-                | "StringInt"
-                | "Name"
-                | "City"
-                | "Email"
-                | "Password"
-                | "Word"
-                | "FirstName"
-                | "LastName"
-                | "Title"
-                | "Suffix"
-                | "NameWithTitle"
-                | "FreeEmailProvider"
-                | "DomainSuffix"
-                | "FreeEmail"
-                | "SafeEmail"
-                | "Username"
-                | "IPv4"
-                | "IPv6"
-                | "IP"
-                | "MACAddress"
-                | "UserAgent"
-                | "RfcStatusCode"
-                | "ValidStatusCode"
-                | "HexColor"
-                | "RgbColor"
-                | "RgbaColor"
-                | "HslColor"
-                | "Color"
-                | "CompanySuffix"
-                | "CompanyName"
-                | "Buzzword"
-                | "BuzzwordMiddle"
-                | "BuzzwordTail"
-                | "CatchPhase"
-                | "Verb"
-                | "Adj"
-                | "Noun"
-                | "Bs"
-                | "Profession"
-                | "Industry"
-                | "Geohash"
-                | "CityPrefix"
-                | "CitySuffix"
-                | "CityName"
-                | "CountryName"
-                | "CountryCode"
-                | "StreetSuffix"
-                | "StreetName"
-                | "FilePath"
-                | "FileName"
-                | "FileExtension"
-                | "DirPath"
-                | "StateName"
-                | "StateAbbr"
-                | "SecondaryAddressType"
-                | "SecondaryAddress"
-                | "PostCode"
-                | "BuildingNumber"
-                | "LicencePlate"
-                | "Isbn"
-                | "Isbn13"
-                | "Isbn10"
-                | "PhoneNumber"
-                | "CellNumber"
-                | "Bic"
-                | "UUIDv1"
-                | "UUIDv3"
-                | "UUIDv4"
-                | "UUIDv5"
-                | "Product" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    column_definition =
-                        format!("{} {}({})", field.fieldname, "VARCHAR", max_length);
-                } //DECIMAL(#, #)
-                "Latitude" => {
-                    column_definition = format!("{} {}", field.fieldname, "DECIMAL(8,6)");
-                }
-                "Longitude" => {
-                    column_definition = format!("{} {}", field.fieldname, "DECIMAL(9,6)");
-                } //BOOLEAN
-                "Bool" => {
-                    column_definition = format!("{} {}", field.fieldname, "BOOLEAN");
-                } //TEXT
-                "Sentence" | "Sentences" | "Words" | "Paragraph" | "Paragraphs" => {
-                    column_definition = format!("{} {}", field.fieldname, "TEXT");
-                } //INT
-                "Int" | "Digit" | "ZipCode" => {
-                    column_definition = format!("{} {}", field.fieldname, "INT");
-                } //SERIAL
-                "Serial" => {
-                    column_definition = format!("{} {}", field.fieldname, "SERIAL");
-                } //FLOAT
-                "Float" => {
-                    column_definition = format!("{} {}", field.fieldname, "FLOAT");
-                } //BIGINT
-                "Bigint" => {
-                    column_definition = format!("{} {}", field.fieldname, "BIGINT");
-                }
-                //TIME
-                "Time" => {
-                    column_definition = format!("{} {}", field.fieldname, "Time");
-                } //DATE
-                "Date" => {
-                    column_definition = format!("{} {}", field.fieldname, "DATE");
-                } //DATETIME //TIMESTAMP
-                "DateTime" | "DateTimeBefore" | "DateTimeAfter" | "DateTimeBetween" => {
-                    column_definition = format!("{} {}", field.fieldname, "TIMESTAMP");
-                }
-                _ => println!(
-                    "Didn't find -> {} <- in any of the expected values.",
-                    field.data_type
-                ),
-            }
-            // checking for not null attribute
+// CREATE TABLE statement of a table, without the constraints of deferred references
+fn create_table_sql(table: &Table, tables: &[Table], dialect: Dialect) -> Result<String, AppError> {
+    let tablename = &table.tablename;
+    let fields = &table.fields;
 
-            let is_not_null: bool = field.attributes.is_not_null.unwrap_or(true);
+    let groups = reference_groups(table);
+    let deferred_fields: Vec<&str> = groups
+        .iter()
+        .filter(|group| is_deferred(tables, table, group))
+        .flat_map(|group| group.fields.iter().map(|(_, f)| f.fieldname.as_str()))
+        .collect();
+    // Sqlite can't add them later, it doesn't need the parent table to exist yet either
+    let inline_deferred = dialect == Dialect::Sqlite;
+
+    let mut create_query = format!("CREATE TABLE {} (", dialect.quote(tablename));
+    let mut column_definitions = vec![];
+
+    for field in fields {
+        let sql_type = column_sql_type(field, tables, dialect).ok_or_else(|| AppError {
+            field: Some(field.fieldname.clone()),
+            ..AppError::internal(format!("Unknown data type -> {} <-", field.data_type))
+                .in_table(tablename)
+        })?;
+        let mut column_definition = format!("{} {}", dialect.quote(&field.fieldname), sql_type);
+        if let Some(constraint) =
+            column_generator(field).and_then(|g| g.column_constraint(field, dialect))
+        {
+            column_definition.push(' ');
+            column_definition.push_str(&constraint);
+        }
+        // Columns are nullable unless the field says otherwise
+        let is_not_null: bool = field.attributes.is_not_null.unwrap_or(false);
 
-            if !is_not_null {
-                column_definition.push_str(" NOT NULL ");
-            }
+        // Deferred references are NULL until every table is filled
+        if is_not_null && !deferred_fields.contains(&field.fieldname.as_str()) {
+            column_definition.push_str(" NOT NULL");
+        }
+        // A primary key of one field is unique already
+        if field.config.unique == Some(true) && !field.attributes.is_primary.unwrap_or(false) {
+            column_definition.push_str(" UNIQUE");
+        }
+        if let Some(default) = &field.attributes.default {
+            column_definition.push_str(&format!(" DEFAULT ({})", default));
+        }
+        if let Some(check) = &field.attributes.check {
+            column_definition.push_str(&format!(" CHECK ({})", check));
+        }
+
+        column_definitions.push(column_definition);
+    }
+    create_query.push_str(&column_definitions.join(", "));
 
-            column_definitions.push(column_definition);
+    // check if there are any primary keys in the table
+    let mut contains_primary_key: bool = false;
+    for field in fields {
+        if field.attributes.is_primary.unwrap_or(false) {
+            contains_primary_key = true;
         }
-        create_query.push_str(&column_definitions.join(", "));
+    }
+
+    //  Create a Composite key if user adds the primary key attribute for more than one field
+    //  Else there will be only one primary key or none
+    let mut p_keys = vec![];
+    if contains_primary_key {
+        create_query.push_str(", PRIMARY KEY(");
 
-        // check if there are any primary keys in the table
-        let mut contains_primary_key: bool = false;
         for field in fields {
             if field.attributes.is_primary.unwrap_or(false) {
-                contains_primary_key = true;
+                p_keys.push(field.fieldname.clone());
             }
         }
+        create_query.push_str(&dialect.quote_all(&p_keys));
+        create_query.push(')');
+    }
 
-        //  Create a Composite key if user adds the primary key attribute for more than one field
-        //  Else there will be only one primary key or none
-        if contains_primary_key {
-            create_query.push_str(", PRIMARY KEY(");
-            let mut p_keys = vec![];
-
-            for field in fields {
-                if field.attributes.is_primary.unwrap_or(false) {
-                    let mut p_key = format!("");
-                    let var: String = format!("{}", field.fieldname);
-                    p_key.push_str(&var);
-                    p_keys.push(p_key);
-                }
+    // An AUTO_INCREMENT column must lead an index in mysql
+    if dialect == Dialect::Mysql {
+        for field in fields {
+            let serial = field.config.foreign_key.is_none()
+                && registry()
+                    .get(&field.data_type)
+                    .is_some_and(|g| g.filled_by_database());
+            if serial && p_keys.first() != Some(&field.fieldname) {
+                create_query.push_str(&format!(", KEY ({})", dialect.quote(&field.fieldname)));
             }
-            create_query.push_str(&p_keys.join(", "));
-            create_query.push_str(")");
         }
+    }
+
+    for key in unique_keys(table) {
+        let columns: Vec<&str> = key.iter().map(|f| f.fieldname.as_str()).collect();
+        create_query.push_str(&format!(", UNIQUE ({})", dialect.quote_all(&columns)));
+    }
+
+    // REFERENCE fields become foreign keys, the parent table is created first.
+    // Deferred ones are added by `deferred_constraints_sql` once their rows are set.
+    for group in groups
+        .iter()
+        .filter(|g| inline_deferred || !is_deferred(tables, table, g))
+    {
+        for constraint in reference_constraints(group, dialect) {
+            create_query.push_str(", ");
+            create_query.push_str(&constraint);
+        }
+    }
+
+    create_query.push(')');
+    Ok(create_query)
+}
+
+fn index_name(table: &Table, index: &Index) -> String {
+    match &index.name {
+        Some(name) => name.clone(),
+        None => format!("{}_{}_idx", table.tablename, index.fields.join("_")),
+    }
+}
+
+// CREATE INDEX statements of the secondary indexes of a table
+fn create_indexes_sql(table: &Table, dialect: Dialect) -> Vec<String> {
+    table
+        .indexes
+        .iter()
+        .map(|index| {
+            let name = dialect.quote(&index_name(table, index));
+            let tablename = dialect.quote(&table.tablename);
+            let columns = dialect.quote_all(&index.fields);
+            let method = match index.method {
+                IndexMethod::Btree => "btree",
+                IndexMethod::Hash => "hash",
+                IndexMethod::Gin => "gin",
+            };
+            match dialect {
+                Dialect::Mysql => format!(
+                    "CREATE INDEX {} USING {} ON {} ({})",
+                    name,
+                    method.to_uppercase(),
+                    tablename,
+                    columns
+                ),
+                // Sqlite only has btree indexes
+                Dialect::Sqlite => format!("CREATE INDEX {} ON {} ({})", name, tablename, columns),
+                // Mongodb collections get theirs from `mongo::create_indexes`
+                Dialect::Postgres | Dialect::Mongodb => format!(
+                    "CREATE INDEX {} ON {} USING {} ({})",
+                    name, tablename, method, columns
+                ),
+            }
+        })
+        .collect()
+}
+
+// Creates the secondary indexes of a table created by `create_table`
+async fn create_indexes(pool: &DbPool, table: &Table) -> Result<(), AppError> {
+    if let DbPool::Mongo(db) = pool {
+        return mongo::create_indexes(db, table).await;
+    }
+    for statement in create_indexes_sql(table, pool.dialect()) {
+        pool.execute(&statement).await?;
+    }
+    Ok(())
+}
+
+// Generator of a column's own data type, references take their type from the parent
+fn column_generator(field: &Field) -> Option<&'static dyn Generator> {
+    if field.config.foreign_key.is_some() {
+        return None;
+    }
+    registry().get(&field.data_type)
+}
+
+// Statements creating the types the columns of a table use, to run before its CREATE TABLE
+fn type_definitions_sql(table: &Table, dialect: Dialect) -> Vec<String> {
+    table
+        .fields
+        .iter()
+        .filter_map(|field| column_generator(field)?.type_definition(field, dialect))
+        .collect()
+}
+
+// ALTER TABLE statement adding the constraints of the deferred references of a table,
+// sqlite tables get them in their CREATE TABLE and mongodb collections have none
+fn deferred_constraints_sql(table: &Table, tables: &[Table], dialect: Dialect) -> Option<String> {
+    if matches!(dialect, Dialect::Sqlite | Dialect::Mongodb) {
+        return None;
+    }
+    let constraints: Vec<String> = reference_groups(table)
+        .iter()
+        .filter(|group| is_deferred(tables, table, group))
+        .flat_map(|group| reference_constraints(group, dialect))
+        .map(|constraint| format!("ADD {}", constraint))
+        .collect();
+    if constraints.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "ALTER TABLE {} {}",
+        dialect.quote(&table.tablename),
+        constraints.join(", ")
+    ))
+}
 
-        create_query.push_str(");");
+//Creating Table, returns false when the table was already there
+async fn create_table(pool: &DbPool, table: &Table, tables: &[Table]) -> Result<bool, AppError> {
+    let tablename = &table.tablename;
 
+    let table_exists: bool = match pool {
+        DbPool::Postgres(pool) => {
+            sqlx::query_scalar(
+                "SELECT EXISTS (
+                SELECT *
+                FROM information_schema.tables 
+                WHERE table_schema = 'public' 
+                AND table_name = $1
+            )",
+            )
+            .bind(tablename)
+            .fetch_one(pool)
+            .await?
+        }
+        DbPool::Mysql(pool) => mysql::table_exists(pool, tablename).await?,
+        DbPool::Sqlite(pool) => sqlite::table_exists(pool, tablename).await?,
+        // Collections have no columns to declare
+        DbPool::Mongo(db) => {
+            return mongo::create_collection(db, tablename)
+                .await
+                .map_err(|err| err.in_table(tablename))
+        }
+    };
 
-        sqlx::query(&create_query)
-            .execute(&pool)
+    if !table_exists {
+        for definition in type_definitions_sql(table, pool.dialect()) {
+            pool.execute(&definition)
+                .await
+                .map_err(|err| AppError::from(err).in_table(tablename))?;
+        }
+        let create_query = create_table_sql(table, tables, pool.dialect())?;
+        pool.execute(&create_query)
             .await
-            .expect("Failed to create table");
-    } else {
+            .map_err(|err| AppError::from(err).in_table(tablename))?;
     }
+    Ok(!table_exists)
+}
+
+// Adds the constraints of the deferred references of a table created by `create_table`
+async fn add_deferred_constraints(
+    pool: &DbPool,
+    table: &Table,
+    tables: &[Table],
+) -> Result<(), AppError> {
+    if let Some(alter_query) = deferred_constraints_sql(table, tables, pool.dialect()) {
+        pool.execute(&alter_query).await?;
+    }
+    Ok(())
+}
+
+// Binds a generated value with its native postgres type
+fn push_bind_value(row: &mut Separated<'_, '_, Postgres, &str>, value: Value) {
+    match value {
+        Value::Null => row.push_bind_unseparated(None::<String>),
+        Value::Int(v) => row.push_bind_unseparated(v),
+        Value::Float(v) => row.push_bind_unseparated(v),
+        Value::Decimal(v) => row.push_bind_unseparated(v),
+        Value::Bool(v) => row.push_bind_unseparated(v),
+        Value::Text(v) => row.push_bind_unseparated(v),
+        Value::Date(v) => row.push_bind_unseparated(v),
+        Value::Time(v) => row.push_bind_unseparated(v),
+        Value::Timestamp(v) => row.push_bind_unseparated(v),
+        Value::Uuid(v) => row.push_bind_unseparated(v),
+        Value::Json(v) => row.push_bind_unseparated(v),
+        Value::Bytes(v) => row.push_bind_unseparated(v),
+    };
 }
 
 //Creating and Inserting fake data into the table
 async fn create_and_insert_data(
-    tablename: &String,
-    datasize: &usize,
-    fields: &Vec<Field>,
-    database: &String,
-) -> impl Responder {
-    let connect_options = PgConnectOptions::new()
-        .username("postgres")
-        .password("password")
-        .host("localhost")
-        .database(&database);
+    pool: &DbPool,
+    table: &Table,
+    tables: &[Table],
+    mut rows: RowGenerator,
+    seed: u64,
+    batch_size: usize,
+    load_mode: LoadMode,
+) -> Result<(), AppError> {
+    let tablename = &table.tablename;
+    let datasize = &table.datasize;
+    let pool = match pool {
+        DbPool::Postgres(pool) => pool,
+        // MySQL and SQLite have no COPY, their rows always go through INSERT
+        DbPool::Mysql(pool) => {
+            return mysql::insert_rows(pool, tablename, *datasize, rows, batch_size).await
+        }
+        DbPool::Sqlite(pool) => {
+            return sqlite::insert_rows(pool, tablename, *datasize, rows, batch_size).await
+        }
+        DbPool::Mongo(db) => {
+            return mongo::insert_documents(db, table, tables, rows, seed, batch_size).await
+        }
+    };
+    let quoted_tablename = Dialect::Postgres.quote(tablename);
+
+    // Every column is filled by the database or set later (serial keys, deferred references)
+    if rows.columns().is_empty() {
+        sqlx::query(&format!(
+            "INSERT INTO {} SELECT FROM generate_series(1, $1)",
+            quoted_tablename
+        ))
+        .bind(*datasize as i64)
+        .execute(pool)
+        .await?;
+        return Ok(());
+    }
 
-    let pool = PgPool::connect_with(connect_options)
-        .await
-        .expect("Failed to create database pool");
+    // Bulk loads stream the rows through COPY instead of INSERT statements
+    let copy_format = match load_mode {
+        LoadMode::Insert => None,
+        LoadMode::Copy => Some(CopyFormat::Text),
+        LoadMode::CopyBinary => Some(CopyFormat::Binary),
+    };
+    if let Some(format) = copy_format {
+        copy::copy_rows(pool, tablename, rows, *datasize, format).await?;
+        return Ok(());
+    }
 
-    // create the fake data using `fake = "2.5.0"` crate
-    let mut values = vec![];
-    for _i in 0..*datasize {
-        let mut row_values = vec![];
-        for field in fields {
-            if &field.data_type == "Serial" {
-                continue;
+    let column_types: Vec<String> = rows.columns().iter().map(|c| c.sql_type.clone()).collect();
+    let column_names: Vec<String> = rows.columns().iter().map(|c| c.name.clone()).collect();
+
+    // Postgres refuses statements with more than 65535 bind parameters
+    let rows_per_batch = batch_size
+        .min(MAX_BIND_PARAMS / column_names.len().max(1))
+        .max(1);
+
+    // Every batch goes through the same transaction so a failure leaves the table untouched
+    let mut tx = pool.begin().await?;
+
+    // create the fake data using `fake = "2.5.0"` crate, one batch at a time
+    let mut remaining = *datasize;
+    while remaining > 0 {
+        let batch = remaining.min(rows_per_batch);
+
+        let mut insert_query: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+            "INSERT INTO {} ({}) ",
+            quoted_tablename,
+            Dialect::Postgres.quote_all(&column_names)
+        ));
+        // The cast narrows the bound value to the column's exact type (INT vs BIGINT, VARCHAR(n)...)
+        insert_query.push_values(rows.next_rows(batch)?, |mut row, values| {
+            for (value, sql_type) in values.into_iter().zip(&column_types) {
+                row.push("CAST(");
+                push_bind_value(&mut row, value);
+                row.push_unseparated(format!(" AS {})", sql_type));
             }
-            let fake_value = match field.data_type.as_str() {
-                //VARCHAR
-                "String" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Faker.fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "StringInt" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let min_length = field.config.min_length.unwrap_or(255);
-                    let mut rng = rand::thread_rng();
-                    let num = rng.gen_range(min_length..=max_length);
-                    num.to_string()
-                }
-                "Name" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Name(EN).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "City" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CityName().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Email" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = FreeEmail().fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Password" => {
-                    let mut max_length = field.config.max_length.unwrap_or(25);
-                    let mut min_length = field.config.min_length.unwrap_or(5);
-                    if min_length >= max_length {
-                        std::mem::swap(&mut min_length, &mut max_length);
-                    }
-                    let fake_string: String =
-                        Password(min_length as usize..max_length as usize).fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Word" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Word().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "FirstName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = FirstName(EN).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "LastName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = LastName(EN).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Title" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Title(EN).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Suffix" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Suffix(EN).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "NameWithTitle" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = NameWithTitle(EN).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "FreeEmailProvider" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = FreeEmailProvider().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "DomainSuffix" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = DomainSuffix().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "FreeEmail" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = FreeEmail().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "SafeEmail" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = SafeEmail().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Username" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Username().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "IPv4" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = IPv4().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "IPv6" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = IPv6().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "IP" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = IP().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "MACAddress" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = MACAddress().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "UserAgent" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = UserAgent().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "RfcStatusCode" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = RfcStatusCode().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "ValidStatusCode" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = ValidStatusCode().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "HexColor" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = HexColor().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "RgbColor" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = RgbColor().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "RgbaColor" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = RgbaColor().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "HslColor" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = HslColor().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Color" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Color().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CompanySuffix" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CompanySuffix().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CompanyName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CompanyName().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Buzzword" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Buzzword().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "BuzzwordMiddle" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = BuzzwordMiddle().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "BuzzwordTail" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = BuzzwordTail().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CatchPhase" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CatchPhase().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Verb" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = BsVerb().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Adj" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = BsAdj().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Noun" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = BsNoun().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Bs" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Bs().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Profession" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Profession().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Industry" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CatchPhase().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Geohash" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Geohash(8).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CityPrefix" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CityPrefix().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CitySuffix" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CitySuffix().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CityName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CityName().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CountryName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CountryName().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CountryCode" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CountryCode().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "StreetSuffix" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = StreetSuffix().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "StreetName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = StreetName().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "FilePath" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = FilePath().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "FileName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = FileName().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "FileExtension" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = FileExtension().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "DirPath" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = DirPath().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "StateName" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = StateName().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "StateAbbr" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = StateAbbr().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "SecondaryAddressType" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = SecondaryAddressType().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "SecondaryAddress" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = SecondaryAddress().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "PostCode" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = PostCode().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "BuildingNumber" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = BuildingNumber().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "LicencePlate" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    // let fake_string: String = LicencePlate().fake();
-                    // let re = Regex::new(r"[A-Z]{2}[0-9]{2}[A-Z]{2}").unwrap();
-                    // let fake_string: String = rand::thread_rng().sample_iter(&re).take(1).next().unwrap().to_string();
-                    let fake_string: String = format!("MH26RB5501"); //Hardcoded String as a placeholder
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Isbn" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Isbn().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Isbn13" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Isbn13().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Isbn10" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Isbn10().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "PhoneNumber" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = PhoneNumber().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "CellNumber" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = CellNumber().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Bic" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Bic(EN).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "UUIDv1" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = UUIDv1.fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "UUIDv3" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = UUIDv3.fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "UUIDv4" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = UUIDv4.fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "UUIDv5" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = UUIDv5.fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                //VARCHAR END
-                // Decimal(8,6) - Latitude
-                "Latitude" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Latitude().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                // Decimal(9,6) - Latitude
-                "Longitude" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Longitude().fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                //BOOLEAN
-                "Bool" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let ratio: u8 = field.config.ratio.unwrap_or(50) as u8;
-                    let fake_bool: bool = Boolean(ratio as u8).fake();
-                    let fake_string: String = fake_bool.to_string();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                //Bool END
-                //TEXT
-                "Sentence" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let min_length = field.config.min_length.unwrap_or_default();
-                    let fake_string: String =
-                        Sentence(min_length as usize..max_length as usize).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Sentences" => {
-                    let max_length = field.config.max_length.unwrap_or(25);
-                    let min_length = field.config.min_length.unwrap_or(5);
-                    let fake_string: String =
-                        Sentence(min_length as usize..max_length as usize).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string
-                        .chars()
-                        .take((max_length * 10) as usize)
-                        .collect()
-                }
-                "Words" => {
-                    let max_length = field.config.max_length.unwrap_or(25);
-                    let mut fake_string = String::new();
-                    for i in 0..max_length {
-                        fake_string.push_str(Word().fake());
-
-                        if i != max_length - 1 {
-                            fake_string.push_str(", ");
-                        }
-                    }
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string
-                        .chars()
-                        .take((max_length * 10) as usize)
-                        .collect()
-                }
-                "Paragraph" => {
-                    let max_length = field.config.max_length.unwrap_or(25);
-                    let min_length = field.config.min_length.unwrap_or(5);
-                    let fake_string: String =
-                        Paragraph(min_length as usize..max_length as usize).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "Paragraphs" => {
-                    let max_length = field.config.max_length.unwrap_or(55);
-                    let min_length = field.config.min_length.unwrap_or(5);
-                    let fake_string: String =
-                        Paragraph(min_length as usize..max_length as usize).fake();
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string
-                        .chars()
-                        .take((max_length * 10) as usize)
-                        .collect()
-                }
-                //INT
-                "Int" => {
-                    let max_length = field.config.max_length.unwrap_or(25);
-                    let min_length = field.config.min_length.unwrap_or(5);
-                    let mut rng = rand::thread_rng();
-                    let num = rng.gen_range(min_length..=max_length);
-                    num.to_string()
-                }
-                "Digit" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Digit().fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "ZipCode" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = ZipCode().fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                //FLOAT
-                "Float" => {
-                    let max_length = field.config.max_length.unwrap_or(250);
-                    let min_length = field.config.min_length.unwrap_or(5);
-                    let mut rng = rand::thread_rng();
-                    let num = rng.gen_range(min_length..=max_length);
-                    num.to_string()
-                }
-                //BIGINT
-                "Bigint" => {
-                    let max_length = field.config.max_length.unwrap_or(250000);
-                    let min_length = field.config.min_length.unwrap_or(5);
-                    let mut rng = rand::thread_rng();
-                    let num = rng.gen_range(min_length..=max_length);
-                    num.to_string()
-                }
-                //TIME
-                "Time" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Time().fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                //Date
-                "Date" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = Date().fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "DateTime" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let fake_string: String = DateTime().fake();
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                "DateTimeBefore" => {
-                    let default_str: String = format!("2023-04-14 05:05:01");
-                    let get_dt: &str = field.config.date.as_ref().unwrap_or(&default_str);
-                    let dt_before = Utc
-                        .datetime_from_str(get_dt, "%Y-%m-%d %H:%M:%S")
-                        .unwrap()
-                        .with_timezone(&Utc);
-                    let fake_string: String = DateTimeBefore(dt_before).fake();
-                    fake_string.chars().take(255 as usize).collect()
-                }
-                "DateTimeAfter" => {
-                    let default_str: String = format!("2023-04-14 05:05:01");
-                    let get_dt: &str = field.config.date.as_ref().unwrap_or(&default_str);
-                    let dt_after = Utc
-                        .datetime_from_str(get_dt, "%Y-%m-%d %H:%M:%S")
-                        .unwrap()
-                        .with_timezone(&Utc);
-                    let fake_string: String = DateTimeAfter(dt_after).fake();
-                    fake_string.chars().take(255 as usize).collect()
-                }
-                "DateTimeBetween" => {
-                    let default_start: String = format!("2001-05-05 05:05:01");
-                    let default_end: String = format!("2023-04-14 05:05:01");
-                    let get_start_dt: &str = field.config.date.as_ref().unwrap_or(&default_start);
-                    let get_end_dt: &str = field.config.date_end.as_ref().unwrap_or(&default_end);
-                    let dt_start = Utc
-                        .datetime_from_str(get_start_dt, "%Y-%m-%d %H:%M:%S")
-                        .unwrap()
-                        .with_timezone(&Utc);
-                    let dt_end = Utc
-                        .datetime_from_str(get_end_dt, "%Y-%m-%d %H:%M:%S")
-                        .unwrap()
-                        .with_timezone(&Utc);
-                    let fake_string: String = DateTimeBetween(dt_start, dt_end).fake();
-                    fake_string.chars().take(255 as usize).collect()
-                }
-                "Product" => {
-                    let max_length = field.config.max_length.unwrap_or(255);
-                    let adj: String = BsAdj().fake();
-                    let noun: String = BsNoun().fake();
-                    let fake_string: String = format!("{} {}", adj, noun);
-                    let fake_string = fake_string.replace("'", "''");
-                    fake_string.chars().take(max_length as usize).collect()
-                }
-                // add support for other data types if needed
-                _ => panic!("Unsupported data type: -> {} <- \n", field.data_type),
-            };
-            row_values.push(fake_value);
+        });
+
+        insert_query.build().execute(&mut tx).await?;
+
+        remaining -= batch;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+// Sets the deferred references of the rows already in the table, `rows` comes from
+// `RowGenerator::deferred`. Rows are matched on their ctid, which changes with every
+// update, so all deferred columns of the table are set in one pass.
+async fn update_deferred_references(
+    pool: &DbPool,
+    table: &Table,
+    tables: &[Table],
+    mut rows: RowGenerator,
+    batch_size: usize,
+) -> Result<(), AppError> {
+    let pool = match pool {
+        DbPool::Postgres(pool) => pool,
+        DbPool::Mysql(pool) => {
+            return mysql::update_deferred_references(pool, table, rows, batch_size).await
+        }
+        DbPool::Sqlite(pool) => {
+            return sqlite::update_deferred_references(pool, table, rows, batch_size).await
         }
-        let row_value_string = format!("'{}'", row_values.join("', '"));
-        values.push(row_value_string);
+        DbPool::Mongo(db) => {
+            return mongo::update_deferred_references(db, table, tables, rows, batch_size).await
+        }
+    };
+    if rows.columns().is_empty() {
+        return Ok(());
     }
+    let tablename = Dialect::Postgres.quote(&table.tablename);
+    let column_types: Vec<String> = rows.columns().iter().map(|c| c.sql_type.clone()).collect();
+    let column_names: Vec<String> = rows
+        .columns()
+        .iter()
+        .map(|c| Dialect::Postgres.quote(&c.name))
+        .collect();
+    let assignments: Vec<String> = column_names
+        .iter()
+        .map(|name| format!("{} = deferred.{}", name, name))
+        .collect();
 
-    let insert_query = format!(
-        "INSERT INTO {} ({}) VALUES ({});",
-        tablename,
-        fields
-            .iter()
-            .filter(|f| f.data_type.to_lowercase() != "serial")
-            .map(|f| f.fieldname.clone())
-            .collect::<Vec<String>>()
-            .join(", "),
-        values.join("), (")
-    );
+    let rows_per_batch = batch_size
+        .min(MAX_BIND_PARAMS / (column_names.len() + 1))
+        .max(1);
+
+    let mut tx = pool.begin().await?;
+
+    // Physical order, so a run with the same seed sets the same values on the same rows
+    let row_ids: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT ctid::text FROM {} ORDER BY ctid",
+        tablename
+    ))
+    .fetch_all(&mut tx)
+    .await?;
+
+    for batch in row_ids.chunks(rows_per_batch) {
+        let mut update_query: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+            "UPDATE {} SET {} FROM (",
+            tablename,
+            assignments.join(", ")
+        ));
+        let values = rows.next_rows(batch.len())?;
+        update_query.push_values(batch.iter().zip(values), |mut row, (row_id, values)| {
+            row.push("CAST(");
+            row.push_bind_unseparated(row_id.clone());
+            row.push_unseparated(" AS tid)");
+            for (value, sql_type) in values.into_iter().zip(&column_types) {
+                row.push("CAST(");
+                push_bind_value(&mut row, value);
+                row.push_unseparated(format!(" AS {})", sql_type));
+            }
+        });
+        update_query.push(format!(
+            ") AS deferred (ds_row_id, {}) WHERE {}.ctid = deferred.ds_row_id",
+            column_names.join(", "),
+            tablename
+        ));
+
+        update_query.build().execute(&mut tx).await?;
+    }
 
+    tx.commit().await?;
+    Ok(())
+}
 
-    sqlx::query(&insert_query)
-        .execute(&pool)
-        .await
-        .expect("Failed to insert data");
+// Schema stored by /add_schema
+async fn find_schema(state: &AppState, schema_id: &str) -> Result<AddSchemaRequest, AppError> {
+    // Access the database and collection
+    let db = state.mongo_db();
+    let collection: Collection<AddSchemaRequest> = db.collection("schemas");
 
-    HttpResponse::Created().json(CreateDataResponse {
-        response: "Data created and Added successfully".to_string(),
-        id: "id".to_string(),
-    })
+    // Convert the schema_id string to an ObjectId and use it to retrieve the document from MongoDB
+    let oid = ObjectId::parse_str(schema_id).map_err(|_| {
+        AppError::invalid_request(format!("-> {} <- is not a valid schema_id", schema_id))
+    })?;
+
+    // Define the filter to search for the document with the given `oid`
+    let filter = doc! { "_id": oid };
+
+    // Define the options for the find_one operation (optional)
+    let options = FindOneOptions::builder().build();
+
+    collection
+        .find_one(filter, options)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No schema found with id {}", oid)))
+}
+
+// Every problem in a schema to export. The files hold postgres types and the sql format
+// is a postgres script, whatever the targets of the server are.
+fn export_errors(database: &str, tables: &[Table], max_unique_rows: usize) -> Vec<SchemaError> {
+    let mut errors = validation::validate_schema(database, tables);
+    errors.extend(validation::validate_limits(tables, max_unique_rows));
+    errors.extend(validation::validate_dialect(tables, Dialect::Postgres));
+    errors
+}
+
+// Streams the files of a schema as a download, nothing is written to a database
+fn export_response(
+    state: &AppState,
+    database: &str,
+    tables: Vec<Table>,
+    seed: Option<u64>,
+    format: FileFormat,
+) -> Result<HttpResponse, AppError> {
+    let errors = export_errors(database, &tables, state.config.max_unique_rows);
+    if !errors.is_empty() {
+        return Err(AppError::invalid_schema(errors));
+    }
+
+    let seed = seed.unwrap_or_else(seed::random_seed);
+    let body = export::export_stream(tables, seed, format, state.config.insert_batch_size);
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", format.file_name(database)),
+        ))
+        // Sending it back as `seed` reproduces the same files
+        .insert_header(("X-Seed", seed.to_string()))
+        .streaming(body))
 }
 
 // // ----- HELPER FUNCTIONS END ----- // //
@@ -1041,144 +973,183 @@ async fn create_and_insert_data(
 // // ----- HANDLER FUNCTIONS START ----- // //
 
 //HANDLE ADD SCHEMA
-async fn handle_add_schema_req(req: web::Json<AddSchemaRequest>) -> impl Responder {
-    // Create a MongoDB client and connect to your server
-    let client = Client::with_uri_str("mongodb://localhost:27017/")
-        .await
-        .unwrap();
+async fn handle_add_schema_req(
+    state: web::Data<AppState>,
+    json: web::Json<JsonValue>,
+) -> Result<HttpResponse, AppError> {
+    // Only schemas that can be generated are stored
+    let req: AddSchemaRequest = parse_request(json.into_inner())?;
+    let errors = schema_errors(&state, &req.database, req.target.as_deref(), &req.tables);
+    if !errors.is_empty() {
+        return Err(AppError::invalid_schema(errors));
+    }
 
     // Access the database and collection that you want to use
-    let db = client.database("datasynth");
+    let db = state.mongo_db();
     // let collection: Collection<AddSchemaRequest> = db.collection(&req.database.clone());
     let collection: Collection<AddSchemaRequest> = db.collection("schemas"); //"schemas"
 
     // Insert the document into the collection using the insert_one method
-    let result = collection.insert_one(req.into_inner(), None).await.unwrap();
+    let seed = req.seed;
+    let result = collection.insert_one(req, None).await?;
 
     // Retrieve the _id field of the inserted document and return it in the response
-    let id = result.inserted_id.as_object_id().unwrap().to_hex();
+    let id = result
+        .inserted_id
+        .as_object_id()
+        .ok_or_else(|| AppError::internal("The stored schema has no ObjectId"))?
+        .to_hex();
 
-    HttpResponse::Created().json(CreateDataResponse {
+    Ok(HttpResponse::Created().json(CreateDataResponse {
         response: "yes".to_string(),
-        id: id,
-    })
+        id,
+        seed,
+    }))
 }
 
 //HANDLE CREATE AND INSERT DATA
 async fn handle_create_table_and_insert_data_req(
+    state: web::Data<AppState>,
     req: web::Json<CreateDataUsingSchemaIdRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, AppError> {
     // Getting the request JSON
     let create_data_using_id_request = req.into_inner();
 
-    // Connect to the MongoDB server and access the database and collection
-    let client = Client::with_uri_str("mongodb://localhost:27017/")
-        .await
-        .unwrap();
+    let document = find_schema(&state, &create_data_using_id_request.schema_id).await?;
 
-    let db = client.database("datasynth");
-    let collection: Collection<AddSchemaRequest> = db.collection("schemas");
-
-    // Convert the schema_id string to an ObjectId and use it to retrieve the document from MongoDB
-    let oid = ObjectId::parse_str(&create_data_using_id_request.schema_id).unwrap();
-    // println!("Searching for document with id: {:?}", oid);
-
-    // Define the filter to search for the document with the given `oid`
-    let filter = doc! { "_id": oid };
-
-    // Define the options for the find_one operation (optional)
-    let options = FindOneOptions::builder().build();
-
-    // Execute the find_one operation and print the result
-    let document = match collection.find_one(filter, options).await {
-        Ok(Some(result)) => {
-            // println!("Found document: {:?}", result);
-            result
-        }
-        Ok(None) => {
-            println!("No document found with id: {:?}", oid);
-            return HttpResponse::NotFound().finish();
-        }
-        Err(e) => {
-            eprintln!("Error finding document: {:?}", e);
-            return HttpResponse::InternalServerError().finish();
-        }
-    };
-
-    let json = match serde_json::to_value(document) {
-        Ok(value) => value,
-        Err(e) => {
-            eprintln!("Error converting document to JSON: {:?}", e);
-            return HttpResponse::InternalServerError().finish();
-        }
-    };
-
-    // CREATE TABLES AND INSERT DATA ACCORDING TO THE JSON
+    // CREATE TABLES AND INSERT DATA ACCORDING TO THE SCHEMA
     // THIS IS FOR POSTGRESQL DATABASE
-    handle_create_tables_and_data_req(web::Json(json)).await;
+    let create_data_request = CreateDataRequest {
+        database: document.database,
+        // A target given in the request wins over the one stored with the schema
+        target: create_data_using_id_request.target.or(document.target),
+        seed: create_data_using_id_request.seed.or(document.seed),
+        tables: document.tables,
+    };
+    let seed = generate_data(&state, create_data_request).await?;
 
-    // HttpResponse::Ok().json(json)
-    HttpResponse::Created().json(NewCreateDataResponse {
+    Ok(HttpResponse::Created().json(NewCreateDataResponse {
         response: "ok_response".to_string(),
-    })
+        seed,
+    }))
 }
 
-#[derive(Deserialize)]
-struct CreateDataRequest {
-    database: String,
-    tables: Vec<Table>,
+async fn handle_create_tables_and_data_req(
+    state: web::Data<AppState>,
+    json: web::Json<JsonValue>,
+) -> Result<HttpResponse, AppError> {
+    // Getting the request JSON
+    let create_data_request: CreateDataRequest = parse_request(json.into_inner())?;
+    let seed = generate_data(&state, create_data_request).await?;
+
+    let response: String = "Data created and added successfully".to_string();
+    // Nothing is stored for an inline schema, the seed is what brings the run back
+    Ok(HttpResponse::Created().json(NewCreateDataResponse { response, seed }))
 }
 
-async fn handle_create_tables_and_data_req(json: web::Json<Value>) -> impl Responder {
-    // Getting the request JSON
-    let create_data_request: CreateDataRequest = serde_json::from_value(json.into_inner())
-        .map_err(|e| HttpResponse::BadRequest().body(format!("Invalid JSON: {}", e)))
-        .unwrap();
+// Creates the database and tables of a schema and fills them, returns the seed used
+async fn generate_data(state: &AppState, req: CreateDataRequest) -> Result<u64, AppError> {
+    let database = &req.database;
+    let tables = &req.tables;
+    let target = req.target.as_deref();
+
+    // Refuse the schema before anything is created in the database
+    let errors = schema_errors(state, database, target, tables);
+    if !errors.is_empty() {
+        return Err(AppError::invalid_schema(errors));
+    }
+    let admin_pool = state
+        .maintenance_pool(target)
+        .map_err(AppError::unknown_target)?;
+    let pool = state
+        .pool(target, database)
+        .map_err(AppError::unknown_target)?;
+
+    if let Some(admin_pool) = admin_pool {
+        create_database(&admin_pool, database).await?;
+    }
 
-    let database = &create_data_request.database;
-    let tables = &create_data_request.tables;
+    // Referenced tables come first, whatever their position in the schema
+    let order: Vec<&Table> = fill_order(tables).into_iter().map(|i| &tables[i]).collect();
 
     // Creating tables in database
-    for i in 0..tables.len() {
-        create_table(&tables[i].tablename, &tables[i].fields, &database).await;
+    let mut created = Vec::new();
+    let defer_indexes = |table: &Table| table.defer_indexes.unwrap_or(state.config.defer_indexes);
+    for &table in &order {
+        if create_table(&pool, table, tables).await? {
+            if !defer_indexes(table) {
+                create_indexes(&pool, table)
+                    .await
+                    .map_err(|err| err.in_table(&table.tablename))?;
+            }
+            created.push(table);
+        }
     }
 
     // creating fake data and inserting into the tables
-    for i in 0..tables.len() {
+    // Parents come before their children, so their keys are loaded by the time a child is filled
+    let seed = req.seed.unwrap_or_else(seed::random_seed);
+    let mut keys = KeyStore::default();
+    for &table in &order {
+        let batch_size = table.batch_size.unwrap_or(state.config.insert_batch_size);
+        let load_mode = table.load_mode.unwrap_or(state.config.load_mode);
+        let table_seed = seed::table_seed(seed, table);
+        let rows = RowGenerator::new(table, tables, &keys, table_seed)
+            .map_err(|err| err.in_table(&table.tablename))?;
         create_and_insert_data(
-            &tables[i].tablename,
-            &tables[i].datasize,
-            &tables[i].fields,
-            &database,
+            &pool, table, tables, rows, table_seed, batch_size, load_mode,
         )
-        .await;
+        .await
+        .map_err(|err| err.in_table(&table.tablename))?;
+        keys.load(&pool, table, tables)
+            .await
+            .map_err(|err| err.in_table(&table.tablename))?;
     }
 
-    let response: String = format!("Data created and added successfully");
-    HttpResponse::Created().json(CreateDataResponse {
-        response: response,
-        id: "id".to_string(),
-    })
+    // References inside a cycle are set now that every table has rows
+    for &table in &order {
+        let batch_size = table.batch_size.unwrap_or(state.config.insert_batch_size);
+        let rows = RowGenerator::deferred(table, tables, &keys, seed::table_seed(seed, table))
+            .map_err(|err| err.in_table(&table.tablename))?;
+        update_deferred_references(&pool, table, tables, rows, batch_size)
+            .await
+            .map_err(|err| err.in_table(&table.tablename))?;
+    }
+    for table in created {
+        add_deferred_constraints(&pool, table, tables)
+            .await
+            .map_err(|err| err.in_table(&table.tablename))?;
+        if defer_indexes(table) {
+            create_indexes(&pool, table)
+                .await
+                .map_err(|err| err.in_table(&table.tablename))?;
+        }
+    }
+
+    Ok(seed)
 }
 
 async fn add_relations(
-    database: &str,
+    pool: &DbPool,
     primary_table: &str,
     secondary_table: &str,
+    embed: bool,
 ) -> anyhow::Result<()> {
-    // Connecting to PostgreSQL
-    let connect_options = PgConnectOptions::new()
-        .username("postgres")
-        .password("password")
-        .host("localhost")
-        .database(database);
-
-    let pool = PgPool::connect_with(connect_options)
-        .await
-        .expect("Failed to create database pool");
+    let pool = match pool {
+        DbPool::Postgres(pool) => pool,
+        DbPool::Mysql(pool) => {
+            return mysql::add_relations(pool, primary_table, secondary_table).await
+        }
+        DbPool::Sqlite(pool) => {
+            return sqlite::add_relations(pool, primary_table, secondary_table).await
+        }
+        DbPool::Mongo(db) => {
+            return mongo::add_relations(db, primary_table, secondary_table, embed).await
+        }
+    };
 
     // //1. Identify the primary key column(s) of the `primary_table`.
-    let primary_key_columns = get_primary_key_columns(&pool, primary_table).await?;
+    let primary_key_columns = get_primary_key_columns(pool, primary_table).await?;
 
     let mut alter_table_sql = format!("ALTER TABLE {} ", secondary_table);
     // Generate ALTER TABLE statement to add new columns with foreign keys constraints
@@ -1202,18 +1173,11 @@ async fn add_relations(
         pk_columns.join(", ")
     );
 
-    sqlx::query(&alter_table_sql)
-        .execute(&pool)
-        .await
-        .expect("Failed to create coloumns in secondary table");
+    sqlx::query(&alter_table_sql).execute(pool).await?;
 
     // // FOREIGN KEY COLUMN CREATED IN THE SECONDARY TABLE
     // // FUNCTION TO POPULATE THE CREATED FOREIGN KEY
-    match (populate_secondary_table_with_primary_keys(primary_table, secondary_table, &pool)).await
-    {
-        Ok(()) => println!("Relations added successfully"),
-        Err(err) => eprintln!("Error adding relations: {}", err),
-    };
+    populate_secondary_table_with_primary_keys(primary_table, secondary_table, pool).await?;
 
     Ok(())
 }
@@ -1222,13 +1186,14 @@ async fn add_relations(
 // A function that takes a postgres pool and a table name as input
 // and returns a vector of column names as strings
 async fn get_column_names(pool: &PgPool, table_name: &str) -> anyhow::Result<Vec<String>> {
-    let query = format!(
+    let rows = sqlx::query(
         "SELECT column_name
          FROM information_schema.columns
-         WHERE table_name = '{}'",
-        table_name
-    );
-    let rows = sqlx::query(&query).fetch_all(pool).await?;
+         WHERE table_name = $1",
+    )
+    .bind(table_name)
+    .fetch_all(pool)
+    .await?;
     let columns = rows.into_iter().map(|row| row.get(0)).collect();
     Ok(columns)
 }
@@ -1239,8 +1204,8 @@ async fn populate_secondary_table_with_primary_keys(
     secondary_table: &str,
     pool: &PgPool,
 ) -> anyhow::Result<()> {
-    let primary_key_columns = get_only_primary_key_columns(&pool, &primary_table).await?;
-   
+    let primary_key_columns = get_only_primary_key_columns(pool, primary_table).await?;
+
     let mut primary_key_query = String::new();
     for column_name in &primary_key_columns {
         primary_key_query.push_str(&format!("{} = c.{}, ", column_name, column_name));
@@ -1298,7 +1263,6 @@ async fn populate_secondary_table_with_primary_keys(
 
     // print!("query -> {query}"); // This is for debugging
 
-
     // Update the secondary table with random values from the primary table
     sqlx::query(&query).execute(pool).await?;
 
@@ -1310,17 +1274,18 @@ async fn get_only_primary_key_columns(
     pool: &PgPool,
     table_name: &str,
 ) -> anyhow::Result<Vec<String>> {
-    let query = format!(
+    let rows = sqlx::query(
         "SELECT column_name
          FROM information_schema.key_column_usage
          WHERE constraint_name IN (
              SELECT constraint_name
              FROM information_schema.table_constraints
-             WHERE table_name = '{}' AND constraint_type = 'PRIMARY KEY'
+             WHERE table_name = $1 AND constraint_type = 'PRIMARY KEY'
          )",
-        table_name
-    );
-    let rows = sqlx::query(&query).fetch_all(pool).await?;
+    )
+    .bind(table_name)
+    .fetch_all(pool)
+    .await?;
     let primary_key_columns = rows.into_iter().map(|row| row.get(0)).collect();
     Ok(primary_key_columns)
 }
@@ -1330,15 +1295,16 @@ async fn get_primary_key_columns(
     pool: &PgPool,
     table_name: &str,
 ) -> anyhow::Result<Vec<(String, String)>> {
-    let query = format!(
+    let rows = sqlx::query(
         "SELECT c.column_name, c.data_type
          FROM information_schema.table_constraints tc
          JOIN information_schema.constraint_column_usage ccu ON tc.constraint_name = ccu.constraint_name
          JOIN information_schema.columns c ON c.table_name = tc.table_name AND ccu.column_name = c.column_name
-         WHERE constraint_type = 'PRIMARY KEY' AND tc.table_name = '{}'",
-        table_name
-    );
-    let rows = sqlx::query(&query).fetch_all(pool).await?;
+         WHERE constraint_type = 'PRIMARY KEY' AND tc.table_name = $1",
+    )
+    .bind(table_name)
+    .fetch_all(pool)
+    .await?;
     let primary_key_columns = rows
         .into_iter()
         .map(|row| (row.get(0), row.get(1)))
@@ -1347,101 +1313,179 @@ async fn get_primary_key_columns(
 }
 
 //HANDLE CREATE RELATIONS BETWEEN EXISTING TABLES
-async fn handle_add_relations_in_tables_req(req: web::Json<CreateRelation>) -> impl Responder {
+async fn handle_add_relations_in_tables_req(
+    state: web::Data<AppState>,
+    req: web::Json<CreateRelation>,
+) -> Result<HttpResponse, AppError> {
     //function to add relations in db
     let relations = req.into_inner();
-    match add_relations(
-        &relations.database,
+    check_names(&[
+        ("database", &relations.database),
+        ("primary_table", &relations.primary_table),
+        ("secondary_table", &relations.secondary_table),
+    ])?;
+    let pool = state
+        .pool(relations.target.as_deref(), &relations.database)
+        .map_err(AppError::unknown_target)?;
+    let embed = relations.embed.unwrap_or(false);
+    if embed && pool.dialect() != Dialect::Mongodb {
+        return Err(AppError::invalid_request(
+            "Relations can only be embedded on mongodb targets",
+        ));
+    }
+    add_relations(
+        &pool,
         &relations.primary_table,
         &relations.secondary_table,
+        embed,
     )
     .await
-    {
-        Ok(()) => println!("Relations added successfully"),
-        Err(err) => eprintln!("Error adding relations: {}", err),
-    }
-
-    // Create a MongoDB client and connect to your server
-    let client = Client::with_uri_str("mongodb://localhost:27017/")
-        .await
-        .unwrap();
+    .map_err(|err| AppError::from(err).in_table(&relations.secondary_table))?;
 
     // Access the database and collection that you want to use
-    let db = client.database("datasynth");
+    let db = state.mongo_db();
     let collection_name = format!("{}_relations", &relations.database.clone());
     let collection: Collection<CreateRelation> = db.collection(&collection_name);
 
     // Insert the document into the collection using the insert_one method
-    let result = collection.insert_one(relations, None).await.unwrap();
+    let result = collection.insert_one(relations, None).await?;
 
     // Retrieve the _id field of the inserted document and return it in the response
-    let id = result.inserted_id.as_object_id().unwrap().to_hex();
+    let id = result
+        .inserted_id
+        .as_object_id()
+        .ok_or_else(|| AppError::internal("The stored relation has no ObjectId"))?
+        .to_hex();
 
-    HttpResponse::Created().json(CreateRelationRes {
+    Ok(HttpResponse::Created().json(CreateRelationRes {
         relation_id: id,
         response: "Relation created successfully".to_string(),
-    })
+    }))
 }
 
 //HANDLE DELETE RELATIONS BETWEEN TABLES
 async fn handle_delete_relations_in_tables_req(
+    state: web::Data<AppState>,
     req: web::Json<DeleteDataRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, AppError> {
     // Getting the request JSON
     let DeleteDataRequest {
         database,
+        target,
         request_id,
     } = req.into_inner();
-
-    let connect_options = PgConnectOptions::new()
-        .username("postgres")
-        .password("password")
-        .host("localhost")
-        .database(&database);
-
-    let pool = PgPool::connect_with(connect_options)
-        .await
-        .expect("Failed to create database pool");
+    check_names(&[("database", &database)])?;
+
+    let DbPool::Postgres(pool) = state
+        .pool(target.as_deref(), &database)
+        .map_err(AppError::unknown_target)?
+    else {
+        return Err(AppError::invalid_request(
+            "Relations can only be deleted on postgres targets",
+        ));
+    };
 
     // -- TODO - HARDCODED VALUES -- TO BE CHANGED LATER ON
     let get_relation_query =
         "SELECT primary_table, secondary_table FROM relations WHERE unique_id = $1";
     let relation = sqlx::query_as::<_, (String, String)>(get_relation_query)
         .bind(&request_id)
-        .fetch_one(&pool)
-        .await;
-
-    match relation {
-        Ok(relation) => {
-            let delete_query = format!(
-                "ALTER TABLE {} DROP COLUMN {}_id",
-                relation.1,
-                relation.0.to_lowercase()
-            );
-
-            sqlx::query(&delete_query)
-                .execute(&pool)
-                .await
-                .expect("Failed to delete relation");
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No relation found with id {}", request_id)))?;
+    // Stored before the names of relations were checked
+    check_names(&[
+        ("primary_table", &relation.0),
+        ("secondary_table", &relation.1),
+    ])?;
+
+    let delete_query = format!(
+        "ALTER TABLE {} DROP COLUMN {}_id",
+        relation.1,
+        relation.0.to_lowercase()
+    );
 
-            // -- TODO - HARDCODED VALUES -- TO BE CHANGED LATER ON
-            let delete_uuid_query = "DELETE FROM relations WHERE unique_id = $1";
-            sqlx::query(delete_uuid_query)
-                .bind(&request_id)
-                .execute(&pool)
-                .await
-                .expect("Failed to delete UUID from relations table");
+    sqlx::query(&delete_query)
+        .execute(&pool)
+        .await
+        .map_err(|err| AppError::from(err).in_table(&relation.1))?;
 
-            HttpResponse::Ok().json(DeleteRelationRes {
-                relation_id: request_id,
-                response: "Relation Deleted Successfully".to_string(),
-            })
-        }
-        Err(_) => HttpResponse::NotFound().json(DeleteRelationRes {
-            relation_id: request_id,
-            response: "Relation not found".to_string(),
-        }),
-    }
+    // -- TODO - HARDCODED VALUES -- TO BE CHANGED LATER ON
+    let delete_uuid_query = "DELETE FROM relations WHERE unique_id = $1";
+    sqlx::query(delete_uuid_query)
+        .bind(&request_id)
+        .execute(&pool)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(DeleteRelationRes {
+        relation_id: request_id,
+        response: "Relation Deleted Successfully".to_string(),
+    }))
+}
+
+//HANDLE VALIDATE SCHEMA
+async fn handle_validate_schema_req(
+    state: web::Data<AppState>,
+    json: web::Json<JsonValue>,
+) -> impl Responder {
+    let errors = match validation::parse::<AddSchemaRequest>(json.into_inner()) {
+        Ok(req) => schema_errors(&state, &req.database, req.target.as_deref(), &req.tables),
+        Err(errors) => errors,
+    };
+
+    HttpResponse::Ok().json(ValidateSchemaRes {
+        valid: errors.is_empty(),
+        errors,
+    })
+}
+
+//HANDLE LIST DATA TYPES
+async fn handle_list_data_types_req() -> impl Responder {
+    let data_types: Vec<DataTypeRes> = registry()
+        .iter()
+        .map(|generator| {
+            let field = Field {
+                data_type: generator.name().to_string(),
+                ..Field::default()
+            };
+            let sql_types = Dialect::ALL
+                .iter()
+                .map(|dialect| (*dialect, generator.sql_type(&field, *dialect)))
+                .collect();
+            DataTypeRes {
+                name: generator.name(),
+                sql_types,
+            }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(data_types)
+}
+
+//HANDLE EXPORT DATA
+async fn handle_export_data_req(
+    state: web::Data<AppState>,
+    json: web::Json<JsonValue>,
+) -> Result<HttpResponse, AppError> {
+    let req: ExportDataRequest = parse_request(json.into_inner())?;
+    export_response(&state, &req.database, req.tables, req.seed, req.format)
+}
+
+//HANDLE EXPORT STORED SCHEMA
+async fn handle_export_schema_req(
+    state: web::Data<AppState>,
+    schema_id: web::Path<String>,
+    query: web::Query<ExportSchemaQuery>,
+) -> Result<HttpResponse, AppError> {
+    let document = find_schema(&state, &schema_id).await?;
+    let seed = query.seed.or(document.seed);
+    export_response(
+        &state,
+        &document.database,
+        document.tables,
+        seed,
+        query.format,
+    )
 }
 
 // // ----- HANDLER FUNCTIONS END ----- // //
@@ -1449,13 +1493,43 @@ async fn handle_delete_relations_in_tables_req(
 // // ----- ACTIX WEB HANDLES THE REST FEATURES ----- // //
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let config = AppConfig::load().unwrap_or_else(|err| {
+        eprintln!("Failed to load configuration: {:#}", err);
+        std::process::exit(1);
+    });
+    let bind = config.bind.clone();
+    let state = match AppState::new(config).await {
+        Ok(state) => web::Data::new(state),
+        Err(err) => {
+            eprintln!("Failed to create the mongo client: {:#}", err);
+            std::process::exit(1);
+        }
+    };
+
     HttpServer::new(move || {
         App::new()
+            .app_data(state.clone())
+            // Bodies that aren't JSON get the same error format as everything else
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|err, _| AppError::invalid_request(err.to_string()).into()),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|err, _| AppError::invalid_request(err.to_string()).into()),
+            )
             .service(web::resource("/add_schema").route(web::post().to(handle_add_schema_req)))
+            .service(
+                web::resource("/validate_schema").route(web::post().to(handle_validate_schema_req)),
+            )
             .service(
                 web::resource("/create_table_and_insert_data")
                     .route(web::post().to(handle_create_table_and_insert_data_req)),
             )
+            .service(
+                web::resource("/create_tables_and_data")
+                    .route(web::post().to(handle_create_tables_and_data_req)),
+            )
             .service(
                 web::resource("/add_relations_in_tables")
                     .route(web::post().to(handle_add_relations_in_tables_req)),
@@ -1464,8 +1538,50 @@ async fn main() -> std::io::Result<()> {
                 web::resource("/delete_relations_in_tables")
                     .route(web::delete().to(handle_delete_relations_in_tables_req)),
             )
+            .service(web::resource("/data_types").route(web::get().to(handle_list_data_types_req)))
+            .service(web::resource("/export_data").route(web::post().to(handle_export_data_req)))
+            .service(
+                web::resource("/export_data/{schema_id}")
+                    .route(web::get().to(handle_export_schema_req)),
+            )
     })
-    .bind("127.0.0.1:3000")?
+    .bind(bind)?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relation_endpoints_must_be_plain_names() {
+        check_names(&[("database", "shop"), ("primary_table", "customers")]).unwrap();
+        let err = check_names(&[
+            ("database", "shop"),
+            ("primary_table", "customers; DROP TABLE orders"),
+            ("secondary_table", "../orders"),
+        ])
+        .unwrap_err();
+        let paths: Vec<&str> = err.errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["primary_table", "secondary_table"]);
+    }
+
+    #[test]
+    fn exports_are_checked_against_postgres() {
+        let tables: Vec<Table> = serde_json::from_value(serde_json::json!([
+            {"tablename": "customers", "datasize": 2, "fields": [
+                {"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}}
+            ]},
+            {"tablename": "orders", "datasize": 2, "fields": [{
+                "fieldname": "customer",
+                "data_type": "REFERENCE",
+                "config": {"foreign_key": {"table": "customers", "field": "id", "embed": true}}
+            }]}
+        ]))
+        .unwrap();
+        let errors = export_errors("shop", &tables, 100);
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["tables[1].fields[0].config.foreign_key.embed"]);
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use bson::oid::ObjectId;
+use bson::{doc, Bson, Document};
+use futures_util::TryStreamExt;
+use mongodb::options::{AggregateOptions, FindOptions, IndexOptions};
+use mongodb::{Database, IndexModel};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::dialect::Dialect;
+use crate::error::{AppError, ErrorCode};
+use crate::generators::registry;
+use crate::references::{
+    column_sql_type, embedded_name, find_field, find_table, is_embedded, reference_groups,
+};
+use crate::rows::{unique_keys, RowGenerator};
+use crate::seed::{field_seed, seeded_rng, SeededRng};
+use crate::value::Value;
+use crate::{index_name, IndexMethod, Table};
+
+// // ----- MONGODB TARGETS ----- // //
+// Every table becomes a collection and every row a document with native BSON values,
+// typed by the generators (`Dialect::Mongodb`). Serial fields get an ObjectId drawn from
+// their seeded stream, so references to them hold the parent's ObjectId. Embedded
+// references hold the whole parent document instead of its key. Deferred references
+// are set by _id.
+
+// Most writes mongo takes in one command
+const MAX_WRITE_BATCH: usize = 100_000;
+
+// Creates the collection of a table, returns false when it was already there
+pub async fn create_collection(db: &Database, name: &str) -> Result<bool, AppError> {
+    let existing = db.list_collection_names(doc! { "name": name }).await?;
+    if !existing.is_empty() {
+        return Ok(false);
+    }
+    db.create_collection(name, None).await?;
+    Ok(true)
+}
+
+// Creates the unique keys and secondary indexes of a table. Btree indexes become
+// ascending indexes, hash indexes hashed ones.
+pub async fn create_indexes(db: &Database, table: &Table) -> Result<(), AppError> {
+    let mut models = Vec::new();
+    for key in unique_keys(table) {
+        let fields: Vec<&str> = key.iter().map(|f| f.fieldname.as_str()).collect();
+        let mut keys = Document::new();
+        for field in &fields {
+            keys.insert(*field, 1);
+        }
+        let options = IndexOptions::builder()
+            .name(format!("{}_{}_key", table.tablename, fields.join("_")))
+            .unique(true)
+            .build();
+        models.push(IndexModel::builder().keys(keys).options(options).build());
+    }
+    for index in &table.indexes {
+        let mut keys = Document::new();
+        for name in &index.fields {
+            // Names as the table spells them, documents are case sensitive
+            let field = find_field(table, name).map_or(name.as_str(), |f| f.fieldname.as_str());
+            match index.method {
+                IndexMethod::Hash => keys.insert(field, "hashed"),
+                IndexMethod::Btree | IndexMethod::Gin => keys.insert(field, 1),
+            };
+        }
+        let options = IndexOptions::builder()
+            .name(index_name(table, index))
+            .build();
+        models.push(IndexModel::builder().keys(keys).options(options).build());
+    }
+    if !models.is_empty() {
+        db.collection::<Document>(&table.tablename)
+            .create_indexes(models, None)
+            .await?;
+    }
+    Ok(())
+}
+
+// Turns the rows of one table into document fields
+struct DocumentBuilder {
+    names: Vec<String>,
+    // BSON type of every column, the values are converted to it
+    types: Vec<String>,
+    embeds: Vec<Embed>,
+}
+
+// An embedded reference: the columns holding the parent key give way to the parent document
+struct Embed {
+    name: String,
+    columns: Vec<usize>,
+    parents: HashMap<String, Document>,
+}
+
+// Lookup key of a parent document, both sides of the lookup come from `Value::from_bson`
+fn parent_key(values: &[Value]) -> String {
+    format!("{:?}", values)
+}
+
+impl DocumentBuilder {
+    async fn new(
+        db: &Database,
+        table: &Table,
+        tables: &[Table],
+        rows: &RowGenerator,
+    ) -> Result<Self, AppError> {
+        let names: Vec<String> = rows.columns().iter().map(|c| c.name.clone()).collect();
+        let types = names
+            .iter()
+            .map(|name| {
+                find_field(table, name)
+                    .and_then(|field| column_sql_type(field, tables, Dialect::Mongodb))
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let mut embeds = Vec::new();
+        for group in reference_groups(table).iter().filter(|g| is_embedded(g)) {
+            let columns: Vec<usize> = group
+                .fields
+                .iter()
+                .filter_map(|(_, f)| names.iter().position(|name| *name == f.fieldname))
+                .collect();
+            if columns.is_empty() {
+                continue;
+            }
+
+            // Names as the parent table spells them, documents are case sensitive
+            let parent_table = find_table(tables, group.parent);
+            let collection = parent_table.map_or(group.parent, |t| t.tablename.as_str());
+            let parent_fields: Vec<&str> = group
+                .parent_fields
+                .iter()
+                .map(|name| {
+                    parent_table
+                        .and_then(|t| find_field(t, name))
+                        .map_or(*name, |f| f.fieldname.as_str())
+                })
+                .collect();
+
+            let mut parents = HashMap::new();
+            let mut cursor = db
+                .collection::<Document>(collection)
+                .find(None, None)
+                .await?;
+            while let Some(parent) = cursor.try_next().await? {
+                let key: Vec<Value> = parent_fields
+                    .iter()
+                    .map(|field| {
+                        parent
+                            .get(field)
+                            .cloned()
+                            .map_or(Value::Null, Value::from_bson)
+                    })
+                    .collect();
+                parents.insert(parent_key(&key), parent);
+            }
+            embeds.push(Embed {
+                name: embedded_name(group).to_string(),
+                columns,
+                parents,
+            });
+        }
+
+        Ok(DocumentBuilder {
+            names,
+            types,
+            embeds,
+        })
+    }
+
+    // Adds the fields of one row to `document`
+    fn fill(&self, document: &mut Document, row: Vec<Value>) {
+        let mut values: Vec<Option<Value>> = row.into_iter().map(Some).collect();
+        let mut embedded = Vec::with_capacity(self.embeds.len());
+        for embed in &self.embeds {
+            let key: Vec<Value> = embed
+                .columns
+                .iter()
+                .map(|&column| values[column].take().unwrap_or(Value::Null))
+                .collect();
+            let parent = embed.parents.get(&parent_key(&key)).cloned();
+            embedded.push((&embed.name, parent.map_or(Bson::Null, Bson::Document)));
+        }
+
+        for ((name, bson_type), value) in self.names.iter().zip(&self.types).zip(values) {
+            if let Some(value) = value {
+                document.insert(name, value.into_bson(bson_type));
+            }
+        }
+        for (name, parent) in embedded {
+            document.insert(name, parent);
+        }
+    }
+}
+
+// Serial fields are left out of the rows, mongo has no sequences to fill them. Each one
+// draws its ObjectIds from the stream of the field, `seed` is the seed of the table.
+fn serial_ids(table: &Table, seed: u64) -> Vec<(&str, SeededRng)> {
+    table
+        .fields
+        .iter()
+        .filter(|f| {
+            f.config.foreign_key.is_none()
+                && registry()
+                    .get(&f.data_type)
+                    .is_some_and(|g| g.filled_by_database())
+        })
+        .map(|f| (f.fieldname.as_str(), seeded_rng(field_seed(f, seed))))
+        .collect()
+}
+
+// Inserts `table.datasize` documents with insert_many, `batch_size` at a time
+pub async fn insert_documents(
+    db: &Database,
+    table: &Table,
+    tables: &[Table],
+    mut rows: RowGenerator,
+    seed: u64,
+    batch_size: usize,
+) -> Result<(), AppError> {
+    let builder = DocumentBuilder::new(db, table, tables, &rows).await?;
+    let mut serials = serial_ids(table, seed);
+
+    let collection = db.collection::<Document>(&table.tablename);
+    let mut remaining = table.datasize;
+    while remaining > 0 {
+        let batch = remaining.min(batch_size.clamp(1, MAX_WRITE_BATCH));
+        let documents = (0..batch)
+            .map(|_| {
+                let mut document = Document::new();
+                for (serial, rng) in &mut serials {
+                    document.insert(*serial, ObjectId::from_bytes(rng.gen()));
+                }
+                builder.fill(&mut document, rows.next_row()?);
+                Ok(document)
+            })
+            .collect::<Result<Vec<Document>, AppError>>()?;
+        collection.insert_many(documents, None).await?;
+        remaining -= batch;
+    }
+    Ok(())
+}
+
+// Distinct key tuples of `fields` in the collection of `table`, sorted
+pub async fn load_keys(
+    db: &Database,
+    table: &str,
+    fields: &[&str],
+) -> Result<Vec<Vec<Value>>, AppError> {
+    // The key fields are renamed k0, k1... since field names may hold dots
+    let mut group = Document::new();
+    let mut sort = Document::new();
+    for (i, field) in fields.iter().enumerate() {
+        group.insert(format!("k{}", i), format!("${}", field));
+        sort.insert(format!("_id.k{}", i), 1);
+    }
+    let pipeline = vec![doc! { "$group": { "_id": group } }, doc! { "$sort": sort }];
+    let options = AggregateOptions::builder().allow_disk_use(true).build();
+
+    let mut cursor = db
+        .collection::<Document>(table)
+        .aggregate(pipeline, options)
+        .await?;
+    let mut keys = Vec::new();
+    while let Some(document) = cursor.try_next().await? {
+        let key = document.get_document("_id").ok();
+        keys.push(
+            (0..fields.len())
+                .map(|i| {
+                    key.and_then(|key| key.get(format!("k{}", i)))
+                        .cloned()
+                        .map_or(Value::Null, Value::from_bson)
+                })
+                .collect(),
+        );
+    }
+    Ok(keys)
+}
+
+// _id of every document of a collection matching `filter`, sorted
+async fn document_ids(
+    db: &Database,
+    collection: &str,
+    filter: Option<Document>,
+) -> Result<Vec<Bson>, mongodb::error::Error> {
+    let options = FindOptions::builder()
+        .projection(doc! { "_id": 1 })
+        .sort(doc! { "_id": 1 })
+        .build();
+    let documents: Vec<Document> = db
+        .collection::<Document>(collection)
+        .find(filter, options)
+        .await?
+        .try_collect()
+        .await?;
+    Ok(documents
+        .into_iter()
+        .filter_map(|mut document| document.remove("_id"))
+        .collect())
+}
+
+// Runs one update command, mongo reports the writes that failed in its reply
+async fn run_updates(
+    db: &Database,
+    collection: &str,
+    updates: Vec<Document>,
+) -> Result<(), AppError> {
+    let reply = db
+        .run_command(doc! { "update": collection, "updates": updates }, None)
+        .await?;
+    if let Ok(errors) = reply.get_array("writeErrors") {
+        let message = errors
+            .first()
+            .and_then(Bson::as_document)
+            .and_then(|error| error.get_str("errmsg").ok())
+            .unwrap_or("An update failed");
+        return Err(AppError::new(ErrorCode::Database, message));
+    }
+    Ok(())
+}
+
+// Sets the deferred references of the documents already in the collection, `rows`
+// comes from `RowGenerator::deferred`
+pub async fn update_deferred_references(
+    db: &Database,
+    table: &Table,
+    tables: &[Table],
+    mut rows: RowGenerator,
+    batch_size: usize,
+) -> Result<(), AppError> {
+    if rows.columns().is_empty() {
+        return Ok(());
+    }
+    let builder = DocumentBuilder::new(db, table, tables, &rows).await?;
+
+    // _id order, so a run with the same seed sets the same values in the same order
+    let ids = document_ids(db, &table.tablename, None).await?;
+    for batch in ids.chunks(batch_size.clamp(1, MAX_WRITE_BATCH)) {
+        let updates = batch
+            .iter()
+            .map(|id| {
+                let mut set = Document::new();
+                builder.fill(&mut set, rows.next_row()?);
+                Ok(doc! { "q": { "_id": id.clone() }, "u": { "$set": set } })
+            })
+            .collect::<Result<Vec<Document>, AppError>>()?;
+        run_updates(db, &table.tablename, updates).await?;
+    }
+    Ok(())
+}
+
+// Points every document of `secondary_table` at a random document of `primary_table`,
+// like the postgres `add_relations`. The secondary documents get the primary _id as
+// `<primary_table>_id`, or the whole primary document as `<primary_table>` when embedded.
+pub async fn add_relations(
+    db: &Database,
+    primary_table: &str,
+    secondary_table: &str,
+    embed: bool,
+) -> anyhow::Result<()> {
+    let options = FindOptions::builder()
+        .projection((!embed).then(|| doc! { "_id": 1 }))
+        .build();
+    let mut primary_documents: Vec<Document> = db
+        .collection::<Document>(primary_table)
+        .find(None, options)
+        .await?
+        .try_collect()
+        .await?;
+    if primary_documents.is_empty() {
+        bail!("-> {} <- has no documents", primary_table);
+    }
+    primary_documents.shuffle(&mut rand::thread_rng());
+
+    let field = if embed {
+        primary_table.to_string()
+    } else {
+        format!("{}_id", primary_table)
+    };
+    // Documents pointing at a primary document already keep it
+    let mut missing = Document::new();
+    missing.insert(&field, doc! { "$exists": false });
+    let ids = document_ids(db, secondary_table, Some(missing)).await?;
+
+    let primary_count = primary_documents.len();
+    for (chunk, batch) in ids.chunks(1000).enumerate() {
+        let updates = batch
+            .iter()
+            .zip(chunk * 1000..)
+            .map(|(id, n)| {
+                let primary = &primary_documents[n % primary_count];
+                let value = if embed {
+                    Bson::Document(primary.clone())
+                } else {
+                    primary.get("_id").cloned().unwrap_or(Bson::Null)
+                };
+                let mut set = Document::new();
+                set.insert(&field, value);
+                doc! { "q": { "_id": id.clone() }, "u": { "$set": set } }
+            })
+            .collect();
+        run_updates(db, secondary_table, updates)
+            .await
+            .map_err(|err| anyhow!(err.message))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn builder(columns: &[(&str, &str)], embeds: Vec<Embed>) -> DocumentBuilder {
+        DocumentBuilder {
+            names: columns.iter().map(|(name, _)| name.to_string()).collect(),
+            types: columns.iter().map(|(_, bson)| bson.to_string()).collect(),
+            embeds,
+        }
+    }
+
+    #[test]
+    fn fields_take_the_bson_type_of_their_column() {
+        let id = ObjectId::new();
+        let builder = builder(
+            &[
+                ("small", "int"),
+                ("large", "int"),
+                ("owner", "objectId"),
+                ("day", "date"),
+            ],
+            vec![],
+        );
+        let mut document = Document::new();
+        builder.fill(
+            &mut document,
+            vec![
+                Value::Int(5),
+                Value::Int(1 << 40),
+                Value::Text(id.to_hex()),
+                Value::Date(NaiveDate::from_ymd_opt(1970, 1, 2).unwrap()),
+            ],
+        );
+        assert_eq!(document.get("small"), Some(&Bson::Int32(5)));
+        assert_eq!(document.get("large"), Some(&Bson::Int64(1 << 40)));
+        assert_eq!(document.get("owner"), Some(&Bson::ObjectId(id)));
+        assert_eq!(
+            document.get_datetime("day").unwrap().timestamp_millis(),
+            86_400_000
+        );
+    }
+
+    #[test]
+    fn embedded_references_hold_the_parent_document() {
+        let id = ObjectId::new();
+        let parent = doc! { "_id": id, "name": "Ann" };
+        // The key as `DocumentBuilder::new` reads it from the parent collection
+        let key = parent_key(&[Value::from_bson(Bson::ObjectId(id))]);
+        let embed = Embed {
+            name: "customer".to_string(),
+            columns: vec![0],
+            parents: HashMap::from([(key, parent.clone())]),
+        };
+        let builder = builder(
+            &[("customer_id", "objectId"), ("total", "int")],
+            vec![embed],
+        );
+
+        let mut document = Document::new();
+        builder.fill(&mut document, vec![Value::Text(id.to_hex()), Value::Int(3)]);
+        assert_eq!(document, doc! { "total": 3, "customer": parent });
+
+        // A key without a parent document embeds null
+        let mut document = Document::new();
+        builder.fill(&mut document, vec![Value::Null, Value::Int(3)]);
+        assert_eq!(document.get("customer"), Some(&Bson::Null));
+    }
+
+    #[test]
+    fn serial_ids_are_seeded() {
+        let table: Table = serde_json::from_value(serde_json::json!({
+            "tablename": "t",
+            "datasize": 3,
+            "fields": [
+                {"fieldname": "id", "data_type": "Serial"},
+                {"fieldname": "n", "data_type": "Int"}
+            ]
+        }))
+        .unwrap();
+        let ids = |seed| -> Vec<ObjectId> {
+            let mut serials = serial_ids(&table, seed);
+            assert_eq!(serials.len(), 1);
+            let (name, rng) = &mut serials[0];
+            assert_eq!(*name, "id");
+            (0..3).map(|_| ObjectId::from_bytes(rng.gen())).collect()
+        };
+        assert_eq!(ids(7), ids(7));
+        assert_ne!(ids(7), ids(8));
+    }
+}
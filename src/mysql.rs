@@ -0,0 +1,400 @@
+use anyhow::bail;
+use sqlx::mysql::MySqlPool;
+use sqlx::query_builder::Separated;
+use sqlx::{MySql, QueryBuilder};
+
+use crate::dialect::Dialect;
+use crate::error::AppError;
+use crate::rows::RowGenerator;
+use crate::value::Value;
+use crate::{Table, MAX_BIND_PARAMS};
+
+// // ----- MYSQL TARGETS ----- // //
+// The statements that can't be shared with postgres: mysql has no COPY, no ctid to find
+// the rows again and no UPDATE ... FROM, so rows are inserted with multi row INSERTs and
+// deferred references are set by primary key. Needs MySQL 8 or MariaDB 10.3 (window
+// functions for `add_relations`).
+
+const QUOTE: Dialect = Dialect::Mysql;
+
+pub async fn create_database(admin_pool: &MySqlPool, database: &str) -> Result<(), AppError> {
+    sqlx::query(&format!(
+        "CREATE DATABASE IF NOT EXISTS {}",
+        QUOTE.quote(database)
+    ))
+    .execute(admin_pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn table_exists(pool: &MySqlPool, tablename: &str) -> Result<bool, AppError> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM information_schema.tables
+         WHERE table_schema = DATABASE() AND table_name = ?",
+    )
+    .bind(tablename)
+    .fetch_one(pool)
+    .await?;
+    Ok(count > 0)
+}
+
+// Binds a generated value with the closest mysql type, the server converts it to the column type
+fn push_bind_value(row: &mut Separated<'_, '_, MySql, &str>, value: Value) {
+    match value {
+        Value::Null => row.push_bind(None::<String>),
+        Value::Int(v) => row.push_bind(v),
+        Value::Float(v) => row.push_bind(v),
+        Value::Decimal(v) => row.push_bind(v),
+        Value::Bool(v) => row.push_bind(v),
+        Value::Text(v) => row.push_bind(v),
+        Value::Date(v) => row.push_bind(v),
+        Value::Time(v) => row.push_bind(v),
+        Value::Timestamp(v) => row.push_bind(v),
+        // Bound as is a uuid is sent as 16 bytes, the columns hold its text form
+        Value::Uuid(v) => row.push_bind(v.to_string()),
+        Value::Json(v) => row.push_bind(v),
+        Value::Bytes(v) => row.push_bind(v),
+    };
+}
+
+// Inserts `datasize` rows with multi row INSERT statements in one transaction
+pub async fn insert_rows(
+    pool: &MySqlPool,
+    tablename: &str,
+    datasize: usize,
+    mut rows: RowGenerator,
+    batch_size: usize,
+) -> Result<(), AppError> {
+    let column_names: Vec<String> = rows.columns().iter().map(|c| c.name.clone()).collect();
+    let insert = format!(
+        "INSERT INTO {} ({}) ",
+        QUOTE.quote(tablename),
+        QUOTE.quote_all(&column_names)
+    );
+    let rows_per_batch = batch_size
+        .min(MAX_BIND_PARAMS / column_names.len().max(1))
+        .max(1);
+
+    let mut tx = pool.begin().await?;
+    let mut remaining = datasize;
+    while remaining > 0 {
+        let batch = remaining.min(rows_per_batch);
+        if column_names.is_empty() {
+            // Every column is filled by the database or set later
+            let values = vec!["()"; batch].join(", ");
+            sqlx::query(&format!("{}VALUES {}", insert, values))
+                .execute(&mut tx)
+                .await?;
+        } else {
+            insert_query(&insert, rows.next_rows(batch)?)
+                .build()
+                .execute(&mut tx)
+                .await?;
+        }
+        remaining -= batch;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// INSERT of `rows` after `insert`, which names the table and its columns
+fn insert_query<'a>(
+    insert: &str,
+    rows: impl IntoIterator<Item = Vec<Value>>,
+) -> QueryBuilder<'a, MySql> {
+    let mut query = QueryBuilder::new(insert);
+    query.push_values(rows, |mut row, values| {
+        for value in values {
+            push_bind_value(&mut row, value);
+        }
+    });
+    query
+}
+
+// Sets the deferred references of the rows already in the table, `rows` comes from
+// `RowGenerator::deferred`. Rows are matched on their primary key, validation makes sure
+// tables with deferred references have one on mysql targets.
+pub async fn update_deferred_references(
+    pool: &MySqlPool,
+    table: &Table,
+    mut rows: RowGenerator,
+    batch_size: usize,
+) -> Result<(), AppError> {
+    if rows.columns().is_empty() {
+        return Ok(());
+    }
+    let primary_key: Vec<&str> = table
+        .fields
+        .iter()
+        .filter(|f| f.attributes.is_primary.unwrap_or(false))
+        .map(|f| f.fieldname.as_str())
+        .collect();
+    if primary_key.is_empty() {
+        return Err(AppError::internal(
+            "Deferred references need a primary key on mysql targets",
+        ));
+    }
+    let column_names: Vec<String> = rows.columns().iter().map(|c| c.name.clone()).collect();
+
+    let rows_per_batch = batch_size
+        .min(MAX_BIND_PARAMS / (column_names.len() + primary_key.len()))
+        .max(1);
+
+    let mut tx = pool.begin().await?;
+
+    // Key order, so a run with the same seed sets the same values on the same rows
+    let key_rows = sqlx::query(&format!(
+        "SELECT {0} FROM {1} ORDER BY {0}",
+        QUOTE.quote_all(&primary_key),
+        QUOTE.quote(&table.tablename)
+    ))
+    .fetch_all(&mut tx)
+    .await?;
+    let row_keys = key_rows
+        .iter()
+        .map(|row| {
+            (0..primary_key.len())
+                .map(|i| Value::decode_mysql(row, i))
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<Value>>, _>>()?;
+
+    for batch in row_keys.chunks(rows_per_batch) {
+        let rows = batch
+            .iter()
+            .map(|key| Ok(key.iter().cloned().chain(rows.next_row()?).collect()))
+            .collect::<Result<_, AppError>>()?;
+        deferred_update_query(&table.tablename, &primary_key, &column_names, rows)
+            .build()
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+// UPDATE joining the rows of the table on a derived table of `rows`: the primary key of
+// each row followed by the values of `column_names`. The derived table names the key
+// columns ds_key_0, ds_key_1... and the others as is.
+fn deferred_update_query<'a>(
+    tablename: &str,
+    primary_key: &[&str],
+    column_names: &[String],
+    rows: Vec<Vec<Value>>,
+) -> QueryBuilder<'a, MySql> {
+    let tablename = QUOTE.quote(tablename);
+    let key_aliases: Vec<String> = (0..primary_key.len())
+        .map(|i| format!("ds_key_{}", i))
+        .collect();
+    let join_condition: Vec<String> = primary_key
+        .iter()
+        .zip(&key_aliases)
+        .map(|(key, alias)| {
+            format!(
+                "{}.{} = deferred.{}",
+                tablename,
+                QUOTE.quote(key),
+                QUOTE.quote(alias)
+            )
+        })
+        .collect();
+    let assignments: Vec<String> = column_names
+        .iter()
+        .map(|name| {
+            format!(
+                "{}.{} = deferred.{}",
+                tablename,
+                QUOTE.quote(name),
+                QUOTE.quote(name)
+            )
+        })
+        .collect();
+
+    let mut query = QueryBuilder::new(format!("UPDATE {} JOIN (", tablename));
+    for (i, values) in rows.into_iter().enumerate() {
+        query.push(if i == 0 {
+            "SELECT "
+        } else {
+            " UNION ALL SELECT "
+        });
+        let mut row = query.separated(", ");
+        let names = key_aliases.iter().chain(column_names);
+        for (value, name) in values.into_iter().zip(names) {
+            push_bind_value(&mut row, value);
+            if i == 0 {
+                row.push_unseparated(format!(" AS {}", QUOTE.quote(name)));
+            }
+        }
+    }
+    query.push(format!(
+        ") AS deferred ON {} SET {}",
+        join_condition.join(" AND "),
+        assignments.join(", ")
+    ));
+    query
+}
+
+// Adds the primary key columns of `primary_table` to `secondary_table` as a foreign key
+// and points every row at a random primary row, like the postgres `add_relations`
+pub async fn add_relations(
+    pool: &MySqlPool,
+    primary_table: &str,
+    secondary_table: &str,
+) -> anyhow::Result<()> {
+    let primary_key_columns: Vec<(String, String)> = sqlx::query_as(
+        "SELECT CAST(c.column_name AS CHAR), CAST(c.column_type AS CHAR)
+         FROM information_schema.key_column_usage k
+         JOIN information_schema.columns c ON c.table_schema = k.table_schema
+             AND c.table_name = k.table_name AND c.column_name = k.column_name
+         WHERE k.table_schema = DATABASE() AND k.table_name = ? AND k.constraint_name = 'PRIMARY'
+         ORDER BY k.ordinal_position",
+    )
+    .bind(primary_table)
+    .fetch_all(pool)
+    .await?;
+    if primary_key_columns.is_empty() {
+        bail!("-> {} <- has no primary key", primary_table);
+    }
+    let pk_columns: Vec<&str> = primary_key_columns
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    let alter_table_sql = foreign_key_sql(primary_table, secondary_table, &primary_key_columns);
+    sqlx::query(&alter_table_sql).execute(pool).await?;
+
+    // Rows of the secondary table are told apart by its first column
+    let first_secondary_column: String = sqlx::query_scalar(
+        "SELECT CAST(column_name AS CHAR) FROM information_schema.columns
+         WHERE table_schema = DATABASE() AND table_name = ?
+         ORDER BY ordinal_position LIMIT 1",
+    )
+    .bind(secondary_table)
+    .fetch_one(pool)
+    .await?;
+
+    let query = relations_update_sql(
+        primary_table,
+        secondary_table,
+        &first_secondary_column,
+        &pk_columns,
+    );
+    sqlx::query(&query).execute(pool).await?;
+
+    Ok(())
+}
+
+// Adds the `primary_key_columns` (name and column type) of `primary_table` to
+// `secondary_table` with a foreign key on them
+fn foreign_key_sql(
+    primary_table: &str,
+    secondary_table: &str,
+    primary_key_columns: &[(String, String)],
+) -> String {
+    let pk_columns: Vec<&str> = primary_key_columns
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    let mut alter_table_sql = format!("ALTER TABLE {} ", QUOTE.quote(secondary_table));
+    for (column_name, data_type) in primary_key_columns {
+        alter_table_sql += &format!("ADD COLUMN {} {}, ", QUOTE.quote(column_name), data_type);
+    }
+    alter_table_sql += &format!(
+        "ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE CASCADE",
+        QUOTE.quote(&format!("{}_{}_fk", secondary_table, primary_table)),
+        QUOTE.quote_all(&pk_columns),
+        QUOTE.quote(primary_table),
+        QUOTE.quote_all(&pk_columns)
+    );
+    alter_table_sql
+}
+
+// Points the rows of `secondary_table` still without a primary row, told apart by their
+// first column, at the rows of `primary_table` in random order
+fn relations_update_sql(
+    primary_table: &str,
+    secondary_table: &str,
+    first_secondary_column: &str,
+    pk_columns: &[&str],
+) -> String {
+    let secondary = QUOTE.quote(secondary_table);
+    let primary = QUOTE.quote(primary_table);
+    let row_id = QUOTE.quote(first_secondary_column);
+    let assignments: Vec<String> = pk_columns
+        .iter()
+        .map(|column| format!("{0}.{1} = c.{1}", secondary, QUOTE.quote(column)))
+        .collect();
+    // The window functions keep both derived tables materialized, which lets mysql
+    // read the table it updates
+    format!(
+        "UPDATE {secondary}
+         JOIN (
+             SELECT {row_id} AS ds_row_id, ROW_NUMBER() OVER (ORDER BY {row_id}) AS rn
+             FROM {secondary}
+             WHERE {first_pk} IS NULL
+         ) AS o ON {secondary}.{row_id} = o.ds_row_id
+         JOIN (
+             SELECT {pk_list}, ROW_NUMBER() OVER (ORDER BY RAND()) AS rn
+             FROM {primary}
+         ) AS c ON c.rn = ((o.rn - 1) % (SELECT COUNT(*) FROM {primary})) + 1
+         SET {assignments}",
+        first_pk = QUOTE.quote(pk_columns[0]),
+        pk_list = QUOTE.quote_all(pk_columns),
+        assignments = assignments.join(", "),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_binds_every_value() {
+        let rows = vec![
+            vec![Value::Int(1), Value::Text("a".to_string())],
+            vec![Value::Null, Value::Bool(true)],
+        ];
+        let query = insert_query("INSERT INTO `t` (`a`, `b`) ", rows);
+        assert_eq!(
+            query.sql(),
+            "INSERT INTO `t` (`a`, `b`) VALUES (?, ?), (?, ?)"
+        );
+    }
+
+    #[test]
+    fn deferred_update_joins_on_the_primary_key() {
+        let rows = vec![
+            vec![Value::Int(1), Value::Int(2), Value::Int(10)],
+            vec![Value::Int(1), Value::Int(3), Value::Null],
+        ];
+        let columns = ["parent_id".to_string()];
+        let query = deferred_update_query("t", &["shop", "number"], &columns, rows);
+        assert_eq!(
+            query.sql(),
+            "UPDATE `t` JOIN (SELECT ? AS `ds_key_0`, ? AS `ds_key_1`, ? AS `parent_id` \
+             UNION ALL SELECT ?, ?, ?) AS deferred \
+             ON `t`.`shop` = deferred.`ds_key_0` AND `t`.`number` = deferred.`ds_key_1` \
+             SET `t`.`parent_id` = deferred.`parent_id`"
+        );
+    }
+
+    #[test]
+    fn relations_add_the_key_columns_and_the_foreign_key() {
+        let columns = [
+            ("id".to_string(), "INT".to_string()),
+            ("region".to_string(), "VARCHAR(255)".to_string()),
+        ];
+        assert_eq!(
+            foreign_key_sql("customers", "orders", &columns),
+            "ALTER TABLE `orders` ADD COLUMN `id` INT, ADD COLUMN `region` VARCHAR(255), \
+             ADD CONSTRAINT `orders_customers_fk` FOREIGN KEY (`id`, `region`) \
+             REFERENCES `customers` (`id`, `region`) ON DELETE CASCADE"
+        );
+
+        let update = relations_update_sql("customers", "orders", "total", &["id", "region"]);
+        assert!(update.starts_with("UPDATE `orders`"));
+        assert!(update.contains("ROW_NUMBER() OVER (ORDER BY `total`)"));
+        assert!(update.contains("`orders`.`id` = c.`id`, `orders`.`region` = c.`region`"));
+    }
+}
@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::dialect::Dialect;
+use crate::error::AppError;
+use crate::generators::registry;
+use crate::state::DbPool;
+use crate::value::Value;
+use crate::{Field, Table};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+// // ----- FOREIGN KEYS ----- // //
+// REFERENCE fields get their values from rows already written to the parent table.
+// Tables are filled parents first (`fill_order`), once a table is filled its referenced
+// keys are read back into the `KeyStore` (so serial keys work too) and every child row
+// samples one of them.
+
+// Data type of fields pointing at another table
+pub const REFERENCE: &str = "REFERENCE";
+
+// The REFERENCE fields of a table that point at the same parent key
+pub struct ReferenceGroup<'a> {
+    pub parent: &'a str,
+    // Child fields with their index in the table
+    pub fields: Vec<(usize, &'a Field)>,
+    pub parent_fields: Vec<&'a str>,
+    pub min_per_parent: Option<usize>,
+    pub max_per_parent: Option<usize>,
+}
+
+pub fn reference_groups(table: &Table) -> Vec<ReferenceGroup<'_>> {
+    let mut groups: Vec<(Option<&str>, ReferenceGroup)> = Vec::new();
+    for (i, field) in table.fields.iter().enumerate() {
+        let Some(fk) = &field.config.foreign_key else {
+            continue;
+        };
+
+        let existing = groups.iter_mut().find(|(key, group)| {
+            fk.key.is_some()
+                && *key == fk.key.as_deref()
+                && group.parent.eq_ignore_ascii_case(&fk.table)
+        });
+        match existing {
+            Some((_, group)) => {
+                group.fields.push((i, field));
+                group.parent_fields.push(&fk.field);
+                group.min_per_parent = group.min_per_parent.or(fk.min_per_parent);
+                group.max_per_parent = group.max_per_parent.or(fk.max_per_parent);
+            }
+            None => groups.push((
+                fk.key.as_deref(),
+                ReferenceGroup {
+                    parent: &fk.table,
+                    fields: vec![(i, field)],
+                    parent_fields: vec![&fk.field],
+                    min_per_parent: fk.min_per_parent,
+                    max_per_parent: fk.max_per_parent,
+                },
+            )),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+// Embedded references hold the parent document, see `ForeignKey::embed`
+pub fn is_embedded(group: &ReferenceGroup) -> bool {
+    group.fields.iter().any(|(_, f)| {
+        f.config
+            .foreign_key
+            .as_ref()
+            .is_some_and(|fk| fk.embed == Some(true))
+    })
+}
+
+// Field holding the embedded parent document: the group's `key`, or its only field
+pub fn embedded_name<'a>(group: &ReferenceGroup<'a>) -> &'a str {
+    let (_, field) = group.fields[0];
+    field
+        .config
+        .foreign_key
+        .as_ref()
+        .and_then(|fk| fk.key.as_deref())
+        .unwrap_or(&field.fieldname)
+}
+
+pub fn find_table<'a>(tables: &'a [Table], name: &str) -> Option<&'a Table> {
+    tables
+        .iter()
+        .find(|t| t.tablename.eq_ignore_ascii_case(name))
+}
+
+pub fn find_field<'a>(table: &'a Table, name: &str) -> Option<&'a Field> {
+    table
+        .fields
+        .iter()
+        .find(|f| f.fieldname.eq_ignore_ascii_case(name))
+}
+
+// Tables `table` points at through its REFERENCE fields
+fn parents(table: &Table) -> Vec<&str> {
+    reference_groups(table)
+        .into_iter()
+        .map(|g| g.parent)
+        .collect()
+}
+
+// Chain of references leading from table `from` to table `to`, both included
+pub fn reference_path<'a>(tables: &'a [Table], from: &'a str, to: &str) -> Option<Vec<&'a str>> {
+    let mut path = vec![from];
+    let mut visited = vec![from.to_lowercase()];
+    if walk_references(tables, &mut path, &mut visited, to) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+fn walk_references<'a>(
+    tables: &'a [Table],
+    path: &mut Vec<&'a str>,
+    visited: &mut Vec<String>,
+    to: &str,
+) -> bool {
+    let Some(table) = find_table(tables, path[path.len() - 1]) else {
+        return false;
+    };
+    for parent in parents(table) {
+        if parent.eq_ignore_ascii_case(to) {
+            path.push(parent);
+            return true;
+        }
+        if visited.contains(&parent.to_lowercase()) {
+            continue;
+        }
+        visited.push(parent.to_lowercase());
+        path.push(parent);
+        if walk_references(tables, path, visited, to) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+// A reference is deferred when its parent (indirectly) references the table back,
+// no order fills both first. Those columns stay NULL until every table has rows
+// and are then set by an UPDATE, see `RowGenerator::deferred`.
+pub fn is_deferred(tables: &[Table], table: &Table, group: &ReferenceGroup) -> bool {
+    reference_path(tables, group.parent, &table.tablename).is_some()
+}
+
+// Indexes of the tables in the order they are created and filled: every table comes
+// after the tables it references, apart from deferred references. Ties keep the schema order.
+pub fn fill_order(tables: &[Table]) -> Vec<usize> {
+    let waits_on: Vec<Vec<usize>> = tables
+        .iter()
+        .map(|table| {
+            reference_groups(table)
+                .iter()
+                .filter(|group| !is_deferred(tables, table, group))
+                .filter_map(|group| {
+                    tables
+                        .iter()
+                        .position(|t| t.tablename.eq_ignore_ascii_case(group.parent))
+                })
+                .collect()
+        })
+        .collect();
+
+    // Every edge of a cycle is deferred, so some table is always ready
+    let mut order: Vec<usize> = Vec::with_capacity(tables.len());
+    while order.len() < tables.len() {
+        let next = (0..tables.len())
+            .find(|i| {
+                !order.contains(i) && waits_on[*i].iter().all(|parent| order.contains(parent))
+            })
+            .expect("the references left after deferring cycles form a cycle");
+        order.push(next);
+    }
+    order
+}
+
+// Column type of a field, REFERENCE fields take the type of the column they point at
+pub fn column_sql_type(field: &Field, tables: &[Table], dialect: Dialect) -> Option<String> {
+    resolve_sql_type(field, tables, dialect, tables.len())
+}
+
+// `hops` stops chains of references that loop back on themselves
+fn resolve_sql_type(
+    field: &Field,
+    tables: &[Table],
+    dialect: Dialect,
+    hops: usize,
+) -> Option<String> {
+    let Some(fk) = &field.config.foreign_key else {
+        return registry()
+            .get(&field.data_type)
+            .map(|g| g.sql_type(field, dialect));
+    };
+
+    if let Some(reference_type) = &fk.reference_type {
+        let typed = Field {
+            data_type: reference_type.clone(),
+            ..field.clone()
+        };
+        return registry()
+            .get(reference_type)
+            .map(|g| g.sql_type(&typed, dialect));
+    }
+
+    let parent = find_field(find_table(tables, &fk.table)?, &fk.field)?;
+    let sql_type = resolve_sql_type(parent, tables, dialect, hops.checked_sub(1)?)?;
+    Some(dialect.key_type(sql_type))
+}
+
+// Key tuples of a parent table, one per parent row
+pub type ParentKeys = Arc<Vec<Vec<Value>>>;
+
+// Keys read back from parent tables, by (table, columns). Every referenced key of a
+// parent stays in memory until the run ends, about 100 bytes per row for a single integer
+// column and more for text or composite keys, so a run needs roughly 100 MB per million
+// referenced parent rows. Children sample them by index, which is why they aren't streamed.
+#[derive(Default)]
+pub struct KeyStore {
+    keys: HashMap<(String, Vec<String>), ParentKeys>,
+}
+
+fn store_key(table: &str, fields: &[&str]) -> (String, Vec<String>) {
+    (
+        table.to_lowercase(),
+        fields.iter().map(|f| f.to_lowercase()).collect(),
+    )
+}
+
+// Column sets of `table` that tables of the schema reference
+pub fn referenced_keys<'a>(table: &Table, tables: &'a [Table]) -> Vec<Vec<&'a str>> {
+    let mut referenced: Vec<Vec<&str>> = Vec::new();
+    for child in tables {
+        for group in reference_groups(child) {
+            let fields = group.parent_fields;
+            if !group.parent.eq_ignore_ascii_case(&table.tablename) {
+                continue;
+            }
+            let known = referenced.iter().any(|other| {
+                other.len() == fields.len()
+                    && other
+                        .iter()
+                        .zip(&fields)
+                        .all(|(a, b)| a.eq_ignore_ascii_case(b))
+            });
+            if !known {
+                referenced.push(fields);
+            }
+        }
+    }
+    referenced
+}
+
+impl KeyStore {
+    // Reads the keys of `table` that any table of the schema references
+    pub async fn load(
+        &mut self,
+        pool: &DbPool,
+        table: &Table,
+        tables: &[Table],
+    ) -> Result<(), AppError> {
+        let dialect = pool.dialect();
+        for fields in referenced_keys(table, tables) {
+            if self.contains(&table.tablename, &fields) {
+                continue;
+            }
+
+            // Sorted, so the same seed samples the same parents
+            let query = format!(
+                "SELECT DISTINCT {0} FROM {1} ORDER BY {0}",
+                dialect.quote_all(&fields),
+                dialect.quote(&table.tablename)
+            );
+            let values = match pool {
+                DbPool::Postgres(pool) => {
+                    let rows = sqlx::query(&query).fetch_all(pool).await?;
+                    rows.iter()
+                        .map(|row| (0..fields.len()).map(|i| Value::decode(row, i)).collect())
+                        .collect::<Result<Vec<Vec<Value>>, _>>()?
+                }
+                DbPool::Mysql(pool) => {
+                    let rows = sqlx::query(&query).fetch_all(pool).await?;
+                    rows.iter()
+                        .map(|row| {
+                            (0..fields.len())
+                                .map(|i| Value::decode_mysql(row, i))
+                                .collect()
+                        })
+                        .collect::<Result<Vec<Vec<Value>>, _>>()?
+                }
+                DbPool::Sqlite(pool) => {
+                    let rows = sqlx::query(&query).fetch_all(pool).await?;
+                    rows.iter()
+                        .map(|row| {
+                            (0..fields.len())
+                                .map(|i| Value::decode_sqlite(row, i))
+                                .collect()
+                        })
+                        .collect::<Result<Vec<Vec<Value>>, _>>()?
+                }
+                DbPool::Mongo(db) => crate::mongo::load_keys(db, &table.tablename, &fields).await?,
+            };
+            self.insert(&table.tablename, &fields, values);
+        }
+        Ok(())
+    }
+
+    // Keys generated without a database, see `export.rs`
+    pub fn insert(&mut self, table: &str, fields: &[&str], values: Vec<Vec<Value>>) {
+        self.keys.insert(store_key(table, fields), Arc::new(values));
+    }
+
+    pub fn contains(&self, table: &str, fields: &[&str]) -> bool {
+        self.keys.contains_key(&store_key(table, fields))
+    }
+
+    pub fn get(&self, table: &str, fields: &[&str]) -> Option<ParentKeys> {
+        self.keys.get(&store_key(table, fields)).cloned()
+    }
+}
+
+// Picks the parent key of every child row
+pub struct ParentSampler {
+    keys: ParentKeys,
+    // Parent index of each row when the cardinality is bounded, uniform sampling otherwise
+    plan: Option<Vec<usize>>,
+    next: usize,
+}
+
+impl ParentSampler {
+    pub fn new(
+        keys: ParentKeys,
+        datasize: usize,
+        min_per_parent: Option<usize>,
+        max_per_parent: Option<usize>,
+        rng: &mut dyn RngCore,
+    ) -> Result<Self, String> {
+        let parents = keys.len();
+        if parents == 0 && datasize > 0 {
+            return Err("the referenced table has no rows".to_string());
+        }
+
+        let min = min_per_parent.unwrap_or(0);
+        if min.saturating_mul(parents) > datasize {
+            return Err(format!(
+                "{} rows can't give each of the {} parent rows at least {} children",
+                datasize, parents, min
+            ));
+        }
+        if let Some(max) = max_per_parent {
+            if datasize > max.saturating_mul(parents) {
+                return Err(format!(
+                    "{} rows can't fit in {} parent rows with at most {} children each",
+                    datasize, parents, max
+                ));
+            }
+        }
+
+        if min == 0 && max_per_parent.is_none() {
+            return Ok(ParentSampler {
+                keys,
+                plan: None,
+                next: 0,
+            });
+        }
+
+        // Every parent gets its minimum first, the rest is spread over the parents with room left
+        let mut plan = Vec::with_capacity(datasize);
+        for parent in 0..parents {
+            plan.extend(std::iter::repeat_n(parent, min));
+        }
+        let extra = datasize - plan.len();
+        match max_per_parent {
+            None => plan.extend((0..extra).map(|_| rng.gen_range(0..parents))),
+            Some(max) => {
+                let mut open: Vec<(usize, usize)> = (0..parents)
+                    .map(|parent| (parent, max.saturating_sub(min)))
+                    .filter(|(_, room)| *room > 0)
+                    .collect();
+                for _ in 0..extra {
+                    let i = rng.gen_range(0..open.len());
+                    plan.push(open[i].0);
+                    open[i].1 -= 1;
+                    if open[i].1 == 0 {
+                        open.swap_remove(i);
+                    }
+                }
+            }
+        }
+        plan.shuffle(rng);
+
+        Ok(ParentSampler {
+            keys,
+            plan: Some(plan),
+            next: 0,
+        })
+    }
+
+    // Key of the parent row the next child points at. A bounded cardinality only plans
+    // `datasize` children, deferred references walk every row of the table, so rows left
+    // from an earlier run make it run out.
+    pub fn sample(&mut self, rng: &mut dyn RngCore) -> Result<&[Value], String> {
+        if self.keys.is_empty() {
+            return Err("the referenced table has no rows".to_string());
+        }
+        let parent = match &self.plan {
+            Some(plan) => *plan.get(self.next).ok_or_else(|| {
+                format!(
+                    "the parents were planned for {} rows and the table holds more, \
+                     some were there before this run",
+                    plan.len()
+                )
+            })?,
+            None => rng.gen_range(0..self.keys.len()),
+        };
+        self.next += 1;
+        Ok(&self.keys[parent])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    use super::*;
+
+    // A table with an id and a REFERENCE to the id of each of `parents`
+    fn table(name: &str, parents: &[&str]) -> Table {
+        let mut fields = vec![json!({"fieldname": "id", "data_type": "Serial"})];
+        fields.extend(parents.iter().map(|parent| {
+            json!({
+                "fieldname": format!("{}_id", parent),
+                "data_type": REFERENCE,
+                "config": {"foreign_key": {"table": parent, "field": "id"}}
+            })
+        }));
+        serde_json::from_value(json!({"tablename": name, "datasize": 10, "fields": fields}))
+            .unwrap()
+    }
+
+    #[test]
+    fn references_sharing_a_key_form_one_group() {
+        let reference = |field: &str, key: Option<&str>| {
+            json!({
+                "fieldname": format!("order_{}", field),
+                "data_type": REFERENCE,
+                "config": {"foreign_key": {"table": "orders", "field": field, "key": key}}
+            })
+        };
+        let table: Table = serde_json::from_value(json!({
+            "tablename": "lines",
+            "datasize": 10,
+            "fields": [
+                reference("shop", Some("order")),
+                reference("number", Some("order")),
+                reference("id", None),
+                reference("ref", None)
+            ]
+        }))
+        .unwrap();
+        let groups = reference_groups(&table);
+        let parent_fields: Vec<&[&str]> =
+            groups.iter().map(|g| g.parent_fields.as_slice()).collect();
+        assert_eq!(
+            parent_fields,
+            [&["shop", "number"][..], &["id"][..], &["ref"][..]]
+        );
+    }
+
+    #[test]
+    fn references_take_the_type_of_their_parent() {
+        let tables = [table("customers", &[]), table("orders", &["customers"])];
+        let reference = find_field(&tables[1], "customers_id").unwrap();
+        assert_eq!(
+            column_sql_type(reference, &tables, Dialect::Postgres).as_deref(),
+            Some("INTEGER")
+        );
+        assert_eq!(
+            column_sql_type(reference, &tables, Dialect::Mysql).as_deref(),
+            Some("INT")
+        );
+
+        // References pointing at each other have no type to take
+        let mut looped = table("a", &["a"]);
+        looped.fields[1].config.foreign_key.as_mut().unwrap().field = "a_id".to_string();
+        let tables = [looped];
+        assert_eq!(
+            column_sql_type(&tables[0].fields[1], &tables, Dialect::Postgres),
+            None
+        );
+    }
+
+    fn deferred(tables: &[Table], index: usize) -> Vec<bool> {
+        reference_groups(&tables[index])
+            .iter()
+            .map(|group| is_deferred(tables, &tables[index], group))
+            .collect()
+    }
+
+    #[test]
+    fn fill_order_defers_only_the_edges_of_cycles() {
+        let tables = [
+            table("orders", &["customers"]),
+            table("customers", &[]),
+            table("employees", &["departments"]),
+            table("departments", &["employees"]),
+            table("reviews", &["employees", "orders"]),
+            table("categories", &["categories"]),
+        ];
+        assert_eq!(deferred(&tables, 0), [false]);
+        assert_eq!(deferred(&tables, 2), [true]);
+        assert_eq!(deferred(&tables, 3), [true]);
+        assert_eq!(deferred(&tables, 4), [false, false]);
+        assert_eq!(deferred(&tables, 5), [true]);
+        assert_eq!(fill_order(&tables), [1, 0, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn fill_order_keeps_parents_first() {
+        let tables = [
+            table("c", &["b"]),
+            table("b", &["a"]),
+            table("a", &[]),
+            table("d", &[]),
+        ];
+        assert_eq!(fill_order(&tables), [2, 1, 0, 3]);
+        assert_eq!(reference_path(&tables, "c", "a"), Some(vec!["c", "b", "a"]));
+        assert_eq!(reference_path(&tables, "a", "c"), None);
+    }
+
+    fn keys(count: i64) -> ParentKeys {
+        Arc::new((0..count).map(|i| vec![Value::Int(i)]).collect())
+    }
+
+    #[test]
+    fn sampler_rejects_bounds_the_rows_cant_meet() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(ParentSampler::new(keys(0), 10, None, None, &mut rng).is_err());
+        assert!(ParentSampler::new(keys(3), 10, Some(usize::MAX), None, &mut rng).is_err());
+        assert!(ParentSampler::new(keys(3), 10, Some(4), None, &mut rng).is_err());
+        assert!(ParentSampler::new(keys(3), 10, None, Some(3), &mut rng).is_err());
+        assert!(ParentSampler::new(keys(3), 10, None, Some(usize::MAX), &mut rng).is_ok());
+        assert!(ParentSampler::new(keys(3), 10, Some(3), Some(4), &mut rng).is_ok());
+        assert!(ParentSampler::new(keys(0), 0, Some(1), None, &mut rng).is_ok());
+    }
+
+    #[test]
+    fn sampler_gives_every_parent_its_bounds() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut sampler = ParentSampler::new(keys(4), 10, Some(2), Some(3), &mut rng).unwrap();
+        let mut children = [0; 4];
+        for _ in 0..10 {
+            match sampler.sample(&mut rng).unwrap() {
+                [Value::Int(parent)] => children[*parent as usize] += 1,
+                key => panic!("unexpected key {:?}", key),
+            }
+        }
+        assert!(children.iter().all(|count| (2..=3).contains(count)));
+    }
+
+    #[test]
+    fn sampler_fails_past_its_plan_or_without_parents() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut sampler = ParentSampler::new(keys(2), 3, Some(1), None, &mut rng).unwrap();
+        for _ in 0..3 {
+            assert!(sampler.sample(&mut rng).is_ok());
+        }
+        assert!(sampler.sample(&mut rng).is_err());
+
+        let mut sampler = ParentSampler::new(keys(0), 0, None, None, &mut rng).unwrap();
+        assert!(sampler.sample(&mut rng).is_err());
+    }
+}
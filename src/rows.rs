@@ -0,0 +1,649 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crate::dialect::Dialect;
+use crate::error::{AppError, ErrorCode};
+use crate::generators::{draw_null, registry, Generator};
+use crate::references::{
+    column_sql_type, is_deferred, reference_groups, KeyStore, ParentSampler, ReferenceGroup,
+};
+use crate::seed::{field_seed, seeded_rng, SeededRng};
+use crate::value::Value;
+use crate::{Field, Table};
+
+// // ----- ROW GENERATION ----- // //
+// Produces the rows of one table for the writers (INSERT, COPY and the file exports).
+// Plain fields get a value from their generator, REFERENCE fields share one
+// parent key per row so composite foreign keys always point at an existing row.
+// Every source draws from its own seeded stream, see `seed.rs`.
+
+// A column the writers fill, columns the database fills itself are left out
+pub struct Column {
+    pub name: String,
+    // Postgres type, the casts of the inserts and the file exports are based on it
+    pub sql_type: String,
+    // Counted from 1 in place of the database sequence, see `Mode::Complete`
+    pub serial: bool,
+    // Index of the field in the table
+    position: usize,
+}
+
+// Which columns the rows hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    // Rows inserted into postgres, which fills serial keys. Deferred references are set later.
+    Insert,
+    // Deferred references only
+    Deferred,
+    // Every column in schema order with serial keys counted from 1, for writers without a
+    // database. Deferred references need the keys of their whole cycle, so they are optional.
+    Complete { deferred: bool },
+}
+
+enum Source {
+    Generated {
+        column: usize,
+        field: Box<Field>,
+        generator: &'static dyn Generator,
+        rng: SeededRng,
+        unique: Option<UniqueValues>,
+        // Last value of a sequential generator
+        previous: Option<Value>,
+    },
+    // Stands in for a fresh sequence
+    Counter {
+        column: usize,
+        next: i64,
+    },
+    Reference {
+        columns: Vec<usize>,
+        // Field whose `null_ratio` leaves the whole key NULL
+        field: Box<Field>,
+        sampler: ParentSampler,
+        rng: SeededRng,
+    },
+}
+
+pub struct RowGenerator {
+    columns: Vec<Column>,
+    sources: Vec<Source>,
+    unique_tuples: Vec<UniqueTuples>,
+}
+
+impl RowGenerator {
+    // Rows to insert, deferred references are left out. `keys` must already hold
+    // the keys of every other table `table` references
+    pub fn new(
+        table: &Table,
+        tables: &[Table],
+        keys: &KeyStore,
+        seed: u64,
+    ) -> Result<Self, AppError> {
+        RowGenerator::build(table, tables, keys, seed, Mode::Insert)
+    }
+
+    // Values of the deferred references only, set on rows already inserted
+    pub fn deferred(
+        table: &Table,
+        tables: &[Table],
+        keys: &KeyStore,
+        seed: u64,
+    ) -> Result<Self, AppError> {
+        RowGenerator::build(table, tables, keys, seed, Mode::Deferred)
+    }
+
+    // Every column of the rows, including the values postgres would fill in
+    pub fn complete(
+        table: &Table,
+        tables: &[Table],
+        keys: &KeyStore,
+        seed: u64,
+    ) -> Result<Self, AppError> {
+        RowGenerator::build(table, tables, keys, seed, Mode::Complete { deferred: true })
+    }
+
+    // Like `complete` without the deferred references, which leaves the other columns
+    // unchanged since every source has its own stream
+    pub fn complete_without_deferred(
+        table: &Table,
+        tables: &[Table],
+        keys: &KeyStore,
+        seed: u64,
+    ) -> Result<Self, AppError> {
+        RowGenerator::build(
+            table,
+            tables,
+            keys,
+            seed,
+            Mode::Complete { deferred: false },
+        )
+    }
+
+    fn build(
+        table: &Table,
+        tables: &[Table],
+        keys: &KeyStore,
+        seed: u64,
+        mode: Mode,
+    ) -> Result<Self, AppError> {
+        let mut rows = RowGenerator {
+            columns: Vec::new(),
+            sources: Vec::new(),
+            unique_tuples: Vec::new(),
+        };
+
+        for (position, field) in table.fields.iter().enumerate() {
+            if mode == Mode::Deferred || field.config.foreign_key.is_some() {
+                continue;
+            }
+            let generator = registry().get(&field.data_type).ok_or_else(|| AppError {
+                field: Some(field.fieldname.clone()),
+                ..AppError::internal(format!("Unknown data type -> {} <-", field.data_type))
+            })?;
+
+            let column = rows.columns.len();
+            if !generator.filled_by_database() {
+                rows.sources.push(Source::Generated {
+                    column,
+                    field: Box::new(field.clone()),
+                    generator,
+                    rng: seeded_rng(field_seed(field, seed)),
+                    unique: is_unique(table, field)
+                        .then(|| UniqueValues::new(generator.value_space(field))),
+                    previous: None,
+                });
+            } else if matches!(mode, Mode::Complete { .. }) {
+                rows.sources.push(Source::Counter { column, next: 1 });
+            } else {
+                // Serial columns are filled in by the database
+                continue;
+            }
+            rows.columns.push(Column {
+                name: field.fieldname.clone(),
+                sql_type: generator.sql_type(field, Dialect::Postgres),
+                serial: generator.filled_by_database(),
+                position,
+            });
+        }
+
+        for group in reference_groups(table) {
+            let wanted = match mode {
+                Mode::Insert | Mode::Complete { deferred: false } => {
+                    !is_deferred(tables, table, &group)
+                }
+                Mode::Deferred => is_deferred(tables, table, &group),
+                Mode::Complete { deferred: true } => true,
+            };
+            if wanted {
+                rows.push_reference(&group, table, tables, keys, seed)?;
+            }
+        }
+
+        if matches!(mode, Mode::Complete { .. }) {
+            rows.sort_columns();
+        }
+
+        let is_primary = |f: &&Field| f.attributes.is_primary.unwrap_or(false);
+        let primary_key: Vec<&Field> = table.fields.iter().filter(is_primary).collect();
+        let composite_keys = unique_keys(table)
+            .into_iter()
+            .chain([primary_key])
+            .filter(|key| key.len() > 1);
+        for key in composite_keys {
+            rows.push_unique_tuples(table, &key);
+        }
+        Ok(rows)
+    }
+
+    // Keeps the rows from repeating the values of a composite key. Keys holding a serial
+    // are unique already, and keys without generated columns have nothing to redraw.
+    fn push_unique_tuples(&mut self, table: &Table, key: &[&Field]) {
+        let mut columns = Vec::new();
+        for field in key {
+            let position = table.fields.iter().position(|f| std::ptr::eq(f, *field));
+            let column = self
+                .columns
+                .iter()
+                .position(|c| Some(c.position) == position);
+            match column {
+                Some(column) if !self.columns[column].serial => columns.push(column),
+                // Filled by the database or set later
+                _ => return,
+            }
+        }
+        let mut sources = Vec::new();
+        let mut value_space = Some(1u64);
+        for (i, source) in self.sources.iter().enumerate() {
+            if let Source::Generated {
+                column,
+                field,
+                generator,
+                ..
+            } = source
+            {
+                if columns.contains(column) {
+                    sources.push(i);
+                    value_space = value_space
+                        .zip(generator.value_space(field))
+                        .map(|(space, values)| space.saturating_mul(values));
+                }
+            }
+        }
+        if sources.is_empty() {
+            return;
+        }
+        let names: Vec<&str> = key.iter().map(|f| f.fieldname.as_str()).collect();
+        self.unique_tuples.push(UniqueTuples {
+            name: names.join(", "),
+            columns,
+            sources,
+            value_space,
+            seen: HashSet::new(),
+        });
+    }
+
+    fn push_reference(
+        &mut self,
+        group: &ReferenceGroup,
+        table: &Table,
+        tables: &[Table],
+        keys: &KeyStore,
+        seed: u64,
+    ) -> Result<(), AppError> {
+        // A composite key draws from the stream of its first field
+        let mut rng = seeded_rng(field_seed(group.fields[0].1, seed));
+        let parent_keys = keys
+            .get(group.parent, &group.parent_fields)
+            .ok_or_else(|| {
+                AppError::internal(format!("The keys of -> {} <- aren't loaded", group.parent))
+            })?;
+        let sampler = ParentSampler::new(
+            parent_keys,
+            table.datasize,
+            group.min_per_parent,
+            group.max_per_parent,
+            &mut rng,
+        )
+        .map_err(|message| AppError {
+            field: Some(group.fields[0].1.fieldname.clone()),
+            ..AppError::internal(format!("Can't reference {}: {}", group.parent, message))
+        })?;
+
+        let mut group_columns = Vec::new();
+        for (position, field) in &group.fields {
+            group_columns.push(self.columns.len());
+            self.columns.push(Column {
+                name: field.fieldname.clone(),
+                sql_type: column_sql_type(field, tables, Dialect::Postgres)
+                    .unwrap_or_else(|| "INTEGER".to_string()),
+                serial: false,
+                position: *position,
+            });
+        }
+        self.sources.push(Source::Reference {
+            columns: group_columns,
+            field: Box::new(group.fields[0].1.clone()),
+            sampler,
+            rng,
+        });
+        Ok(())
+    }
+
+    // Puts the columns back in the order of the fields
+    fn sort_columns(&mut self) {
+        let mut order: Vec<usize> = (0..self.columns.len()).collect();
+        order.sort_by_key(|&i| self.columns[i].position);
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+
+        for source in &mut self.sources {
+            match source {
+                Source::Generated { column, .. } | Source::Counter { column, .. } => {
+                    *column = new_index[*column]
+                }
+                Source::Reference { columns, .. } => {
+                    for column in columns.iter_mut() {
+                        *column = new_index[*column];
+                    }
+                }
+            }
+        }
+        self.columns.sort_by_key(|c| c.position);
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    // Values of the next row, in the order of `columns()`. Fails when a unique field
+    // can't find a value it hasn't produced yet.
+    pub fn next_row(&mut self) -> Result<Vec<Value>, AppError> {
+        let mut row = vec![Value::Null; self.columns.len()];
+        for source in &mut self.sources {
+            match source {
+                Source::Generated { column, .. } => {
+                    let column = *column;
+                    row[column] = source.generate()?;
+                }
+                Source::Counter { column, next } => {
+                    row[*column] = Value::Int(*next);
+                    *next += 1;
+                }
+                Source::Reference {
+                    columns,
+                    field,
+                    sampler,
+                    rng,
+                } => {
+                    if draw_null(field, rng) {
+                        continue;
+                    }
+                    let key = sampler.sample(rng).map_err(|message| AppError {
+                        field: Some(field.fieldname.clone()),
+                        ..AppError::new(ErrorCode::Generation, message)
+                    })?;
+                    for (column, value) in columns.iter().zip(key) {
+                        row[*column] = value.clone();
+                    }
+                }
+            }
+        }
+
+        // A repeated key gets its generated columns redrawn, SQL keys holding a NULL
+        // never collide
+        for tuples in &mut self.unique_tuples {
+            let mut attempt = 0;
+            while tuples.columns.iter().all(|&c| row[c] != Value::Null)
+                && !tuples
+                    .seen
+                    .insert(fingerprint(tuples.columns.iter().map(|&c| &row[c])))
+            {
+                attempt += 1;
+                if attempt == attempts(tuples.value_space, tuples.seen.len()) {
+                    return Err(AppError::new(
+                        ErrorCode::Generation,
+                        format!(
+                            "Ran out of unique combinations for the key ({}) after {} rows",
+                            tuples.name,
+                            tuples.seen.len()
+                        ),
+                    ));
+                }
+                for &i in &tuples.sources {
+                    if let Source::Generated { column, .. } = &self.sources[i] {
+                        let column = *column;
+                        row[column] = self.sources[i].generate()?;
+                    }
+                }
+            }
+        }
+        Ok(row)
+    }
+
+    // The next `count` rows
+    pub fn next_rows(&mut self, count: usize) -> Result<Vec<Vec<Value>>, AppError> {
+        (0..count).map(|_| self.next_row()).collect()
+    }
+}
+
+impl Source {
+    // Next value of a generated column
+    fn generate(&mut self) -> Result<Value, AppError> {
+        let Source::Generated {
+            field,
+            generator,
+            rng,
+            unique,
+            previous,
+            ..
+        } = self
+        else {
+            return Ok(Value::Null);
+        };
+        if draw_null(field, rng) {
+            return Ok(Value::Null);
+        }
+        let mut draw = || {
+            if !generator.sequential() {
+                return Ok(generator.generate(field, rng));
+            }
+            let value = generator
+                .generate_next(field, previous.as_ref(), rng)
+                .map_err(|message| AppError {
+                    field: Some(field.fieldname.clone()),
+                    ..AppError::new(
+                        ErrorCode::Generation,
+                        format!("Can't generate -> {} <-: {}", field.fieldname, message),
+                    )
+                })?;
+            *previous = Some(value.clone());
+            Ok(value)
+        };
+        match unique {
+            Some(unique) => unique.generate(field, draw),
+            None => draw(),
+        }
+    }
+}
+
+// The columns of a composite key and the generated sources among them
+struct UniqueTuples {
+    name: String,
+    columns: Vec<usize>,
+    sources: Vec<usize>,
+    // Combinations of the generated columns, when they can be counted
+    value_space: Option<u64>,
+    seen: HashSet<u64>,
+}
+
+// Fields no two rows share a value of: `unique` ones, a primary key or a unique key of
+// one field
+pub fn is_unique(table: &Table, field: &Field) -> bool {
+    let is_primary = |f: &Field| f.attributes.is_primary.unwrap_or(false);
+    let alone_in_unique_key = field.attributes.unique_key.is_some()
+        && unique_keys(table)
+            .iter()
+            .any(|key| key.len() == 1 && std::ptr::eq(key[0], field));
+    field.config.unique == Some(true)
+        || alone_in_unique_key
+        || (is_primary(field) && table.fields.iter().filter(|f| is_primary(f)).count() == 1)
+}
+
+// The fields of every `unique_key` of a table, in the order of the fields
+pub fn unique_keys(table: &Table) -> Vec<Vec<&Field>> {
+    let mut keys: Vec<(&str, Vec<&Field>)> = Vec::new();
+    for field in &table.fields {
+        let Some(name) = &field.attributes.unique_key else {
+            continue;
+        };
+        match keys.iter_mut().find(|(key, _)| key == name) {
+            Some((_, fields)) => fields.push(field),
+            None => keys.push((name, vec![field])),
+        }
+    }
+    keys.into_iter().map(|(_, fields)| fields).collect()
+}
+
+// Draws a field does before giving up on a value it hasn't produced yet, when its
+// value space is unknown
+const MAX_ATTEMPTS: u64 = 1000;
+
+// Values a unique field already produced. A 64 bit fingerprint of each is kept, so the
+// memory grows with the rows (about 16 bytes each with the hash set) but not with the
+// size of the values, `max_unique_rows` in the server config caps it. Two distinct
+// values sharing a fingerprint, odds of about n² / 2^65 over n rows, make the second
+// one look taken: it is redrawn, and on a nearly full value space that can end in a
+// Generation error although a value was left.
+struct UniqueValues {
+    seen: HashSet<u64>,
+    value_space: Option<u64>,
+}
+
+impl UniqueValues {
+    fn new(value_space: Option<u64>) -> Self {
+        UniqueValues {
+            seen: HashSet::new(),
+            value_space,
+        }
+    }
+
+    // A value `draw` hasn't produced yet
+    fn generate(
+        &mut self,
+        field: &Field,
+        mut draw: impl FnMut() -> Result<Value, AppError>,
+    ) -> Result<Value, AppError> {
+        for _ in 0..attempts(self.value_space, self.seen.len()) {
+            let value = draw()?;
+            if self.seen.insert(fingerprint([&value])) {
+                return Ok(value);
+            }
+        }
+        Err(AppError {
+            field: Some(field.fieldname.clone()),
+            ..AppError::new(
+                ErrorCode::Generation,
+                format!(
+                    "Ran out of unique values for -> {} <- after {}, its config doesn't produce more",
+                    field.fieldname,
+                    self.seen.len()
+                ),
+            )
+        })
+    }
+}
+
+// Draws allowed to find a new value once `seen` values of `value_space` are taken. The
+// fewer values are left, the more draws it takes to find one.
+fn attempts(value_space: Option<u64>, seen: usize) -> u64 {
+    match value_space {
+        Some(space) => {
+            let left = space.saturating_sub(seen as u64).max(1);
+            (space / left).saturating_mul(30).max(MAX_ATTEMPTS)
+        }
+        None => MAX_ATTEMPTS,
+    }
+}
+
+fn fingerprint<'a>(values: impl IntoIterator<Item = &'a Value>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for value in values {
+        value.to_sql_text().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn table(fields: serde_json::Value) -> Table {
+        serde_json::from_value(json!({"tablename": "t", "datasize": 10, "fields": fields})).unwrap()
+    }
+
+    fn rows(table: &Table) -> RowGenerator {
+        RowGenerator::new(table, &[], &KeyStore::default(), 1).unwrap()
+    }
+
+    #[test]
+    fn unique_fields_never_repeat_a_value() {
+        let table = table(json!([{
+            "fieldname": "code",
+            "data_type": "Enum",
+            "config": {"values": ["a", "b", "c"], "unique": true}
+        }]));
+        let mut rows = rows(&table);
+        let mut values: Vec<Value> = rows.next_rows(3).unwrap().into_iter().flatten().collect();
+        values.sort_by_key(|value| value.to_sql_text());
+        assert_eq!(values, ["a", "b", "c"].map(|v| Value::Text(v.to_string())));
+
+        let err = rows.next_row().unwrap_err();
+        assert!(err
+            .message
+            .contains("Ran out of unique values for -> code <- after 3"));
+    }
+
+    #[test]
+    fn a_primary_key_of_one_field_is_unique() {
+        let single = table(json!([
+            {"fieldname": "a", "data_type": "Int", "attributes": {"is_primary": true}},
+            {"fieldname": "b", "data_type": "Int"}
+        ]));
+        assert!(is_unique(&single, &single.fields[0]));
+        assert!(!is_unique(&single, &single.fields[1]));
+
+        let composite = table(json!([
+            {"fieldname": "a", "data_type": "Int", "attributes": {"is_primary": true}},
+            {"fieldname": "b", "data_type": "Int", "attributes": {"is_primary": true}}
+        ]));
+        assert!(!is_unique(&composite, &composite.fields[0]));
+    }
+
+    #[test]
+    fn values_are_tracked_by_their_fingerprint() {
+        let table = table(json!([{
+            "fieldname": "only",
+            "data_type": "Enum",
+            "config": {"values": ["x"]}
+        }]));
+        let field = &table.fields[0];
+        let generator = registry().get("Enum").unwrap();
+        let mut unique = UniqueValues::new(None);
+        let mut rng = seeded_rng(1);
+        assert_eq!(
+            unique
+                .generate(field, || Ok(generator.generate(field, &mut rng)))
+                .unwrap(),
+            Value::Text("x".to_string())
+        );
+        assert!(unique
+            .generate(field, || Ok(generator.generate(field, &mut rng)))
+            .is_err());
+        assert_eq!(unique.seen.len(), 1);
+    }
+
+    #[test]
+    fn composite_unique_keys_never_repeat_a_combination() {
+        let table = table(json!([
+            {"fieldname": "a", "data_type": "Enum", "config": {"values": ["x", "y"]}, "attributes": {"unique_key": "k"}},
+            {"fieldname": "b", "data_type": "Enum", "config": {"values": ["x", "y"]}, "attributes": {"unique_key": "k"}},
+            {"fieldname": "c", "data_type": "Int", "attributes": {"unique_key": "other"}}
+        ]));
+        let keys: Vec<Vec<&str>> = unique_keys(&table)
+            .iter()
+            .map(|key| key.iter().map(|f| f.fieldname.as_str()).collect())
+            .collect();
+        assert_eq!(keys, [vec!["a", "b"], vec!["c"]]);
+
+        let mut rows = rows(&table);
+        let mut pairs: Vec<(Value, Value)> = rows
+            .next_rows(4)
+            .unwrap()
+            .into_iter()
+            .map(|row| (row[0].clone(), row[1].clone()))
+            .collect();
+        pairs.sort_by_key(|(a, b)| (a.to_sql_text(), b.to_sql_text()));
+        pairs.dedup();
+        assert_eq!(pairs.len(), 4);
+
+        let err = rows.next_row().unwrap_err();
+        assert!(err
+            .message
+            .contains("unique combinations for the key (a, b)"));
+    }
+
+    #[test]
+    fn attempts_grow_as_the_values_run_out() {
+        assert_eq!(attempts(None, 5), MAX_ATTEMPTS);
+        assert_eq!(attempts(Some(10), 0), MAX_ATTEMPTS);
+        assert_eq!(attempts(Some(1_000_000), 999_999), 30_000_000);
+        // More seen than counted, from fingerprints sharing a value
+        assert_eq!(attempts(Some(1_000_000), 2_000_000), 30_000_000);
+        assert_eq!(attempts(Some(u64::MAX), usize::MAX), u64::MAX);
+    }
+}
@@ -0,0 +1,86 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{Field, Table};
+
+// // ----- SEEDS ----- // //
+// Every run starts from one schema seed, given in the request or picked at random and
+// returned in the response. Tables and fields derive their own seed from their parent's
+// seed and their name, unless they set one, and every column draws from its own stream.
+// So the same schema and seed always produce the same rows, and adding a table or field
+// leaves the values of the others unchanged.
+
+// Portable generator, the same seed gives the same stream on every platform
+pub type SeededRng = ChaCha8Rng;
+
+// Random seeds stay below 2^53 so JSON clients read them back exactly
+const MAX_RANDOM_SEED: u64 = 1 << 53;
+
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen_range(0..MAX_RANDOM_SEED)
+}
+
+pub fn seeded_rng(seed: u64) -> SeededRng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+// Seed of a table or field named `name` under `seed`. Names are case insensitive like
+// the postgres identifiers they become.
+pub fn derive_seed(seed: u64, name: &str) -> u64 {
+    // FNV-1a of the name, then the splitmix64 finalizer to spread it over the seed
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.to_lowercase().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    let mut z = seed ^ hash;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Seed of a table under the schema seed
+pub fn table_seed(seed: u64, table: &Table) -> u64 {
+    table
+        .seed
+        .unwrap_or_else(|| derive_seed(seed, &table.tablename))
+}
+
+// Seed of a field's stream, `seed` is the seed of its table
+pub fn field_seed(field: &Field, seed: u64) -> u64 {
+    field
+        .seed
+        .unwrap_or_else(|| derive_seed(seed, &field.fieldname))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // Changing these values changes the rows of every seeded schema
+    #[test]
+    fn derived_seeds_are_stable() {
+        assert_eq!(derive_seed(0, "users"), 18120196979788530405);
+        assert_eq!(derive_seed(42, "users"), 4677902494653196517);
+        assert_eq!(derive_seed(42, "orders"), 11640664830301385507);
+        assert_eq!(seeded_rng(42).gen::<u64>(), 12578764544318200737);
+    }
+
+    #[test]
+    fn derived_seeds_ignore_case() {
+        assert_eq!(derive_seed(42, "Users"), derive_seed(42, "users"));
+        assert_ne!(derive_seed(43, "users"), derive_seed(42, "users"));
+    }
+
+    #[test]
+    fn table_seed_prefers_the_seed_of_the_table() {
+        let table = |value| serde_json::from_value::<Table>(value).unwrap();
+        let derived = table(json!({"tablename": "users", "datasize": 1, "fields": []}));
+        assert_eq!(table_seed(42, &derived), derive_seed(42, "users"));
+        let seeded = table(json!({"tablename": "users", "datasize": 1, "seed": 7, "fields": []}));
+        assert_eq!(table_seed(42, &seeded), 7);
+    }
+}
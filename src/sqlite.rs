@@ -0,0 +1,373 @@
+use anyhow::bail;
+use sqlx::query_builder::Separated;
+use sqlx::sqlite::SqlitePool;
+use sqlx::{QueryBuilder, Sqlite};
+
+use crate::dialect::Dialect;
+use crate::error::AppError;
+use crate::rows::RowGenerator;
+use crate::value::Value;
+use crate::Table;
+
+// // ----- SQLITE TARGETS ----- // //
+// Every database of a sqlite target is a file, so fixtures can be generated without
+// running a server. Rows are inserted with multi row INSERTs and matched again on their
+// rowid. Sqlite can't add constraints to an existing table, `create_table_sql` writes
+// every foreign key in the CREATE TABLE, which sqlite allows before the parent exists.
+
+const QUOTE: Dialect = Dialect::Sqlite;
+
+// Upper bound on bind parameters in a single sqlite statement
+const MAX_VARIABLES: usize = 32766;
+
+pub async fn table_exists(pool: &SqlitePool, tablename: &str) -> Result<bool, AppError> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(tablename)
+            .fetch_one(pool)
+            .await?;
+    Ok(count > 0)
+}
+
+// Binds a generated value with the storage class sqlite keeps it in
+fn push_bind_value(row: &mut Separated<'_, '_, Sqlite, &str>, value: Value) {
+    match value {
+        Value::Null => row.push_bind(None::<String>),
+        Value::Int(v) => row.push_bind(v),
+        Value::Float(v) => row.push_bind(v),
+        // Sqlite has no decimal type, NUMERIC columns convert the text themselves
+        Value::Decimal(v) => row.push_bind(v.to_string()),
+        Value::Bool(v) => row.push_bind(v),
+        Value::Text(v) => row.push_bind(v),
+        Value::Date(v) => row.push_bind(v),
+        Value::Time(v) => row.push_bind(v),
+        Value::Timestamp(v) => row.push_bind(v),
+        // Bound as is a uuid is stored as 16 bytes, the columns hold its text form
+        Value::Uuid(v) => row.push_bind(v.to_string()),
+        Value::Json(v) => row.push_bind(v.to_string()),
+        Value::Bytes(v) => row.push_bind(v),
+    };
+}
+
+// Inserts `datasize` rows with multi row INSERT statements in one transaction
+pub async fn insert_rows(
+    pool: &SqlitePool,
+    tablename: &str,
+    datasize: usize,
+    mut rows: RowGenerator,
+    batch_size: usize,
+) -> Result<(), AppError> {
+    let quoted_tablename = QUOTE.quote(tablename);
+
+    // Every column is filled by the database or set later, a NULL rowid is numbered by sqlite
+    if rows.columns().is_empty() {
+        sqlx::query(&format!(
+            "WITH RECURSIVE ds_rows (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM ds_rows WHERE n < ?)
+             INSERT INTO {} (rowid) SELECT NULL FROM ds_rows",
+            quoted_tablename
+        ))
+        .bind(datasize as i64)
+        .execute(pool)
+        .await?;
+        return Ok(());
+    }
+
+    let column_names: Vec<String> = rows.columns().iter().map(|c| c.name.clone()).collect();
+    let insert = format!(
+        "INSERT INTO {} ({}) ",
+        quoted_tablename,
+        QUOTE.quote_all(&column_names)
+    );
+    let rows_per_batch = batch_size.min(MAX_VARIABLES / column_names.len()).max(1);
+
+    let mut tx = pool.begin().await?;
+    let mut remaining = datasize;
+    while remaining > 0 {
+        let batch = remaining.min(rows_per_batch);
+        let mut insert_query: QueryBuilder<Sqlite> = QueryBuilder::new(&insert);
+        insert_query.push_values(rows.next_rows(batch)?, |mut row, values| {
+            for value in values {
+                push_bind_value(&mut row, value);
+            }
+        });
+        insert_query.build().execute(&mut tx).await?;
+        remaining -= batch;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// Sets the deferred references of the rows already in the table, `rows` comes from
+// `RowGenerator::deferred`. Rows are matched on their rowid, which updates don't change.
+pub async fn update_deferred_references(
+    pool: &SqlitePool,
+    table: &Table,
+    mut rows: RowGenerator,
+    batch_size: usize,
+) -> Result<(), AppError> {
+    if rows.columns().is_empty() {
+        return Ok(());
+    }
+    let tablename = QUOTE.quote(&table.tablename);
+    let column_names: Vec<String> = rows
+        .columns()
+        .iter()
+        .map(|c| QUOTE.quote(&c.name))
+        .collect();
+    let assignments: Vec<String> = column_names
+        .iter()
+        .map(|name| format!("{} = deferred.{}", name, name))
+        .collect();
+
+    let rows_per_batch = batch_size
+        .min(MAX_VARIABLES / (column_names.len() + 1))
+        .max(1);
+
+    let mut tx = pool.begin().await?;
+
+    // Insertion order, so a run with the same seed sets the same values on the same rows
+    let row_ids: Vec<i64> =
+        sqlx::query_scalar(&format!("SELECT rowid FROM {} ORDER BY rowid", tablename))
+            .fetch_all(&mut tx)
+            .await?;
+
+    for batch in row_ids.chunks(rows_per_batch) {
+        let mut update_query: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+            "WITH deferred (ds_row_id, {}) AS (",
+            column_names.join(", ")
+        ));
+        let values = rows.next_rows(batch.len())?;
+        update_query.push_values(batch.iter().zip(values), |mut row, (row_id, values)| {
+            row.push_bind(*row_id);
+            for value in values {
+                push_bind_value(&mut row, value);
+            }
+        });
+        update_query.push(format!(
+            ") UPDATE {0} SET {1} FROM deferred WHERE {0}.rowid = deferred.ds_row_id",
+            tablename,
+            assignments.join(", ")
+        ));
+
+        update_query.build().execute(&mut tx).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+// Adds the primary key column of `primary_table` to `secondary_table` as a foreign key
+// and points every row at a random primary row, like the postgres `add_relations`.
+// Sqlite only adds foreign keys with a new column, so composite keys can't be referenced.
+pub async fn add_relations(
+    pool: &SqlitePool,
+    primary_table: &str,
+    secondary_table: &str,
+) -> anyhow::Result<()> {
+    let primary_key_columns: Vec<(String, String)> =
+        sqlx::query_as("SELECT name, type FROM pragma_table_info(?) WHERE pk > 0 ORDER BY pk")
+            .bind(primary_table)
+            .fetch_all(pool)
+            .await?;
+    let (pk_column, data_type) = match primary_key_columns.as_slice() {
+        [] => bail!("-> {} <- has no primary key", primary_table),
+        [column] => column,
+        _ => bail!(
+            "-> {} <- has a composite primary key, sqlite can't add a foreign key over several columns to an existing table",
+            primary_table
+        ),
+    };
+
+    let secondary = QUOTE.quote(secondary_table);
+    let primary = QUOTE.quote(primary_table);
+    let pk_column = QUOTE.quote(pk_column);
+    sqlx::query(&format!(
+        "ALTER TABLE {} ADD COLUMN {} {} CONSTRAINT {} REFERENCES {} ({}) ON DELETE CASCADE",
+        secondary,
+        pk_column,
+        data_type,
+        QUOTE.quote(&format!("{}_{}_fk", secondary_table, primary_table)),
+        primary,
+        pk_column
+    ))
+    .execute(pool)
+    .await?;
+
+    let query = format!(
+        "UPDATE {secondary} SET {pk_column} = c.{pk_column}
+         FROM (
+             SELECT rowid AS ds_row_id, ROW_NUMBER() OVER (ORDER BY rowid) AS rn
+             FROM {secondary}
+             WHERE {pk_column} IS NULL
+         ) AS o
+         JOIN (
+             SELECT {pk_column}, ROW_NUMBER() OVER (ORDER BY RANDOM()) AS rn
+             FROM {primary}
+         ) AS c ON c.rn = ((o.rn - 1) % (SELECT COUNT(*) FROM {primary})) + 1
+         WHERE {secondary}.rowid = o.ds_row_id"
+    );
+    sqlx::query(&query).execute(pool).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::references::KeyStore;
+    use crate::state::DbPool;
+    use crate::{create_table_sql, Table};
+
+    // One connection, every connection to sqlite::memory: opens a database of its own
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    fn table(config: serde_json::Value) -> Table {
+        serde_json::from_value(config).unwrap()
+    }
+
+    async fn create(pool: &SqlitePool, table: &Table, tables: &[Table]) {
+        let sql = create_table_sql(table, tables, Dialect::Sqlite).unwrap();
+        sqlx::query(&sql).execute(pool).await.unwrap();
+    }
+
+    async fn count(pool: &SqlitePool, query: &str) -> i64 {
+        sqlx::query_scalar(query).fetch_one(pool).await.unwrap()
+    }
+
+    async fn insert(pool: &SqlitePool, table: &Table, tables: &[Table], batch_size: usize) {
+        let rows = RowGenerator::new(table, tables, &KeyStore::default(), 1).unwrap();
+        insert_rows(pool, &table.tablename, table.datasize, rows, batch_size)
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn inserts_every_row_in_batches() {
+        let pool = memory_pool().await;
+        let tables = [table(json!({
+            "tablename": "Order",
+            "datasize": 5,
+            "fields": [
+                {"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}},
+                {"fieldname": "total", "data_type": "Int"}
+            ]
+        }))];
+        assert!(!table_exists(&pool, "Order").await.unwrap());
+        create(&pool, &tables[0], &tables).await;
+        assert!(table_exists(&pool, "Order").await.unwrap());
+
+        insert(&pool, &tables[0], &tables, 2).await;
+        assert_eq!(
+            count(&pool, "SELECT COUNT(DISTINCT id) FROM \"Order\"").await,
+            5
+        );
+        assert_eq!(
+            count(&pool, "SELECT COUNT(*) FROM \"Order\" WHERE total IS NULL").await,
+            0
+        );
+    }
+
+    #[actix_web::test]
+    async fn tables_without_generated_columns_still_get_their_rows() {
+        let pool = memory_pool().await;
+        let tables = [table(json!({
+            "tablename": "counter",
+            "datasize": 7,
+            "fields": [{"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}}]
+        }))];
+        create(&pool, &tables[0], &tables).await;
+        insert(&pool, &tables[0], &tables, 100).await;
+        assert_eq!(count(&pool, "SELECT MAX(id) FROM counter").await, 7);
+    }
+
+    #[actix_web::test]
+    async fn deferred_references_point_at_inserted_rows() {
+        let pool = memory_pool().await;
+        let tables = [table(json!({
+            "tablename": "employees",
+            "datasize": 6,
+            "fields": [
+                {"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}},
+                {
+                    "fieldname": "manager_id",
+                    "data_type": "REFERENCE",
+                    "config": {"foreign_key": {"table": "employees", "field": "id"}}
+                }
+            ]
+        }))];
+        let employees = &tables[0];
+        create(&pool, employees, &tables).await;
+        insert(&pool, employees, &tables, 4).await;
+        assert_eq!(
+            count(
+                &pool,
+                "SELECT COUNT(*) FROM employees WHERE manager_id IS NULL"
+            )
+            .await,
+            6
+        );
+
+        let mut keys = KeyStore::default();
+        keys.load(&DbPool::Sqlite(pool.clone()), employees, &tables)
+            .await
+            .unwrap();
+        let rows = RowGenerator::deferred(employees, &tables, &keys, 1).unwrap();
+        update_deferred_references(&pool, employees, rows, 4)
+            .await
+            .unwrap();
+        assert_eq!(
+            count(
+                &pool,
+                "SELECT COUNT(*) FROM employees WHERE manager_id IN (SELECT id FROM employees)"
+            )
+            .await,
+            6
+        );
+    }
+
+    #[actix_web::test]
+    async fn relations_point_every_row_at_a_primary_row() {
+        let pool = memory_pool().await;
+        let tables = [
+            table(json!({
+                "tablename": "customers",
+                "datasize": 3,
+                "fields": [{"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}}]
+            })),
+            table(json!({
+                "tablename": "orders",
+                "datasize": 10,
+                "fields": [{"fieldname": "total", "data_type": "Int"}]
+            })),
+        ];
+        for table in &tables {
+            create(&pool, table, &tables).await;
+            insert(&pool, table, &tables, 4).await;
+        }
+
+        add_relations(&pool, "customers", "orders").await.unwrap();
+        assert_eq!(
+            count(
+                &pool,
+                "SELECT COUNT(*) FROM orders WHERE id IN (SELECT id FROM customers)"
+            )
+            .await,
+            10
+        );
+
+        // Orders have no primary key to point at
+        let err = add_relations(&pool, "orders", "customers")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("has no primary key"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Context;
+use mongodb::{Client, Database};
+use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+
+use crate::config::AppConfig;
+use crate::dialect::Dialect;
+
+// // ----- APPLICATION STATE ----- // //
+// Shared by every actix worker through `web::Data<AppState>`.
+// Database pools are created lazily, one per (target, database) pair, and reused
+// across requests so parallel calls don't each open their own connections.
+pub struct AppState {
+    pub config: AppConfig,
+    mongo: Client,
+    // Clients of the mongodb targets, by target name
+    mongo_targets: HashMap<String, Client>,
+    // No database is the maintenance pool of a mysql target
+    pools: Mutex<HashMap<(String, Option<String>), DbPool>>,
+}
+
+// Pool of the server behind a target, in the target's dialect
+#[derive(Clone)]
+pub enum DbPool {
+    Postgres(PgPool),
+    Mysql(MySqlPool),
+    Sqlite(SqlitePool),
+    Mongo(Database),
+}
+
+impl DbPool {
+    pub fn dialect(&self) -> Dialect {
+        match self {
+            DbPool::Postgres(_) => Dialect::Postgres,
+            DbPool::Mysql(_) => Dialect::Mysql,
+            DbPool::Sqlite(_) => Dialect::Sqlite,
+            DbPool::Mongo(_) => Dialect::Mongodb,
+        }
+    }
+
+    // Runs a statement that returns no rows, like DDL
+    pub async fn execute(&self, sql: &str) -> Result<(), sqlx::Error> {
+        match self {
+            DbPool::Postgres(pool) => sqlx::query(sql).execute(pool).await.map(|_| ()),
+            DbPool::Mysql(pool) => sqlx::query(sql).execute(pool).await.map(|_| ()),
+            DbPool::Sqlite(pool) => sqlx::query(sql).execute(pool).await.map(|_| ()),
+            DbPool::Mongo(_) => Err(sqlx::Error::Configuration(
+                "mongodb targets don't run SQL statements".into(),
+            )),
+        }
+    }
+}
+
+impl AppState {
+    pub async fn new(config: AppConfig) -> anyhow::Result<Self> {
+        // The mongo driver connects lazily and keeps its own pool, one client is enough
+        let mongo = Client::with_uri_str(&config.mongo.uri).await?;
+
+        let mut mongo_targets = HashMap::new();
+        for (name, target) in &config.targets {
+            match target.dialect {
+                // Sqlite creates the database files but not the directory holding them
+                Dialect::Sqlite => {
+                    std::fs::create_dir_all(&target.path).with_context(|| {
+                        format!("Failed to create the directory {}", target.path.display())
+                    })?;
+                }
+                // Targets without their own uri share the client of the schema store
+                Dialect::Mongodb => {
+                    let client = match &target.uri {
+                        Some(uri) => Client::with_uri_str(uri)
+                            .await
+                            .with_context(|| format!("Invalid uri of the target {}", name))?,
+                        None => mongo.clone(),
+                    };
+                    mongo_targets.insert(name.clone(), client);
+                }
+                Dialect::Postgres | Dialect::Mysql => {}
+            }
+        }
+
+        Ok(AppState {
+            config,
+            mongo,
+            mongo_targets,
+            pools: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // The database holding the stored schemas and relations
+    pub fn mongo_db(&self) -> Database {
+        self.mongo.database(&self.config.mongo.database)
+    }
+
+    // Pool for `database` on the given target, created on first use
+    pub fn pool(&self, target: Option<&str>, database: &str) -> anyhow::Result<DbPool> {
+        self.pool_for(target, Some(database))
+    }
+
+    // Pool for the maintenance database of the given target. None for sqlite targets,
+    // which have no server: their database files are created by the pool opening them,
+    // and for mongodb targets, which create a database on its first write
+    pub fn maintenance_pool(&self, target: Option<&str>) -> anyhow::Result<Option<DbPool>> {
+        let target_config = self.config.target(target)?;
+        match target_config.dialect {
+            Dialect::Postgres => {
+                let database = target_config.database.clone();
+                self.pool_for(target, Some(&database)).map(Some)
+            }
+            // A mysql server can be reached without picking a database
+            Dialect::Mysql => self.pool_for(target, None).map(Some),
+            Dialect::Sqlite | Dialect::Mongodb => Ok(None),
+        }
+    }
+
+    fn pool_for(&self, target: Option<&str>, database: Option<&str>) -> anyhow::Result<DbPool> {
+        let name = self.config.target_name(target);
+        let target = self.config.target(Some(name))?;
+
+        let mut pools = self.pools.lock().unwrap();
+        let key = (name.to_string(), database.map(str::to_string));
+        if let Some(pool) = pools.get(&key) {
+            return Ok(pool.clone());
+        }
+
+        // connect_lazy doesn't touch the network, connections are opened when first needed
+        let pool = match target.dialect {
+            Dialect::Postgres => DbPool::Postgres(
+                PgPoolOptions::new()
+                    .max_connections(target.max_connections)
+                    .connect_lazy_with(target.pg_connect_options(database.unwrap_or_default())),
+            ),
+            Dialect::Mysql => DbPool::Mysql(
+                MySqlPoolOptions::new()
+                    .max_connections(target.max_connections)
+                    .connect_lazy_with(target.mysql_connect_options(database)),
+            ),
+            Dialect::Mongodb => {
+                DbPool::Mongo(self.mongo_targets[name].database(database.unwrap_or_default()))
+            }
+            Dialect::Sqlite => DbPool::Sqlite(
+                SqlitePoolOptions::new()
+                    .max_connections(target.max_connections)
+                    .connect_lazy_with(target.sqlite_connect_options(database.unwrap_or_default())),
+            ),
+        };
+        pools.insert(key, pool.clone());
+        Ok(pool)
+    }
+}
@@ -0,0 +1,984 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::dialect::Dialect;
+use crate::generators::registry;
+use crate::references::{
+    column_sql_type, find_field, is_deferred, is_embedded, reference_groups, reference_path,
+    REFERENCE,
+};
+use crate::rows::{is_unique, unique_keys};
+use crate::{index_name, Field, IndexMethod, Table};
+
+// Data types holding other fields, see `generators/nested.rs`
+const OBJECT: &str = "Object";
+const ARRAY: &str = "Array";
+
+// // ----- SCHEMA VALIDATION ----- // //
+// Checks a schema before anything is created, so a bad schema fails as a whole
+// instead of half way through the DDL. Every problem is reported with the JSON
+// path of the offending value, e.g. `tables[1].fields[0].data_type`.
+
+// Longest identifier postgres keeps without truncating it
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaError {
+    pub path: String,
+    pub message: String,
+}
+
+impl SchemaError {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        SchemaError {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+// Deserializes a request body, reporting where in the JSON it doesn't match the expected shape
+pub fn parse<T: DeserializeOwned>(json: JsonValue) -> Result<T, Vec<SchemaError>> {
+    serde_path_to_error::deserialize(json).map_err(|err| {
+        let path = err.path().to_string();
+        vec![SchemaError::new(path, err.into_inner().to_string())]
+    })
+}
+
+// Collects every problem in the schema, an empty list means it can be generated
+pub fn validate_schema(database: &str, tables: &[Table]) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+
+    check_identifier(&mut errors, "database", database);
+
+    if tables.is_empty() {
+        errors.push(SchemaError::new("tables", "at least one table is required"));
+    }
+
+    // Index names are shared by every table of a postgres or sqlite database
+    let mut index_names = HashSet::new();
+    // Postgres enum types by name, with the path of the field that defines them first
+    let mut enum_types: HashMap<String, (String, &[String])> = HashMap::new();
+
+    let mut tablenames = HashSet::new();
+    for (i, table) in tables.iter().enumerate() {
+        let table_path = format!("tables[{}]", i);
+
+        let tablename_path = format!("{}.tablename", table_path);
+        check_identifier(&mut errors, &tablename_path, &table.tablename);
+        // Postgres folds unquoted names to lower case
+        if !tablenames.insert(table.tablename.to_lowercase()) {
+            errors.push(SchemaError::new(
+                tablename_path,
+                format!("table -> {} <- is defined more than once", table.tablename),
+            ));
+        }
+
+        if table.batch_size == Some(0) {
+            errors.push(SchemaError::new(
+                format!("{}.batch_size", table_path),
+                "batch_size must be at least 1",
+            ));
+        }
+
+        if table.fields.is_empty() {
+            errors.push(SchemaError::new(
+                format!("{}.fields", table_path),
+                "at least one field is required",
+            ));
+        }
+
+        let mut fieldnames = HashSet::new();
+        for (j, field) in table.fields.iter().enumerate() {
+            let field_path = format!("{}.fields[{}]", table_path, j);
+
+            let fieldname_path = format!("{}.fieldname", field_path);
+            check_identifier(&mut errors, &fieldname_path, &field.fieldname);
+            if !fieldnames.insert(field.fieldname.to_lowercase()) {
+                errors.push(SchemaError::new(
+                    fieldname_path,
+                    format!("field -> {} <- is defined more than once", field.fieldname),
+                ));
+            }
+
+            check_data_type(&mut errors, &field_path, field);
+            check_unique(&mut errors, &field_path, table, field);
+            check_expressions(&mut errors, &field_path, field);
+
+            // Fields sharing an enum type share its labels too
+            if let Some(enum_type) = &field.config.enum_type {
+                let enum_type_path = format!("{}.config.enum_type", field_path);
+                check_identifier(&mut errors, &enum_type_path, enum_type);
+                let values = field.config.values.as_deref().unwrap_or_default();
+                let (first_path, first_values) = enum_types
+                    .entry(enum_type.to_lowercase())
+                    .or_insert_with(|| (field_path.clone(), values));
+                if *first_values != values {
+                    errors.push(SchemaError::new(
+                        enum_type_path,
+                        format!(
+                            "enum type -> {} <- is defined with other values by {}",
+                            enum_type, first_path
+                        ),
+                    ));
+                }
+            }
+
+            if field.config.foreign_key.is_some() && field.data_type != REFERENCE {
+                errors.push(SchemaError::new(
+                    format!("{}.config.foreign_key", field_path),
+                    format!("only {} fields can have a foreign_key", REFERENCE),
+                ));
+            }
+        }
+
+        check_unique_keys(&mut errors, tables, i);
+        check_indexes(&mut errors, tables, i, &mut index_names);
+        check_references(&mut errors, tables, i);
+    }
+
+    errors
+}
+
+// The data type and config of a field, and of the members of Object and Array fields
+fn check_data_type(errors: &mut Vec<SchemaError>, field_path: &str, field: &Field) {
+    match registry().get(&field.data_type) {
+        Some(generator) => {
+            if let Err(message) = generator.validate(field) {
+                errors.push(SchemaError::new(format!("{}.config", field_path), message));
+            }
+        }
+        None => errors.push(SchemaError::new(
+            format!("{}.data_type", field_path),
+            format!(
+                "unknown data type -> {} <-, GET /data_types lists the supported ones",
+                field.data_type
+            ),
+        )),
+    }
+
+    if let Some(null_ratio) = field.config.null_ratio {
+        let null_ratio_path = format!("{}.config.null_ratio", field_path);
+        let is_primary = field.attributes.is_primary.unwrap_or(false);
+        let filled_by_database = registry()
+            .get(&field.data_type)
+            .is_some_and(|g| g.filled_by_database());
+        if null_ratio > 100 {
+            errors.push(SchemaError::new(
+                null_ratio_path,
+                format!("null_ratio ({}) can't be above 100", null_ratio),
+            ));
+        } else if null_ratio > 0 && (is_primary || field.attributes.is_not_null == Some(true)) {
+            errors.push(SchemaError::new(
+                null_ratio_path,
+                "a NOT NULL or primary key field can't have a null_ratio",
+            ));
+        } else if null_ratio > 0 && filled_by_database && field.config.foreign_key.is_none() {
+            errors.push(SchemaError::new(
+                null_ratio_path,
+                format!(
+                    "{} fields are filled by the database, they can't have a null_ratio",
+                    field.data_type
+                ),
+            ));
+        }
+    }
+
+    if !field.fields.is_empty() && field.data_type != OBJECT {
+        errors.push(SchemaError::new(
+            format!("{}.fields", field_path),
+            format!("only {} fields can have fields", OBJECT),
+        ));
+    }
+    if field.items.is_some() && field.data_type != ARRAY {
+        errors.push(SchemaError::new(
+            format!("{}.items", field_path),
+            format!("only {} fields can have items", ARRAY),
+        ));
+    }
+
+    let mut names = HashSet::new();
+    for (k, member) in field.fields.iter().enumerate() {
+        let member_path = format!("{}.fields[{}]", field_path, k);
+        if member.fieldname.is_empty() {
+            errors.push(SchemaError::new(
+                format!("{}.fieldname", member_path),
+                "a member of an Object field needs a fieldname",
+            ));
+        } else if !names.insert(member.fieldname.as_str()) {
+            errors.push(SchemaError::new(
+                format!("{}.fieldname", member_path),
+                format!(
+                    "member -> {} <- is defined more than once",
+                    member.fieldname
+                ),
+            ));
+        }
+        check_member(errors, &member_path, member);
+    }
+    if let Some(items) = &field.items {
+        check_member(errors, &format!("{}.items", field_path), items);
+    }
+}
+
+// A unique field needs a distinct value for every row of its table
+fn check_unique(errors: &mut Vec<SchemaError>, field_path: &str, table: &Table, field: &Field) {
+    if field.data_type == REFERENCE {
+        if field.config.unique == Some(true) {
+            errors.push(SchemaError::new(
+                format!("{}.config.unique", field_path),
+                "a REFERENCE field can't be unique, max_per_parent: 1 gives every parent row one child",
+            ));
+        }
+        return;
+    }
+    if !is_unique(table, field) {
+        return;
+    }
+    let value_space = registry()
+        .get(&field.data_type)
+        .and_then(|g| g.value_space(field));
+    if let Some(value_space) = value_space.filter(|&space| space < table.datasize as u64) {
+        errors.push(SchemaError::new(
+            format!("{}.config", field_path),
+            format!(
+                "-> {} <- is unique but its config only produces {} distinct values, fewer than the {} rows of -> {} <-",
+                field.fieldname, value_space, table.datasize, table.tablename
+            ),
+        ));
+    }
+}
+
+// `check` and `default` are trusted SQL, pasted into the CREATE TABLE as they are. They
+// are held to one expression: nothing closing the parentheses around them or ending the
+// statement, and no comments hiding the rest of it.
+fn check_expressions(errors: &mut Vec<SchemaError>, field_path: &str, field: &Field) {
+    let expressions = [
+        ("check", &field.attributes.check),
+        ("default", &field.attributes.default),
+    ];
+    for (name, expression) in expressions {
+        let Some(expression) = expression else {
+            continue;
+        };
+        if let Err(err) = check_expression(expression) {
+            errors.push(SchemaError::new(
+                format!("{}.attributes.{}", field_path, name),
+                format!(
+                    "{} is raw SQL written into the CREATE TABLE as it is and must be a single expression: {}",
+                    name, err
+                ),
+            ));
+        }
+    }
+}
+
+// Quotes are doubled to escape them, a backslash would escape them in mysql only
+fn check_expression(expression: &str) -> Result<(), String> {
+    if expression.trim().is_empty() {
+        return Err("it is empty".to_string());
+    }
+    for token in [";", "--", "/*", "\\"] {
+        if expression.contains(token) {
+            return Err(format!("it can't hold {}", token));
+        }
+    }
+
+    let mut depth = 0usize;
+    let mut quote = None;
+    for c in expression.chars() {
+        match (quote, c) {
+            // A doubled quote closes the text and opens it again
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth == 0 => {
+                return Err("a ) closes a parenthesis it doesn't open".to_string())
+            }
+            (None, ')') => depth -= 1,
+            _ => {}
+        }
+    }
+    match (quote, depth) {
+        (Some(open), _) => Err(format!("a {} is never closed", open)),
+        (None, 0) => Ok(()),
+        (None, _) => Err("a ( is never closed".to_string()),
+    }
+}
+
+// Composite unique keys are kept by redrawing their generated fields
+fn check_unique_keys(errors: &mut Vec<SchemaError>, tables: &[Table], index: usize) {
+    let table = &tables[index];
+    for key in unique_keys(table) {
+        let j = table
+            .fields
+            .iter()
+            .position(|f| std::ptr::eq(f, key[0]))
+            .unwrap_or_default();
+        let path = format!("tables[{}].fields[{}].attributes.unique_key", index, j);
+
+        if key.iter().all(|f| f.data_type == REFERENCE) {
+            errors.push(SchemaError::new(
+                path,
+                "a unique_key needs a field that isn't a REFERENCE, references can't be redrawn to avoid repeated keys (max_per_parent: 1 makes a reference unique)",
+            ));
+            continue;
+        }
+
+        // The combinations of the fields, when every one of them can be counted
+        let value_space = key.iter().try_fold(1u64, |space, field| {
+            let generator = registry().get(&field.data_type)?;
+            if field.data_type == REFERENCE {
+                return None;
+            }
+            Some(space.saturating_mul(generator.value_space(field)?))
+        });
+        if let Some(value_space) = value_space.filter(|&space| space < table.datasize as u64) {
+            let names: Vec<&str> = key.iter().map(|f| f.fieldname.as_str()).collect();
+            errors.push(SchemaError::new(
+                path,
+                format!(
+                    "the unique key ({}) only has {} combinations, fewer than the {} rows of -> {} <-",
+                    names.join(", "),
+                    value_space,
+                    table.datasize,
+                    table.tablename
+                ),
+            ));
+        }
+    }
+}
+
+fn check_indexes(
+    errors: &mut Vec<SchemaError>,
+    tables: &[Table],
+    index: usize,
+    index_names: &mut HashSet<String>,
+) {
+    let table = &tables[index];
+    for (k, table_index) in table.indexes.iter().enumerate() {
+        let path = format!("tables[{}].indexes[{}]", index, k);
+
+        if table_index.fields.is_empty() {
+            errors.push(SchemaError::new(
+                format!("{}.fields", path),
+                "an index needs at least one field",
+            ));
+            continue;
+        }
+        let mut fieldnames = HashSet::new();
+        for (l, name) in table_index.fields.iter().enumerate() {
+            let field_path = format!("{}.fields[{}]", path, l);
+            if find_field(table, name).is_none() {
+                errors.push(SchemaError::new(
+                    field_path,
+                    format!("-> {} <- has no field -> {} <-", table.tablename, name),
+                ));
+            } else if !fieldnames.insert(name.to_lowercase()) {
+                errors.push(SchemaError::new(
+                    field_path,
+                    format!("field -> {} <- is indexed more than once", name),
+                ));
+            }
+        }
+
+        let name = index_name(table, table_index);
+        let name_path = format!("{}.name", path);
+        check_identifier(errors, &name_path, &name);
+        if !index_names.insert(name.to_lowercase()) {
+            errors.push(SchemaError::new(
+                name_path,
+                format!("index -> {} <- is defined more than once", name),
+            ));
+        }
+
+        match table_index.method {
+            IndexMethod::Btree => {}
+            IndexMethod::Hash if table_index.fields.len() > 1 => {
+                errors.push(SchemaError::new(
+                    format!("{}.method", path),
+                    "a hash index covers a single field",
+                ));
+            }
+            IndexMethod::Hash => {}
+            IndexMethod::Gin => {
+                let not_json = table_index.fields.iter().find(|name| {
+                    find_field(table, name).is_some_and(|field| {
+                        column_sql_type(field, tables, Dialect::Postgres).as_deref()
+                            != Some("JSONB")
+                    })
+                });
+                if let Some(name) = not_json {
+                    errors.push(SchemaError::new(
+                        format!("{}.method", path),
+                        format!(
+                            "a gin index covers JSON fields (Object, Array, Json), -> {} <- isn't one",
+                            name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// Members are generated with their field, without a column or a database of their own
+fn check_member(errors: &mut Vec<SchemaError>, member_path: &str, member: &Field) {
+    // Sequences follow the previous row of their column, members have no column
+    let column_bound = registry()
+        .get(&member.data_type)
+        .is_some_and(|g| g.filled_by_database() || g.sequential());
+    if member.data_type == REFERENCE || column_bound {
+        errors.push(SchemaError::new(
+            format!("{}.data_type", member_path),
+            format!(
+                "{} can't be used inside an {} or {} field",
+                member.data_type, OBJECT, ARRAY
+            ),
+        ));
+        return;
+    }
+    if member.config.unique == Some(true) {
+        errors.push(SchemaError::new(
+            format!("{}.config.unique", member_path),
+            "members of an Object or Array field can't be unique",
+        ));
+    }
+    check_data_type(errors, member_path, member);
+}
+
+// Limits of this server, on top of `validate_schema`. Unique fields and keys remember
+// every row of their table while it is generated, see `rows::UniqueValues`, so the
+// tables holding one can't be larger than `max_unique_rows`.
+pub fn validate_limits(tables: &[Table], max_unique_rows: usize) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    for (index, table) in tables.iter().enumerate() {
+        if table.datasize <= max_unique_rows {
+            continue;
+        }
+        let filled_by_database = |field: &Field| {
+            registry()
+                .get(&field.data_type)
+                .is_some_and(|g| g.filled_by_database())
+        };
+        let generated = |field: &&Field| field.data_type != REFERENCE && !filled_by_database(field);
+        let is_primary = |field: &&Field| field.attributes.is_primary.unwrap_or(false);
+        let primary_key: Vec<&Field> = table.fields.iter().filter(is_primary).collect();
+        // Composite keys holding a serial are unique already, those without a generated
+        // field have nothing to remember
+        let tracked_key = unique_keys(table)
+            .into_iter()
+            .chain([primary_key])
+            .filter(|key| key.len() > 1)
+            .any(|key| {
+                key.iter().any(generated) && !key.iter().any(|field| filled_by_database(field))
+            });
+        let tracked_field = table
+            .fields
+            .iter()
+            .any(|field| generated(&field) && is_unique(table, field));
+        if tracked_key || tracked_field {
+            errors.push(SchemaError::new(
+                format!("tables[{}].datasize", index),
+                format!(
+                    "-> {} <- has unique fields or keys, which keep a fingerprint of every row in memory: its {} rows are above the max_unique_rows ({}) of this server",
+                    table.tablename, table.datasize, max_unique_rows
+                ),
+            ));
+        }
+    }
+    errors
+}
+
+// What the dialect of the target can't generate, on top of `validate_schema`
+pub fn validate_dialect(tables: &[Table], dialect: Dialect) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    for (i, table) in tables.iter().enumerate() {
+        let primary_key: Vec<&str> = table
+            .fields
+            .iter()
+            .filter(|f| f.attributes.is_primary.unwrap_or(false))
+            .map(|f| f.fieldname.as_str())
+            .collect();
+        for group in reference_groups(table).iter().filter(|g| is_embedded(g)) {
+            let (j, _) = group.fields[0];
+            let path = format!("tables[{}].fields[{}].config.foreign_key.embed", i, j);
+            if dialect != Dialect::Mongodb {
+                errors.push(SchemaError::new(
+                    path,
+                    "only mongodb targets can embed a reference",
+                ));
+            } else if is_deferred(tables, table, group) {
+                // The copies would miss the references set once every table has rows
+                errors.push(SchemaError::new(
+                    path,
+                    "an embedded reference can't be part of a reference cycle",
+                ));
+            }
+        }
+        for (k, table_index) in table.indexes.iter().enumerate() {
+            let unsupported = match table_index.method {
+                IndexMethod::Btree => None,
+                IndexMethod::Hash if dialect == Dialect::Sqlite => {
+                    Some("sqlite targets only have btree indexes")
+                }
+                IndexMethod::Hash => None,
+                IndexMethod::Gin if dialect != Dialect::Postgres => {
+                    Some("gin indexes are only available on postgres targets")
+                }
+                IndexMethod::Gin => None,
+            };
+            if let Some(message) = unsupported {
+                errors.push(SchemaError::new(
+                    format!("tables[{}].indexes[{}].method", i, k),
+                    message,
+                ));
+            }
+        }
+        if dialect == Dialect::Mongodb {
+            for (j, field) in table.fields.iter().enumerate() {
+                let attributes = &field.attributes;
+                if attributes.check.is_some() || attributes.default.is_some() {
+                    errors.push(SchemaError::new(
+                        format!("tables[{}].fields[{}].attributes", i, j),
+                        "mongodb collections have no CHECK or DEFAULT",
+                    ));
+                }
+                // A unique index counts NULL as a value like any other
+                if attributes.unique_key.is_some() && field.config.null_ratio.unwrap_or(0) > 0 {
+                    errors.push(SchemaError::new(
+                        format!("tables[{}].fields[{}].config.null_ratio", i, j),
+                        "on mongodb targets a unique_key field can't have a null_ratio",
+                    ));
+                }
+            }
+        }
+        match dialect {
+            Dialect::Postgres | Dialect::Mongodb => {}
+            // MySQL rows have no ctid, deferred references find their rows by primary key
+            Dialect::Mysql => {
+                let deferred = reference_groups(table)
+                    .iter()
+                    .any(|group| is_deferred(tables, table, group));
+                if deferred && primary_key.is_empty() {
+                    errors.push(SchemaError::new(
+                        format!("tables[{}].fields", i),
+                        format!(
+                            "-> {} <- closes a reference cycle, on mysql targets it needs a primary key to set its references once every table has rows",
+                            table.tablename
+                        ),
+                    ));
+                }
+            }
+            // SQLite only numbers the rows of an INTEGER PRIMARY KEY column
+            Dialect::Sqlite => {
+                for (j, field) in table.fields.iter().enumerate() {
+                    let filled_by_database = field.config.foreign_key.is_none()
+                        && registry()
+                            .get(&field.data_type)
+                            .is_some_and(|g| g.filled_by_database());
+                    if filled_by_database && primary_key != [field.fieldname.as_str()] {
+                        errors.push(SchemaError::new(
+                            format!("tables[{}].fields[{}].data_type", i, j),
+                            format!(
+                                "on sqlite targets a {} field must be the only primary key of its table",
+                                field.data_type
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    errors
+}
+
+// Foreign keys must point at the primary key of a table of the schema, with a
+// cardinality the row counts can satisfy
+fn check_references(errors: &mut Vec<SchemaError>, tables: &[Table], index: usize) {
+    let table = &tables[index];
+    for group in reference_groups(table) {
+        let (j, _) = group.fields[0];
+        let path = format!("tables[{}].fields[{}].config.foreign_key", index, j);
+
+        for (j, field) in &group.fields {
+            let fk = field.config.foreign_key.as_ref().unwrap();
+            if let Some(reference_type) = &fk.reference_type {
+                if registry().get(reference_type).is_none() {
+                    errors.push(SchemaError::new(
+                        format!(
+                            "tables[{}].fields[{}].config.foreign_key.reference_type",
+                            index, j
+                        ),
+                        format!("unknown data type -> {} <-", reference_type),
+                    ));
+                }
+            }
+        }
+
+        // A composite key is NULL as a whole, with the null_ratio of its first field
+        let (_, first) = group.fields[0];
+        let null_ratio = first.config.null_ratio.unwrap_or(0);
+        for (j, field) in &group.fields[1..] {
+            if field.config.null_ratio.unwrap_or(0) != null_ratio {
+                errors.push(SchemaError::new(
+                    format!("tables[{}].fields[{}].config.null_ratio", index, j),
+                    format!(
+                        "the fields of a composite key share one null_ratio, -> {} <- has {}",
+                        first.fieldname, null_ratio
+                    ),
+                ));
+            }
+        }
+        if null_ratio > 0 && group.min_per_parent.unwrap_or(0) > 0 {
+            errors.push(SchemaError::new(
+                format!("{}.min_per_parent", path),
+                "min_per_parent can't be kept when null_ratio leaves some references NULL",
+            ));
+        }
+
+        let parent_index = tables
+            .iter()
+            .position(|t| t.tablename.eq_ignore_ascii_case(group.parent));
+        let Some(parent_index) = parent_index else {
+            errors.push(SchemaError::new(
+                format!("{}.table", path),
+                format!("table -> {} <- isn't part of the schema", group.parent),
+            ));
+            continue;
+        };
+        let parent = &tables[parent_index];
+
+        // References inside a cycle are set by an UPDATE once every table of the cycle has rows
+        if let Some(cycle) = reference_path(tables, group.parent, &table.tablename) {
+            for (j, field) in &group.fields {
+                let is_primary = field.attributes.is_primary.unwrap_or(false);
+                if is_primary || field.attributes.is_not_null == Some(true) {
+                    errors.push(SchemaError::new(
+                        format!("tables[{}].fields[{}].attributes", index, j),
+                        format!(
+                            "-> {} <- closes the reference cycle {} -> {}, it is filled after the rows are written so it can't be NOT NULL or part of the primary key",
+                            field.fieldname,
+                            table.tablename,
+                            cycle.join(" -> ")
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let mut fields_found = true;
+        for (j, field) in &group.fields {
+            let fk = field.config.foreign_key.as_ref().unwrap();
+            if find_field(parent, &fk.field).is_none() {
+                fields_found = false;
+                errors.push(SchemaError::new(
+                    format!("tables[{}].fields[{}].config.foreign_key.field", index, j),
+                    format!("-> {} <- has no field -> {} <-", parent.tablename, fk.field),
+                ));
+            }
+        }
+
+        // Postgres only accepts foreign keys on the whole primary key
+        let mut primary_key: Vec<String> = parent
+            .fields
+            .iter()
+            .filter(|f| f.attributes.is_primary.unwrap_or(false))
+            .map(|f| f.fieldname.to_lowercase())
+            .collect();
+        let mut referenced: Vec<String> = group
+            .parent_fields
+            .iter()
+            .map(|f| f.to_lowercase())
+            .collect();
+        primary_key.sort();
+        referenced.sort();
+        if fields_found && primary_key != referenced {
+            errors.push(SchemaError::new(
+                format!("{}.field", path),
+                format!(
+                    "({}) isn't the primary key of -> {} <-, fields sharing a `key` must cover it",
+                    group.parent_fields.join(", "),
+                    parent.tablename
+                ),
+            ));
+        }
+
+        let min = group.min_per_parent.unwrap_or(0);
+        if let Some(max) = group.max_per_parent {
+            if max == 0 {
+                errors.push(SchemaError::new(
+                    format!("{}.max_per_parent", path),
+                    "max_per_parent must be at least 1",
+                ));
+                continue;
+            }
+            if min > max {
+                errors.push(SchemaError::new(
+                    format!("{}.min_per_parent", path),
+                    format!(
+                        "min_per_parent ({}) can't be above max_per_parent ({})",
+                        min, max
+                    ),
+                ));
+                continue;
+            }
+            if table.datasize > max.saturating_mul(parent.datasize) {
+                errors.push(SchemaError::new(
+                    format!("{}.max_per_parent", path),
+                    format!(
+                        "{} rows can't fit in {} parent rows with at most {} children each",
+                        table.datasize, parent.datasize, max
+                    ),
+                ));
+            }
+        }
+        if min.saturating_mul(parent.datasize) > table.datasize {
+            errors.push(SchemaError::new(
+                format!("{}.min_per_parent", path),
+                format!(
+                    "{} rows can't give each of the {} parent rows at least {} children",
+                    table.datasize, parent.datasize, min
+                ),
+            ));
+        }
+    }
+}
+
+// Names end up unquoted in the generated SQL, so only plain identifiers are allowed
+pub fn check_identifier(errors: &mut Vec<SchemaError>, path: &str, name: &str) {
+    let mut chars = name.chars();
+    let valid_start = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_');
+    let valid_rest = chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !valid_start || !valid_rest {
+        errors.push(SchemaError::new(
+            path,
+            format!(
+                "-> {} <- must start with a letter or underscore and contain only letters, digits and underscores",
+                name
+            ),
+        ));
+    } else if name.len() > MAX_IDENTIFIER_LENGTH {
+        errors.push(SchemaError::new(
+            path,
+            format!(
+                "-> {} <- is longer than {} characters",
+                name, MAX_IDENTIFIER_LENGTH
+            ),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn tables(tables: JsonValue) -> Vec<Table> {
+        parse(tables).unwrap()
+    }
+
+    // Paths of the errors, in the order they are reported
+    fn paths(errors: &[SchemaError]) -> Vec<&str> {
+        errors.iter().map(|error| error.path.as_str()).collect()
+    }
+
+    #[test]
+    fn expressions_stay_single_expressions() {
+        for accepted in [
+            "a > 0",
+            "lower(name) <> ''",
+            "'it''s (odd' <> \"we(ird\"",
+            "now()",
+        ] {
+            assert!(
+                check_expression(accepted).is_ok(),
+                "{} was rejected",
+                accepted
+            );
+        }
+        let rejected = [
+            " ",
+            "1; DROP TABLE t",
+            "1 -- rest",
+            "1 /* rest */",
+            "'a\\' OR 1 = 1",
+            "a > 0) OR (1 = 1",
+            "(a > 0",
+            "'open",
+        ];
+        for expression in rejected {
+            assert!(
+                check_expression(expression).is_err(),
+                "{} was accepted",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn identifiers_are_plain_names() {
+        let long = "a".repeat(MAX_IDENTIFIER_LENGTH + 1);
+        for name in ["", "1abc", "../data", "a;b", "a\"b", "a b", long.as_str()] {
+            let mut errors = Vec::new();
+            check_identifier(&mut errors, "name", name);
+            assert_eq!(errors.len(), 1, "{:?} was accepted", name);
+        }
+        let mut errors = Vec::new();
+        check_identifier(&mut errors, "name", "_users_2");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn reports_the_path_of_each_problem() {
+        let tables = tables(json!([
+            {"tablename": "t", "datasize": 10, "batch_size": 0, "fields": [
+                {"fieldname": "a", "data_type": "Int", "config": {"min_length": 5, "max_length": 1}},
+                {"fieldname": "A", "data_type": "Int"},
+                {"fieldname": "c", "data_type": "Unknown"}
+            ]},
+            {"tablename": "T", "datasize": 1, "fields": []}
+        ]));
+        assert_eq!(
+            paths(&validate_schema("db", &tables)),
+            [
+                "tables[0].batch_size",
+                "tables[0].fields[0].config",
+                "tables[0].fields[1].fieldname",
+                "tables[0].fields[2].data_type",
+                "tables[1].tablename",
+                "tables[1].fields"
+            ]
+        );
+        assert!(paths(&validate_schema("../db", &tables)).contains(&"database"));
+        assert_eq!(paths(&validate_schema("db", &[])), ["tables"]);
+    }
+
+    #[test]
+    fn reports_references_the_rows_cant_satisfy() {
+        let tables = tables(json!([
+            {"tablename": "customers", "datasize": 2, "fields": [
+                {"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}}
+            ]},
+            {"tablename": "orders", "datasize": 10, "fields": [
+                {"fieldname": "customer", "data_type": "REFERENCE", "config": {
+                    "foreign_key": {"table": "customers", "field": "id", "max_per_parent": 3}
+                }},
+                {"fieldname": "missing", "data_type": "REFERENCE", "config": {
+                    "foreign_key": {"table": "customers", "field": "nope"}
+                }},
+                {"fieldname": "nowhere", "data_type": "REFERENCE", "config": {
+                    "foreign_key": {"table": "suppliers", "field": "id"}
+                }}
+            ]}
+        ]));
+        assert_eq!(
+            paths(&validate_schema("db", &tables)),
+            [
+                "tables[1].fields[0].config.foreign_key.max_per_parent",
+                "tables[1].fields[1].config.foreign_key.field",
+                "tables[1].fields[2].config.foreign_key.table"
+            ]
+        );
+    }
+
+    #[test]
+    fn references_closing_a_cycle_stay_nullable() {
+        let reference = |name: &str, table: &str, not_null: bool| {
+            json!({
+                "fieldname": name,
+                "data_type": "REFERENCE",
+                "config": {"foreign_key": {"table": table, "field": "id"}},
+                "attributes": {"is_not_null": not_null}
+            })
+        };
+        let id =
+            json!({"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}});
+        let tables = tables(json!([
+            {"tablename": "employees", "datasize": 10, "fields": [
+                id, reference("department", "departments", true)
+            ]},
+            {"tablename": "departments", "datasize": 2, "fields": [
+                id, reference("manager", "employees", false)
+            ]}
+        ]));
+        assert_eq!(
+            paths(&validate_schema("db", &tables)),
+            ["tables[0].fields[1].attributes"]
+        );
+    }
+
+    #[test]
+    fn null_ratio_needs_a_nullable_column() {
+        let tables = tables(json!([{
+            "tablename": "t",
+            "datasize": 1,
+            "fields": [
+                {"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}, "config": {"null_ratio": 10}},
+                {"fieldname": "a", "data_type": "Int", "config": {"null_ratio": 101}},
+                {"fieldname": "b", "data_type": "Int", "attributes": {"is_not_null": true}, "config": {"null_ratio": 10}},
+                {"fieldname": "c", "data_type": "Int", "config": {"null_ratio": 100}}
+            ]
+        }]));
+        assert_eq!(
+            paths(&validate_schema("db", &tables)),
+            [
+                "tables[0].fields[0].config.null_ratio",
+                "tables[0].fields[1].config.null_ratio",
+                "tables[0].fields[2].config.null_ratio",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_and_default_are_single_expressions() {
+        let tables = tables(json!([{
+            "tablename": "t",
+            "datasize": 1,
+            "fields": [
+                {"fieldname": "a", "data_type": "Int", "attributes": {"check": "a > 0; DROP TABLE t", "default": " "}},
+                {"fieldname": "b", "data_type": "Int", "attributes": {"check": "b > 0", "default": "1"}}
+            ]
+        }]));
+        assert_eq!(
+            paths(&validate_schema("db", &tables)),
+            [
+                "tables[0].fields[0].attributes.check",
+                "tables[0].fields[0].attributes.default",
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_count_only_the_rows_kept_in_memory() {
+        let unique = tables(json!([
+            {"tablename": "a", "datasize": 10, "fields": [
+                {"fieldname": "id", "data_type": "Int", "config": {"unique": true}}
+            ]},
+            {"tablename": "b", "datasize": 10, "fields": [
+                {"fieldname": "id", "data_type": "Serial", "attributes": {"is_primary": true}},
+                {"fieldname": "n", "data_type": "Int"}
+            ]},
+            {"tablename": "c", "datasize": 5, "fields": [
+                {"fieldname": "id", "data_type": "Int", "config": {"unique": true}}
+            ]}
+        ]));
+        assert_eq!(paths(&validate_limits(&unique, 5)), ["tables[0].datasize"]);
+        assert!(validate_limits(&unique, 10).is_empty());
+    }
+
+    #[test]
+    fn parse_reports_where_the_body_doesnt_match() {
+        let errors = parse::<Vec<Table>>(json!([
+            {"tablename": "t", "datasize": "ten", "fields": []}
+        ]))
+        .unwrap_err();
+        assert_eq!(paths(&errors), ["[0].datasize"]);
+    }
+}
@@ -0,0 +1,327 @@
+use std::str::FromStr;
+
+use bson::oid::ObjectId;
+use bson::{Binary, Bson, Decimal128};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use sqlx::mysql::MySqlRow;
+use sqlx::postgres::{PgRow, PgTypeKind};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column, Row, TypeInfo, ValueRef};
+use uuid::Uuid;
+
+// // ----- GENERATED VALUES ----- // //
+// A single generated value. Generators produce these and every writer decides
+// how to encode each variant for its own output.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    Decimal(Decimal),
+    Bool(bool),
+    Text(String),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    Uuid(Uuid),
+    Json(serde_json::Value),
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    // Text form postgres accepts as input for the matching column type, None for NULL
+    pub fn to_sql_text(&self) -> Option<String> {
+        let text = match self {
+            Value::Null => return None,
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) => v.to_string(),
+            Value::Decimal(v) => v.to_string(),
+            Value::Bool(v) => v.to_string(),
+            Value::Text(v) => v.clone(),
+            Value::Date(v) => format_date(v),
+            Value::Time(v) => v.format("%H:%M:%S%.f").to_string(),
+            Value::Timestamp(v) => format_timestamp(v),
+            Value::Uuid(v) => v.to_string(),
+            Value::Json(v) => v.to_string(),
+            Value::Bytes(v) => {
+                let hex: String = v.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("\\x{}", hex)
+            }
+        };
+        Some(text)
+    }
+
+    // JSON form for the file exports: numbers and booleans stay native, the rest is text
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Int(v) => (*v).into(),
+            Value::Float(v) => serde_json::Number::from_f64(*v)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            // Decimal columns (coordinates) keep their digits as a JSON number
+            Value::Decimal(v) => serde_json::from_str(&v.to_string())
+                .unwrap_or_else(|_| serde_json::Value::String(v.to_string())),
+            Value::Bool(v) => (*v).into(),
+            Value::Json(v) => v.clone(),
+            _ => self
+                .to_sql_text()
+                .map_or(serde_json::Value::Null, Into::into),
+        }
+    }
+
+    // Literal usable in a SQL script
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            Value::Null => "NULL".to_string(),
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) if v.is_finite() => v.to_string(),
+            Value::Decimal(v) => v.to_string(),
+            Value::Bool(v) => v.to_string().to_uppercase(),
+            _ => {
+                let text = self.to_sql_text().unwrap_or_default();
+                format!("'{}'", text.replace('\'', "''"))
+            }
+        }
+    }
+
+    // Reads column `index` of a postgres row back into a value
+    pub fn decode(row: &PgRow, index: usize) -> Result<Value, sqlx::Error> {
+        let type_info = row.column(index).type_info();
+        // Enum labels travel as text but don't pass the type check of String
+        if let PgTypeKind::Enum(_) = type_info.kind() {
+            return Ok(row.try_get_unchecked::<Option<String>, _>(index)?.into());
+        }
+        let value = match type_info.name() {
+            "INT2" => row.try_get::<Option<i16>, _>(index)?.map(i64::from).into(),
+            "INT4" => row.try_get::<Option<i32>, _>(index)?.into(),
+            "INT8" => row.try_get::<Option<i64>, _>(index)?.into(),
+            "FLOAT4" => row.try_get::<Option<f32>, _>(index)?.map(f64::from).into(),
+            "FLOAT8" => row.try_get::<Option<f64>, _>(index)?.into(),
+            "NUMERIC" => row.try_get::<Option<Decimal>, _>(index)?.into(),
+            "BOOL" => row.try_get::<Option<bool>, _>(index)?.into(),
+            "DATE" => row.try_get::<Option<NaiveDate>, _>(index)?.into(),
+            "TIME" => row.try_get::<Option<NaiveTime>, _>(index)?.into(),
+            "TIMESTAMP" => row.try_get::<Option<NaiveDateTime>, _>(index)?.into(),
+            "UUID" => row.try_get::<Option<Uuid>, _>(index)?.into(),
+            "JSON" | "JSONB" => row.try_get::<Option<serde_json::Value>, _>(index)?.into(),
+            "BYTEA" => row.try_get::<Option<Vec<u8>>, _>(index)?.into(),
+            // Text and anything we don't model keeps its text form
+            _ => row.try_get::<Option<String>, _>(index)?.into(),
+        };
+        Ok(value)
+    }
+
+    // Reads column `index` of a mysql row back into a value
+    pub fn decode_mysql(row: &MySqlRow, index: usize) -> Result<Value, sqlx::Error> {
+        let value = match row.column(index).type_info().name() {
+            "BOOLEAN" => row.try_get::<Option<bool>, _>(index)?.into(),
+            "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+                row.try_get::<Option<i64>, _>(index)?.into()
+            }
+            name if name.ends_with(" UNSIGNED") => row
+                .try_get::<Option<u64>, _>(index)?
+                .map(|v| Value::Int(v as i64))
+                .into(),
+            "FLOAT" => row.try_get::<Option<f32>, _>(index)?.map(f64::from).into(),
+            "DOUBLE" => row.try_get::<Option<f64>, _>(index)?.into(),
+            "DECIMAL" => row.try_get::<Option<Decimal>, _>(index)?.into(),
+            "DATE" => row.try_get::<Option<NaiveDate>, _>(index)?.into(),
+            "TIME" => row.try_get::<Option<NaiveTime>, _>(index)?.into(),
+            "DATETIME" | "TIMESTAMP" => row.try_get::<Option<NaiveDateTime>, _>(index)?.into(),
+            "JSON" => row.try_get::<Option<serde_json::Value>, _>(index)?.into(),
+            "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+                row.try_get::<Option<Vec<u8>>, _>(index)?.into()
+            }
+            _ => row.try_get::<Option<String>, _>(index)?.into(),
+        };
+        Ok(value)
+    }
+
+    // Reads column `index` of a sqlite row back into a value. Sqlite keeps a type per
+    // value rather than per column, so the value is read as it was stored.
+    pub fn decode_sqlite(row: &SqliteRow, index: usize) -> Result<Value, sqlx::Error> {
+        let raw = row.try_get_raw(index)?;
+        if raw.is_null() {
+            return Ok(Value::Null);
+        }
+        let value = match raw.type_info().name() {
+            "INTEGER" => row.try_get::<i64, _>(index)?.into(),
+            "REAL" => row.try_get::<f64, _>(index)?.into(),
+            "BLOB" => row.try_get::<Vec<u8>, _>(index)?.into(),
+            _ => row.try_get::<String, _>(index)?.into(),
+        };
+        Ok(value)
+    }
+
+    // BSON value for a field of the given BSON type, see `Dialect::Mongodb`
+    pub fn into_bson(self, bson_type: &str) -> Bson {
+        match self {
+            Value::Null => Bson::Null,
+            Value::Int(v) => match i32::try_from(v) {
+                Ok(v) if bson_type == "int" => Bson::Int32(v),
+                _ => Bson::Int64(v),
+            },
+            Value::Float(v) => Bson::Double(v),
+            Value::Decimal(v) => match Decimal128::from_str(&v.to_string()) {
+                Ok(v) => Bson::Decimal128(v),
+                Err(_) => Bson::String(v.to_string()),
+            },
+            Value::Bool(v) => Bson::Boolean(v),
+            // Keys read back from a serial field are ObjectIds in their hex form
+            Value::Text(v) if bson_type == "objectId" => match ObjectId::parse_str(&v) {
+                Ok(id) => Bson::ObjectId(id),
+                Err(_) => Bson::String(v),
+            },
+            Value::Text(v) => Bson::String(v),
+            // Dates are kept as midnight UTC, BSON dates have millisecond precision
+            Value::Date(v) => bson_date(v.and_time(NaiveTime::MIN)),
+            Value::Time(v) => Bson::String(v.format("%H:%M:%S%.f").to_string()),
+            Value::Timestamp(v) => bson_date(v),
+            Value::Uuid(v) => bson::Uuid::from_bytes(v.into_bytes()).into(),
+            Value::Json(v) => Bson::try_from(v.clone()).unwrap_or_else(|_| v.to_string().into()),
+            Value::Bytes(v) => Bson::Binary(Binary {
+                subtype: bson::spec::BinarySubtype::Generic,
+                bytes: v,
+            }),
+        }
+    }
+
+    // Reads a BSON value back, ObjectIds become their hex form
+    pub fn from_bson(bson: Bson) -> Value {
+        match bson {
+            Bson::Null | Bson::Undefined => Value::Null,
+            Bson::Int32(v) => v.into(),
+            Bson::Int64(v) => v.into(),
+            Bson::Double(v) => v.into(),
+            Bson::Decimal128(v) => {
+                let text = v.to_string();
+                Decimal::from_str(&text)
+                    .or_else(|_| Decimal::from_scientific(&text))
+                    .map_or(Value::Text(text), Value::Decimal)
+            }
+            Bson::Boolean(v) => v.into(),
+            Bson::String(v) => v.into(),
+            Bson::DateTime(v) => DateTime::from_timestamp_millis(v.timestamp_millis())
+                .map(|v| v.naive_utc())
+                .into(),
+            Bson::ObjectId(v) => Value::Text(v.to_hex()),
+            Bson::Binary(v) => match v.to_uuid() {
+                Ok(uuid) => Value::Uuid(Uuid::from_bytes(uuid.bytes())),
+                Err(_) => Value::Bytes(v.bytes),
+            },
+            other => Value::Json(other.into_relaxed_extjson()),
+        }
+    }
+}
+
+// Years before 1 AD are written with a BC suffix, postgres has no year 0
+fn format_date(date: &NaiveDate) -> String {
+    if date.year() > 0 {
+        date.format("%Y-%m-%d").to_string()
+    } else {
+        format!(
+            "{:04}-{:02}-{:02} BC",
+            1 - date.year(),
+            date.month(),
+            date.day()
+        )
+    }
+}
+
+fn bson_date(timestamp: NaiveDateTime) -> Bson {
+    Bson::DateTime(bson::DateTime::from_millis(
+        timestamp.and_utc().timestamp_millis(),
+    ))
+}
+
+fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    if timestamp.year() > 0 {
+        timestamp.format("%Y-%m-%d %H:%M:%S%.f").to_string()
+    } else {
+        format!(
+            "{:04}-{} BC",
+            1 - timestamp.year(),
+            timestamp.format("%m-%d %H:%M:%S%.f")
+        )
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Int(v)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::Int(v as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(v: Decimal) -> Self {
+        Value::Decimal(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Text(v)
+    }
+}
+
+impl From<NaiveDate> for Value {
+    fn from(v: NaiveDate) -> Self {
+        Value::Date(v)
+    }
+}
+
+impl From<NaiveTime> for Value {
+    fn from(v: NaiveTime) -> Self {
+        Value::Time(v)
+    }
+}
+
+impl From<NaiveDateTime> for Value {
+    fn from(v: NaiveDateTime) -> Self {
+        Value::Timestamp(v)
+    }
+}
+
+impl From<Uuid> for Value {
+    fn from(v: Uuid) -> Self {
+        Value::Uuid(v)
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(v: serde_json::Value) -> Self {
+        Value::Json(v)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(v: Vec<u8>) -> Self {
+        Value::Bytes(v)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}